@@ -24,14 +24,14 @@ static TEXTS: &[&str] = &[
     "",
 ];
 
-fn make_line(md: &'static str, skin: &MadSkin, width: usize, align: Alignment) -> FmtComposite<'static> {
+fn make_line<'s>(md: &'s str, skin: &'s MadSkin, width: usize, align: Alignment) -> FmtComposite<'s> {
     let composite = Composite::from_inline(md);
     let mut fc = FmtComposite::from(composite, skin);
     fc.fill_width(width, align, skin);
     fc
 }
 
-fn make_all_lines(skin: &MadSkin, width: usize) -> Vec<FmtComposite<'static>> {
+fn make_all_lines(skin: &MadSkin, width: usize) -> Vec<FmtComposite<'_>> {
     let mut lines = Vec::new();
     lines.push(make_line("**Left align:**", skin, width, Alignment::Left));
     for md in TEXTS {
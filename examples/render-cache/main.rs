@@ -0,0 +1,51 @@
+//! A benchmark comparing the rendering of a big scrolled text,
+//! wrapping it at every frame, and with a MadView which keeps the
+//! wrapped lines between frames.
+//!
+//! run this example with
+//!   cargo run --release --example render-cache
+//!
+use std::time::Instant;
+use termimad::*;
+
+const FRAMES: usize = 200;
+
+fn main() -> Result<(), Error> {
+    let mut markdown = String::new();
+    for i in 0..500 {
+        markdown.push_str(&format!(
+            "## Section {}\nSome *styled* text with `code`, long enough to be wrapped on \
+            a few lines of the area, and **more text**.\n\n* an item\n* another item\n\n",
+            i,
+        ));
+    }
+    let area = Area::new(0, 0, 80, 40);
+    let skin = MadSkin::default();
+
+    // wrapping at every frame
+    let start = Instant::now();
+    let mut w = Vec::new();
+    for frame in 0..FRAMES {
+        let text = skin.area_text(&markdown, &area);
+        let mut text_view = TextView::from(&area, &text);
+        text_view.scroll = frame;
+        text_view.write_on(&mut w)?;
+        w.clear();
+    }
+    let uncached = start.elapsed();
+
+    // keeping the wrapped lines
+    let start = Instant::now();
+    let mut view = MadView::from(markdown, area, skin);
+    for _ in 0..FRAMES {
+        view.write_on(&mut w)?;
+        view.try_scroll_lines(1);
+        w.clear();
+    }
+    let cached = start.elapsed();
+
+    println!("{} frames", FRAMES);
+    println!("wrapping at every frame: {:?}", uncached);
+    println!("with the MadView cache:  {:?}", cached);
+    Ok(())
+}
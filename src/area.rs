@@ -9,10 +9,6 @@ const DEFAULT_TERMINAL_WIDTH: u16 = 50;
 /// A default height which is used when we failed measuring the real terminal width
 const DEFAULT_TERMINAL_HEIGHT: u16 = 20;
 
-pub trait AreaContent {
-    fn height() -> u16;
-}
-
 /// A rectangular part of the screen
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Area {
@@ -96,6 +92,88 @@ impl Area {
         self.width -= pw;
     }
 
+    /// build an area of the given size, centered in the screen
+    /// (or any other area), and never bigger than it
+    pub fn centered_in(screen: &Area, width: u16, height: u16) -> Area {
+        let width = width.min(screen.width);
+        let height = height.min(screen.height);
+        Area {
+            left: screen.left + (screen.width - width) / 2,
+            top: screen.top + (screen.height - height) / 2,
+            width,
+            height,
+        }
+    }
+
+    /// build an area centered in the screen (or any other area), whose
+    /// dimensions are the given percentages of the screen ones
+    pub fn from_percents(screen: &Area, w_pct: u16, h_pct: u16) -> Area {
+        let percent = |dim: u16, pct: u16| (u32::from(dim) * u32::from(pct.min(100)) / 100) as u16;
+        Self::centered_in(
+            screen,
+            percent(screen.width, w_pct),
+            percent(screen.height, h_pct),
+        )
+    }
+
+    /// return a copy of the area shrinked by a border of `dx`
+    /// columns on both sides and `dy` rows at top and bottom.
+    ///
+    /// Contrary to `pad`, this never fails: the returned area is
+    /// empty when the border is too big.
+    pub fn inset(&self, dx: u16, dy: u16) -> Area {
+        let dx = dx.min(self.width / 2);
+        let dy = dy.min(self.height / 2);
+        Area {
+            left: self.left + dx,
+            top: self.top + dy,
+            width: self.width - 2 * dx,
+            height: self.height - 2 * dy,
+        }
+    }
+
+    /// divide the width of the area according to the given weights,
+    /// returning areas side by side, from left to right.
+    ///
+    /// The remainder of the rounding goes to the last area.
+    pub fn split_horizontal(&self, ratios: &[u16]) -> Vec<Area> {
+        split_length(self.width, ratios)
+            .into_iter()
+            .map(|(offset, width)| Area::new(self.left + offset, self.top, width, self.height))
+            .collect()
+    }
+
+    /// divide the height of the area according to the given weights,
+    /// returning areas stacked from top to bottom.
+    ///
+    /// The remainder of the rounding goes to the last area.
+    pub fn split_vertical(&self, ratios: &[u16]) -> Vec<Area> {
+        split_length(self.height, ratios)
+            .into_iter()
+            .map(|(offset, height)| Area::new(self.left, self.top + offset, self.width, height))
+            .collect()
+    }
+
+    /// cut the area in a left part of width `x` (or less if the
+    /// area isn't wide enough) and a right part with the rest
+    pub fn split_at_column(&self, x: u16) -> (Area, Area) {
+        let x = x.min(self.width);
+        (
+            Area::new(self.left, self.top, x, self.height),
+            Area::new(self.left + x, self.top, self.width - x, self.height),
+        )
+    }
+
+    /// cut the area in a top part of height `y` (or less if the
+    /// area isn't high enough) and a bottom part with the rest
+    pub fn split_at_row(&self, y: u16) -> (Area, Area) {
+        let y = y.min(self.height);
+        (
+            Area::new(self.left, self.top, self.width, y),
+            Area::new(self.left, self.top + y, self.width, self.height - y),
+        )
+    }
+
     /// Return an option which when filled contains
     ///  a tupple with the top and bottom of the vertical
     ///  scrollbar. Return none when the content fits
@@ -111,6 +189,26 @@ impl Area {
     }
 }
 
+/// divide a length according to weights, returning the offset
+/// and length of every part
+fn split_length(length: u16, ratios: &[u16]) -> Vec<(u16, u16)> {
+    let total: u32 = ratios.iter().map(|&r| u32::from(r)).sum();
+    let mut parts = Vec::with_capacity(ratios.len());
+    let mut offset = 0;
+    for (idx, &ratio) in ratios.iter().enumerate() {
+        let part = if idx + 1 == ratios.len() {
+            length - offset
+        } else {
+            (u32::from(length) * u32::from(ratio))
+                .checked_div(total)
+                .map_or(0, |part| part as u16)
+        };
+        parts.push((offset, part));
+        offset += part;
+    }
+    parts
+}
+
 /// Compute the min and max y (from the top of the terminal, both inclusive)
 /// for the thumb part of the scrollbar which would represent the scrolled
 /// content in the available height.
@@ -161,3 +259,44 @@ pub fn terminal_size() -> (u16, u16) {
     let size = terminal::size();
     size.unwrap_or((DEFAULT_TERMINAL_WIDTH, DEFAULT_TERMINAL_HEIGHT))
 }
+
+#[cfg(test)]
+mod area_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_sub_areas() {
+        let screen = Area::new(0, 0, 100, 40);
+        assert_eq!(Area::centered_in(&screen, 20, 10), Area::new(40, 15, 20, 10));
+        assert_eq!(Area::centered_in(&screen, 200, 10), Area::new(0, 15, 100, 10));
+        assert_eq!(Area::from_percents(&screen, 50, 50), Area::new(25, 10, 50, 20));
+        assert_eq!(Area::from_percents(&screen, 100, 150), screen);
+        let area = Area::new(10, 5, 20, 10);
+        assert_eq!(area.inset(2, 1), Area::new(12, 6, 16, 8));
+        assert_eq!(area.inset(30, 30), Area::new(20, 10, 0, 0));
+    }
+
+    #[test]
+    fn test_splits() {
+        let area = Area::new(10, 5, 20, 10);
+        assert_eq!(
+            area.split_horizontal(&[1, 1, 1]),
+            vec![Area::new(10, 5, 6, 10), Area::new(16, 5, 6, 10), Area::new(22, 5, 8, 10)],
+        );
+        assert_eq!(
+            area.split_vertical(&[1, 4]),
+            vec![Area::new(10, 5, 20, 2), Area::new(10, 7, 20, 8)],
+        );
+        assert_eq!(area.split_vertical(&[]), vec![]);
+        assert_eq!(area.split_vertical(&[0, 0]), vec![Area::new(10, 5, 20, 0), area.clone()]);
+        assert_eq!(
+            area.split_at_column(5),
+            (Area::new(10, 5, 5, 10), Area::new(15, 5, 15, 10)),
+        );
+        assert_eq!(
+            area.split_at_row(50),
+            (area.clone(), Area::new(10, 15, 20, 0)),
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use {
+    crate::skin::MadSkin,
+    minimad::{Composite, CompositeStyle, Line},
+};
+
+/// The numbers of blank lines before and after a kind of block
+/// (headers, paragraphs, code blocks or lists)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BlockSpacing {
+    pub before: usize,
+    pub after: usize,
+}
+
+impl BlockSpacing {
+    pub const fn new(before: usize, after: usize) -> Self {
+        Self { before, after }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Paragraph,
+    Header,
+    Code,
+    List,
+    /// a quote, a table, a rule, etc.
+    Other,
+}
+
+impl BlockKind {
+    /// Return the kind of block the line is part of, or None
+    /// if it's a blank line
+    fn of(line: &Line<'_>) -> Option<Self> {
+        let composite = match line {
+            Line::Normal(composite) => composite,
+            _ => { return Some(Self::Other); }
+        };
+        match composite.style {
+            CompositeStyle::Paragraph => {
+                if composite.compounds.iter().all(|c| c.src.trim().is_empty()) {
+                    None
+                } else {
+                    Some(Self::Paragraph)
+                }
+            }
+            CompositeStyle::Header(_) => Some(Self::Header),
+            CompositeStyle::Code => Some(Self::Code),
+            CompositeStyle::ListItem => Some(Self::List),
+            CompositeStyle::Quote => Some(Self::Other),
+        }
+    }
+    fn spacing(self, skin: &MadSkin) -> Option<BlockSpacing> {
+        match self {
+            Self::Paragraph => skin.paragraph_spacing,
+            Self::Header => skin.headers_spacing,
+            Self::Code => skin.code_block_spacing,
+            Self::List => skin.list_spacing,
+            Self::Other => None,
+        }
+    }
+}
+
+/// Changes the numbers of blank lines between blocks according
+/// to the spacings of the skin.
+///
+/// Between two blocks, there are as many blank lines as the biggest
+/// of the spacing after the first one and the spacing before the
+/// second one. When none of them is defined, the blank lines of the
+/// markdown are kept, as are the ones at the start and end of the text.
+///
+/// Lines can be given in several times (for example block after block)
+/// as long as `finish` is called after the last ones.
+pub(crate) struct BlockSpacer<'s> {
+    last_kind: Option<BlockKind>,
+    blank_lines: Vec<Line<'s>>,
+}
+
+impl<'s> BlockSpacer<'s> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            last_kind: None,
+            blank_lines: Vec::new(),
+        }
+    }
+    /// Return the lines with the blank lines between blocks fixed,
+    /// the ones which may be at the end being kept for later.
+    pub(crate) fn space_lines(&mut self, skin: &MadSkin, lines: Vec<Line<'s>>) -> Vec<Line<'s>> {
+        let mut spaced_lines = Vec::with_capacity(lines.len());
+        for line in lines {
+            let kind = match BlockKind::of(&line) {
+                Some(kind) => kind,
+                None => {
+                    self.blank_lines.push(line);
+                    continue;
+                }
+            };
+            let blank_count = match self.last_kind {
+                Some(last_kind) => {
+                    let same_block = last_kind == kind
+                        && kind != BlockKind::Header
+                        && self.blank_lines.is_empty();
+                    let spacings = (last_kind.spacing(skin), kind.spacing(skin));
+                    match spacings {
+                        _ if same_block => 0,
+                        (None, None) => self.blank_lines.len(),
+                        (Some(last), None) => last.after,
+                        (None, Some(next)) => next.before,
+                        (Some(last), Some(next)) => last.after.max(next.before),
+                    }
+                }
+                None => self.blank_lines.len(), // start of the text
+            };
+            let mut blank_lines = std::mem::take(&mut self.blank_lines);
+            blank_lines.resize_with(blank_count, || Line::Normal(Composite::new()));
+            spaced_lines.append(&mut blank_lines);
+            spaced_lines.push(line);
+            self.last_kind = Some(kind);
+        }
+        spaced_lines
+    }
+    /// Return the blank lines of the end of the text
+    pub(crate) fn finish(&mut self) -> Vec<Line<'s>> {
+        std::mem::take(&mut self.blank_lines)
+    }
+}
+
+#[cfg(test)]
+mod block_spacing_tests {
+
+    use crate::*;
+
+    #[test]
+    fn check_block_spacing() {
+        let md = "# Title\nsome text\nmore text\n\n\n* a\n* b\n\n```\ncode\n\ncode\n```\nend\n";
+        let mut skin = MadSkin::no_style();
+        skin.headers[0].align = Alignment::Left;
+        skin.code_block.align = Alignment::Left;
+        let rendered = |skin: &MadSkin| skin.text(md, None).to_plain_string();
+        assert_eq!(
+            rendered(&skin),
+            "Title\nsome text\nmore text\n\n\n• a\n• b\n\ncode\n    \ncode\nend\n",
+        );
+        skin.set_headers_spacing(0, 1);
+        skin.set_list_spacing(1, 1);
+        skin.set_code_block_spacing(0, 0);
+        assert_eq!(
+            rendered(&skin),
+            "Title\n\nsome text\nmore text\n\n• a\n• b\n\ncode\n    \ncode\nend\n",
+        );
+        skin.set_paragraph_spacing(0, 0);
+        skin.set_list_spacing(0, 0);
+        assert_eq!(
+            rendered(&skin),
+            "Title\n\nsome text\nmore text\n• a\n• b\ncode\n    \ncode\nend\n",
+        );
+        // the number of lines of a text is computed with the spacing
+        assert_eq!(FmtText::line_count_for_width(&skin, md, 20), 10);
+    }
+}
@@ -0,0 +1,110 @@
+use {
+    crate::composite::KindedCompound,
+    minimad::Compound,
+};
+
+/// The soft hyphen, which is displayed only when a line
+/// is wrapped at its position
+static SOFT_HYPHEN: &str = "\u{ad}";
+
+/// What's displayed at the end of a line wrapped at a soft hyphen
+static HYPHEN: &str = "-";
+
+pub(crate) fn is_soft_hyphen(compound: &Compound<'_>) -> bool {
+    std::ptr::eq(compound.src, SOFT_HYPHEN)
+}
+
+/// Return the compound to display at the end of a line wrapped
+/// at the given soft hyphen
+pub(crate) fn hyphen<'s>(soft_hyphen: &Compound<'s>) -> Compound<'s> {
+    Compound {
+        src: HYPHEN,
+        ..soft_hyphen.clone()
+    }
+}
+
+/// Remove the marks of a hard line break at the end of the
+/// composite: two spaces or a backslash.
+///
+/// As every line of the markdown is displayed on its own line,
+/// they would only make the line wider.
+pub(crate) fn remove_hard_break_mark(compounds: &mut Vec<KindedCompound<'_>>) {
+    let last = match compounds.last_mut() {
+        Some((last, _)) => last,
+        None => { return; }
+    };
+    if let Some(src) = last.src.strip_suffix('\\') {
+        last.set_str(src);
+    } else if last.src.ends_with("  ") {
+        last.set_str(last.src.trim_end_matches(' '));
+    } else {
+        return;
+    }
+    if last.is_empty() {
+        compounds.pop();
+    }
+}
+
+/// Put the soft hyphens in their own compounds, so that they can
+/// be recognized when wrapping and not displayed when not needed.
+pub(crate) fn isolate_soft_hyphens(compounds: &mut Vec<KindedCompound<'_>>) {
+    if !compounds.iter().any(|(c, _)| !c.code && c.src.contains(SOFT_HYPHEN)) {
+        return;
+    }
+    let src_compounds = std::mem::take(compounds);
+    for (mut compound, kinds) in src_compounds {
+        if compound.code {
+            compounds.push((compound, kinds));
+            continue;
+        }
+        while let Some(idx) = compound.src.find(SOFT_HYPHEN) {
+            if idx > 0 {
+                compounds.push((compound.sub(0, idx), kinds.clone()));
+            }
+            compounds.push((
+                Compound {
+                    src: SOFT_HYPHEN,
+                    ..compound.clone()
+                },
+                kinds.clone(),
+            ));
+            compound = compound.tail(idx + SOFT_HYPHEN.len());
+        }
+        if !compound.is_empty() {
+            compounds.push((compound, kinds));
+        }
+    }
+}
+
+#[cfg(test)]
+mod breaks_tests {
+
+    use crate::*;
+
+    #[test]
+    fn check_hard_breaks() {
+        let mut skin = MadSkin::no_style();
+        skin.paragraph.align = Alignment::Right;
+        let md = "first line  \nsecond\\\n**third**  \nlast";
+        assert_eq!(
+            skin.text(md, Some(12)).to_string(),
+            "  first line\n      second\n       third\n        last\n",
+        );
+    }
+
+    #[test]
+    fn check_soft_hyphens() {
+        let skin = MadSkin::no_style();
+        let md = "an incom\u{ad}pre\u{ad}hensible word\nshort hy\u{ad}phen\\\nnext";
+        // the soft hyphens are displayed only where lines are wrapped
+        assert_eq!(
+            skin.text(md, Some(15)).to_plain_string(),
+            "an incompre-\nhensible word\nshort hyphen\nnext\n",
+        );
+        assert_eq!(
+            skin.text(md, Some(10)).to_plain_string(),
+            "an incom-\npre-\nhensible \nword\nshort hy-\nphen\nnext\n",
+        );
+        assert_eq!(skin.visible_width("hy\u{ad}phen"), 6);
+    }
+}
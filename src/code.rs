@@ -15,9 +15,9 @@ pub struct CodeBlock {
 }
 impl CodeBlock {
     /// ensure all lines of the block have the same width
-    pub fn justify(&self, lines: &mut Vec<FmtLine<'_>>) {
-        for idx in self.start..self.start + self.height {
-            if let FmtLine::Normal(ref mut fc) = lines[idx] {
+    pub fn justify(&self, lines: &mut [FmtLine<'_>]) {
+        for line in &mut lines[self.start..self.start + self.height] {
+            if let FmtLine::Normal(ref mut fc) = line {
                 fc.spacing = Some(Spacing {
                     width: self.width,
                     align: Alignment::Left,
@@ -27,12 +27,9 @@ impl CodeBlock {
     }
 }
 
-const fn code_line_length(line: &FmtLine<'_>) -> Option<usize> {
+fn styled_line_length(line: &FmtLine<'_>, style: CompositeStyle) -> Option<usize> {
     match line {
-        FmtLine::Normal(fc) => match fc.composite.style {
-            CompositeStyle::Code => Some(fc.visible_length),
-            _ => None,
-        },
+        FmtLine::Normal(fc) if fc.composite.style == style => Some(fc.visible_length),
         _ => None,
     }
 }
@@ -43,10 +40,15 @@ const fn code_line_length(line: &FmtLine<'_>) -> Option<usize> {
 /// soon as lines are inserted or removed. This function
 /// should normally not be used from another module or lib
 pub fn find_blocks(lines: &[FmtLine<'_>]) -> Vec<CodeBlock> {
+    find_blocks_of_style(lines, CompositeStyle::Code)
+}
+
+/// find ranges of consecutive lines of the given style
+pub fn find_blocks_of_style(lines: &[FmtLine<'_>], style: CompositeStyle) -> Vec<CodeBlock> {
     let mut blocks: Vec<CodeBlock> = Vec::new();
     let mut current: Option<CodeBlock> = None;
     for (idx, line) in lines.iter().enumerate() {
-        if let Some(ll) = code_line_length(line) {
+        if let Some(ll) = styled_line_length(line, style) {
             match current.as_mut() {
                 Some(b) => {
                     b.height += 1;
@@ -72,9 +74,18 @@ pub fn find_blocks(lines: &[FmtLine<'_>]) -> Vec<CodeBlock> {
 
 /// ensure the widths of all lines in a code block are
 /// the same line.
-pub fn justify_blocks(lines: &mut Vec<FmtLine<'_>>) {
+pub fn justify_blocks(lines: &mut [FmtLine<'_>]) {
     let blocks = find_blocks(lines);
     for b in blocks {
         b.justify(lines);
     }
 }
+
+/// ensure the widths of all lines in a block quote are the
+/// same, so that the block is aligned as a unit
+pub fn justify_quote_blocks(lines: &mut [FmtLine<'_>]) {
+    let blocks = find_blocks_of_style(lines, CompositeStyle::Quote);
+    for b in blocks {
+        b.justify(lines);
+    }
+}
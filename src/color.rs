@@ -1,4 +1,7 @@
-use crossterm::style::Color;
+use {
+    crossterm::style::Color,
+    std::convert::TryFrom,
+};
 
 /// Build a RGB color
 ///
@@ -20,6 +23,56 @@ pub const fn ansi(level: u8) -> Color {
     Color::AnsiValue(level)
 }
 
+/// Parse a color written like `red`, `dark_grey`, `#ffbb00`,
+/// `rgb(255,187,0)`, `gray(10)` or `ansi(178)`
+pub(crate) fn parse_color(s: &str) -> Option<Color> {
+    let s = s.to_lowercase();
+    if let Some(hex) = s.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(rgb(component(0)?, component(2)?, component(4)?));
+    }
+    if let Some(args) = s.strip_suffix(')') {
+        let (name, args) = args.split_once('(')?;
+        let args: Vec<u8> = args
+            .split(',')
+            .map(|arg| arg.trim().parse())
+            .collect::<Result<_, _>>()
+            .ok()?;
+        return match (name, args.as_slice()) {
+            ("rgb", &[r, g, b]) => Some(rgb(r, g, b)),
+            ("gray" | "grey", &[level]) if level < 24 => Some(gray(level)),
+            ("ansi", &[level]) => Some(ansi(level)),
+            _ => None,
+        };
+    }
+    let name = s.replace("gray", "grey");
+    Color::try_from(name.as_str()).ok()
+}
+
+/// Tell whether the background is light according to the value
+/// of a `COLORFGBG` environment variable (like `15;0` or
+/// `0;default;15`, the last part being the ANSI background color).
+///
+/// Return `None` when the value can't be understood.
+pub(crate) fn is_light_colorfgbg(value: &str) -> Option<bool> {
+    let bg: u8 = value.rsplit(';').next()?.trim().parse().ok()?;
+    match bg {
+        0..=6 | 8 => Some(false),
+        7 | 9..=15 => Some(true),
+        _ => None,
+    }
+}
+
+/// Tell whether the background of the terminal looks light, if
+/// there's a way to know it
+pub(crate) fn terminal_has_light_background() -> Option<bool> {
+    let value = std::env::var("COLORFGBG").ok()?;
+    is_light_colorfgbg(&value)
+}
+
 #[cfg(test)]
 mod color_tests {
 
@@ -32,4 +85,28 @@ mod color_tests {
     fn check_gray_panic() {
         let _ = gray(24);
     }
+
+    #[test]
+    fn check_color_parsing() {
+        assert_eq!(parse_color("Red"), Some(Color::Red));
+        assert_eq!(parse_color("dark_gray"), Some(Color::DarkGrey));
+        assert_eq!(parse_color("#20a0FF"), Some(rgb(32, 160, 255)));
+        assert_eq!(parse_color("rgb(1,2,3)"), Some(rgb(1, 2, 3)));
+        assert_eq!(parse_color("gray(10)"), Some(gray(10)));
+        assert_eq!(parse_color("ansi(178)"), Some(ansi(178)));
+        assert_eq!(parse_color("gray(24)"), None);
+        assert_eq!(parse_color("#12345"), None);
+        assert_eq!(parse_color("rgb(1,2)"), None);
+        assert_eq!(parse_color("purple"), None);
+    }
+
+    #[test]
+    fn check_colorfgbg() {
+        assert_eq!(is_light_colorfgbg("15;0"), Some(false));
+        assert_eq!(is_light_colorfgbg("0;15"), Some(true));
+        assert_eq!(is_light_colorfgbg("0;default;7"), Some(true));
+        assert_eq!(is_light_colorfgbg("7;8"), Some(false));
+        assert_eq!(is_light_colorfgbg("0;default"), None);
+        assert_eq!(is_light_colorfgbg(""), None);
+    }
 }
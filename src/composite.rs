@@ -4,12 +4,43 @@ use {
         Alignment,
         MadSkin,
         Spacing,
+        FitMode,
         Fitter,
+        breaks,
+        entities,
+        image,
+        link::{self, FmtLink},
+        list::ListItemMark,
     },
-    minimad::{Composite, Compound},
+    minimad::{Composite, CompositeStyle, Compound},
+    std::borrow::Cow,
     unicode_width::UnicodeWidthStr,
 };
 
+/// What a compound added by termimad, and not coming from the
+/// markdown, stands for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CompoundKind {
+    /// the marker of a reference to a footnote
+    FootnoteRef,
+    /// the marker starting the definition of a footnote
+    FootnoteDef,
+    /// the string replacing a part removed when fitting
+    Ellipsis,
+    /// a part of the text of the link at this index in `links`
+    Link(usize),
+    /// a decoded html entity, whose char isn't the source of the
+    /// compound (which is only a string of the same width)
+    Char(char),
+    /// a part of what's displayed for an image
+    Image,
+    /// the mark prefixing a header (see `MadSkin::set_header_mark`)
+    HeaderMark,
+}
+
+/// A compound with the kinds it was given by termimad
+pub(crate) type KindedCompound<'s> = (Compound<'s>, Vec<CompoundKind>);
+
 /// Wrap a Minimad Composite, which is a list of Compounds
 /// (which are strings with an homogeneous style)
 #[derive(Debug, Clone)]
@@ -17,6 +48,18 @@ pub struct FmtComposite<'s> {
     pub composite: Composite<'s>,
     pub visible_length: usize, // to avoid recomputing it again and again
     pub spacing: Option<Spacing>,
+    /// the links whose text is in the composite
+    pub links: Vec<FmtLink<'s>>,
+    /// what replaces the bullet of a list item, if not the standard one
+    pub list_mark: Option<ListItemMark>,
+    /// the nesting depth of a quote (0 if the composite isn't a quote)
+    pub quote_depth: usize,
+    /// whether the composite is the continuation, after
+    /// wrapping, of the previous line
+    pub continuation: bool,
+    /// the indexes of the compounds added or modified by termimad,
+    /// with what they stand for (a compound may have several kinds)
+    pub(crate) kinds: Vec<(usize, CompoundKind)>,
 }
 
 impl<'s> FmtComposite<'s> {
@@ -25,20 +68,101 @@ impl<'s> FmtComposite<'s> {
             composite: Composite::new(),
             visible_length: 0,
             spacing: None,
+            links: Vec::new(),
+            list_mark: None,
+            quote_depth: 0,
+            continuation: false,
+            kinds: Vec::new(),
         }
     }
-    pub fn from(composite: Composite<'s>, skin: &MadSkin) -> Self {
-        FmtComposite {
-            visible_length: skin.visible_composite_length(&composite),
+    pub fn from(mut composite: Composite<'s>, skin: &MadSkin) -> Self {
+        #[cfg(feature="emoji")]
+        if skin.emoji_shortcodes && !composite.is_code() {
+            crate::emoji::expand_shortcodes(&mut composite.compounds);
+        }
+        let mut compounds: Vec<KindedCompound<'s>> = composite.compounds
+            .drain(..)
+            .map(|compound| (compound, Vec::new()))
+            .collect();
+        if !composite.is_code() && skin.render_options.allow_images {
+            image::extract_images(&mut compounds, skin.image_mode);
+        }
+        let mut links = if composite.is_code() {
+            Vec::new()
+        } else {
+            link::extract_links(&mut compounds, skin.hyperlinks)
+        };
+        // after the links, so that their urls aren't split
+        if skin.html_entities && skin.render_options.allow_html && !composite.is_code() {
+            entities::decode_entities(&mut compounds);
+        }
+        if !composite.is_code() {
+            breaks::remove_hard_break_mark(&mut compounds);
+            breaks::isolate_soft_hyphens(&mut compounds);
+        }
+        if let CompositeStyle::Header(level) = composite.style {
+            let mark = skin.header_marks.get(level as usize - 1).copied().unwrap_or_default();
+            if !mark.is_empty() {
+                compounds.insert(0, (Compound::raw_str(mark), vec![CompoundKind::HeaderMark]));
+            }
+        }
+        let quote_depth = usize::from(composite.is_quote());
+        let mut fc = FmtComposite {
+            visible_length: 0,
             composite,
             spacing: None,
+            links: Vec::new(),
+            list_mark: None,
+            quote_depth,
+            continuation: false,
+            kinds: Vec::new(),
+        };
+        fc.set_compounds(compounds);
+        fc.visible_length = skin.visible_composite_length(&fc.composite);
+        // the texts of the links are the decoded ones
+        for idx in 0..fc.composite.compounds.len() {
+            if let Some(link_idx) = fc.link_idx(idx) {
+                links[link_idx].push_text(&fc.compound_text(idx));
+            }
         }
+        fc.links = links;
+        fc
     }
     pub fn from_compound(compound: Compound<'s>) -> Self {
         let mut fc = Self::new();
         fc.add_compound(compound);
         fc
     }
+    /// Return the index in `links` of the link the compound at the
+    /// given index is part of, if any
+    pub(crate) fn link_idx(&self, idx: usize) -> Option<usize> {
+        self.kinds.iter().find_map(|&(kind_idx, kind)| match kind {
+            CompoundKind::Link(link_idx) if kind_idx == idx => Some(link_idx),
+            _ => None,
+        })
+    }
+    /// Return the url of the link the compound at the given
+    /// index is part of, if any
+    pub fn link_url(&self, idx: usize) -> Option<&'s str> {
+        self.link_idx(idx).map(|link_idx| self.links[link_idx].url)
+    }
+    /// Return the text displayed for the compound at the given
+    /// index, which is its source unless it stands for a char
+    pub(crate) fn compound_text(&self, idx: usize) -> Cow<'s, str> {
+        let char = self.kinds.iter().find_map(|&(kind_idx, kind)| match kind {
+            CompoundKind::Char(c) if kind_idx == idx => Some(c),
+            _ => None,
+        });
+        match char {
+            Some(c) => Cow::Owned(c.to_string()),
+            None => Cow::Borrowed(self.composite.compounds[idx].src),
+        }
+    }
+    /// Tell whether the compound at the given index is part
+    /// of what's displayed for an image
+    pub fn is_image_part(&self, idx: usize) -> bool {
+        self.has_kind(idx, CompoundKind::Image)
+    }
     /// Return the number of characters (usually spaces) to insert both
     /// sides of the composite
     #[inline(always)]
@@ -54,13 +178,117 @@ impl<'s> FmtComposite<'s> {
         self.visible_length += compound.src.width();
         self.composite.compounds.push(compound);
     }
+    /// Add a compound which may have been given kinds by termimad,
+    /// and modifies `visible_length` accordingly
+    pub(crate) fn add_compound_of_kinds(
+        &mut self,
+        compound: Compound<'s>,
+        kinds: Vec<CompoundKind>,
+    ) {
+        let idx = self.composite.compounds.len();
+        self.kinds.extend(kinds.into_iter().map(|kind| (idx, kind)));
+        self.add_compound(compound);
+    }
+    /// Return what the compound at the given index stands for, if
+    /// it was added or modified by termimad
+    pub(crate) fn compound_kinds(&self, idx: usize) -> Vec<CompoundKind> {
+        self.kinds
+            .iter()
+            .filter(|&&(kind_idx, _)| kind_idx == idx)
+            .map(|&(_, kind)| kind)
+            .collect()
+    }
+    /// Tell whether the compound at the given index has the kind
+    pub(crate) fn has_kind(&self, idx: usize, kind: CompoundKind) -> bool {
+        self.kinds.contains(&(idx, kind))
+    }
+    /// Insert a compound at the given index, keeping the kinds of
+    /// the other compounds (`visible_length` isn't modified)
+    pub(crate) fn insert_compound(
+        &mut self,
+        idx: usize,
+        compound: Compound<'s>,
+        kinds: Vec<CompoundKind>,
+    ) {
+        for (kind_idx, _) in &mut self.kinds {
+            if *kind_idx >= idx {
+                *kind_idx += 1;
+            }
+        }
+        self.kinds.extend(kinds.into_iter().map(|kind| (idx, kind)));
+        self.composite.compounds.insert(idx, compound);
+    }
+    /// Remove the compound at the given index, keeping the kinds of
+    /// the other compounds (`visible_length` isn't modified)
+    pub(crate) fn remove_compound(&mut self, idx: usize) -> Compound<'s> {
+        self.kinds.retain(|&(kind_idx, _)| kind_idx != idx);
+        for (kind_idx, _) in &mut self.kinds {
+            if *kind_idx > idx {
+                *kind_idx -= 1;
+            }
+        }
+        self.composite.compounds.remove(idx)
+    }
+    /// Remove all the compounds and return them with their
+    /// kinds (`visible_length` isn't modified)
+    pub(crate) fn take_compounds(&mut self) -> Vec<KindedCompound<'s>> {
+        let compounds = std::mem::take(&mut self.composite.compounds);
+        let kinds: Vec<Vec<CompoundKind>> = (0..compounds.len())
+            .map(|idx| self.compound_kinds(idx))
+            .collect();
+        self.kinds.clear();
+        compounds.into_iter().zip(kinds).collect()
+    }
+    /// Replace the compounds with the given ones, with their
+    /// kinds (`visible_length` isn't modified)
+    pub(crate) fn set_compounds(&mut self, compounds: Vec<KindedCompound<'s>>) {
+        self.kinds.clear();
+        self.composite.compounds.clear();
+        for (idx, (compound, kinds)) in compounds.into_iter().enumerate() {
+            self.kinds.extend(kinds.into_iter().map(|kind| (idx, kind)));
+            self.composite.compounds.push(compound);
+        }
+    }
     /// Ensure the cached visible_length is correct.
     ///
     /// It's normally not necessary to call it, but
     /// this must be called if compounds are added,
     /// removed or modified without using the FmtComposite API
     pub fn recompute_width(&mut self, skin: &MadSkin) {
-        self.visible_length = skin.visible_composite_length(&self.composite);
+        self.visible_length = skin.visible_composite_length(&self.composite)
+            + self.list_mark_extra_width()
+            + self.quote_extra_width();
+    }
+    /// Set the mark replacing the bullet of a list item, modifying
+    /// `visible_length` accordingly
+    pub fn set_list_mark(&mut self, list_mark: Option<ListItemMark>) {
+        self.visible_length -= self.list_mark_extra_width();
+        self.list_mark = list_mark;
+        self.visible_length += self.list_mark_extra_width();
+    }
+    /// Return the width the list mark takes in addition to the
+    /// one of a standard bullet
+    pub(crate) fn list_mark_extra_width(&self) -> usize {
+        match self.list_mark {
+            Some(mark) if self.composite.is_list_item() => mark.width() - 2,
+            _ => 0,
+        }
+    }
+    /// Set the nesting depth of a quote, modifying `visible_length`
+    /// accordingly
+    pub fn set_quote_depth(&mut self, quote_depth: usize) {
+        self.visible_length -= self.quote_extra_width();
+        self.quote_depth = quote_depth;
+        self.visible_length += self.quote_extra_width();
+    }
+    /// Return the width the quote marks of the nesting levels take
+    /// in addition to the first one
+    pub(crate) fn quote_extra_width(&self) -> usize {
+        if self.composite.is_quote() {
+            2 * self.quote_depth.saturating_sub(1)
+        } else {
+            0
+        }
     }
     /// try to ensure the composite's width doesn't exceed the given
     /// width.
@@ -70,9 +298,14 @@ impl<'s> FmtComposite<'s> {
     /// right).
     /// The fitter may remove a part in the core of the composite if it looks
     /// good enough. In this specific case an ellipsis will replace the removed part.
-    pub fn fit_width(&mut self, width: usize, align: Alignment, skin: &MadSkin) {
+    pub fn fit_width(&mut self, width: usize, align: Alignment, skin: &'s MadSkin) {
         Fitter::for_align(align).fit(self, width, skin);
     }
+    /// try to ensure the composite's width doesn't exceed the given
+    /// width, always removing the part given by the mode.
+    pub fn fit_width_with_mode(&mut self, width: usize, mode: FitMode, skin: &'s MadSkin) {
+        Fitter::for_mode(mode).fit(self, width, skin);
+    }
     /// if the composite is smaller than the given width, pad it
     /// according to the alignment.
     pub fn extend_width(&mut self, width: usize, align: Alignment) {
@@ -89,9 +322,9 @@ impl<'s> FmtComposite<'s> {
     /// either by shortening it or by adding space.
     ///
     /// This calls the `fit_width` and `extend_width` methods.
-    pub fn fill_width(&mut self, width: usize, align: Alignment, skin: &MadSkin) {
+    pub fn fill_width(&mut self, width: usize, align: Alignment, skin: &'s MadSkin) {
         self.fit_width(width, align, skin);
-        self.extend_width(width, align);
+        self.extend_width(width, skin.directed_align(align));
     }
 }
 
@@ -1,5 +1,9 @@
 use {
-    crate::{errors::Result, styled_char::StyledChar},
+    crate::{
+        color::{ansi, parse_color, rgb},
+        errors::{Error, Result},
+        styled_char::StyledChar,
+    },
     crossterm::{
         QueueableCommand,
         style::{
@@ -14,7 +18,10 @@ use {
         },
         terminal::{Clear, ClearType},
     },
-    std::fmt::{self, Display},
+    std::{
+        fmt::{self, Display},
+        str::FromStr,
+    },
 };
 
 /// A style which may be applied to a compound
@@ -29,6 +36,58 @@ impl From<ContentStyle> for CompoundStyle {
     }
 }
 
+impl FromStr for CompoundStyle {
+    type Err = Error;
+    /// Parse a style written like `"yellow bold on #202020"` or
+    /// `"gray(10) italic"`: a foreground color, a background color after
+    /// `on`, and attributes (bold, italic, underline, dim, reverse, crossed),
+    /// all optional and in any order
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut style = CompoundStyle::default();
+        // whether the previous token was 'on', so that a color is expected
+        let mut background = false;
+        for token in s.split_whitespace() {
+            let attr = match token.to_lowercase().as_str() {
+                "on" => {
+                    if background || style.get_bg().is_some() {
+                        return Err(Error::InvalidStyle("unexpected 'on'".to_string()));
+                    }
+                    background = true;
+                    continue;
+                }
+                "bold" => Attribute::Bold,
+                "italic" => Attribute::Italic,
+                "underline" | "underlined" => Attribute::Underlined,
+                "dim" => Attribute::Dim,
+                "reverse" | "reversed" => Attribute::Reverse,
+                "crossed" | "crossedout" | "strikeout" => Attribute::CrossedOut,
+                _ => {
+                    let color = parse_color(token).ok_or_else(|| {
+                        Error::InvalidStyle(format!("unrecognized token {:?}", token))
+                    })?;
+                    if background {
+                        style.set_bg(color);
+                        background = false;
+                    } else if style.get_fg().is_some() {
+                        return Err(Error::InvalidStyle(format!("unexpected second color {:?}", token)));
+                    } else {
+                        style.set_fg(color);
+                    }
+                    continue;
+                }
+            };
+            if background {
+                return Err(Error::InvalidStyle(format!("expected a color after 'on', got {:?}", token)));
+            }
+            style.add_attr(attr);
+        }
+        if background {
+            return Err(Error::InvalidStyle("missing color after 'on'".to_string()));
+        }
+        Ok(style)
+    }
+}
+
 impl CompoundStyle {
     /// Apply an `StyledContent` to the passed displayable object.
     pub fn apply_to<D>(&self, val: D) -> StyledContent<D>
@@ -86,6 +145,22 @@ impl CompoundStyle {
         }
     }
 
+    /// Get an new instance of `CompoundStyle` with a RGB foreground color
+    pub fn with_fg_rgb(r: u8, g: u8, b: u8) -> CompoundStyle {
+        Self::with_fg(rgb(r, g, b))
+    }
+
+    /// Get an new instance of `CompoundStyle` with a RGB background color
+    pub fn with_bg_rgb(r: u8, g: u8, b: u8) -> CompoundStyle {
+        Self::with_bg(rgb(r, g, b))
+    }
+
+    /// Get an new instance of `CompoundStyle` with an ANSI (256 colors)
+    /// foreground color
+    pub fn with_ansi(fg: u8) -> CompoundStyle {
+        Self::with_fg(ansi(fg))
+    }
+
     /// Get an new instance of `CompoundStyle`
     pub fn with_attr(attr: Attribute) -> CompoundStyle {
         let mut cp = CompoundStyle::default();
@@ -103,6 +178,16 @@ impl CompoundStyle {
         self.object_style.background_color = Some(color);
     }
 
+    /// Set the foreground color to the passed RGB one.
+    pub fn set_fg_rgb(&mut self, r: u8, g: u8, b: u8) {
+        self.set_fg(rgb(r, g, b));
+    }
+
+    /// Set the background color to the passed RGB one.
+    pub fn set_bg_rgb(&mut self, r: u8, g: u8, b: u8) {
+        self.set_bg(rgb(r, g, b));
+    }
+
     /// Set the colors to the passed ones
     pub fn set_fgbg(&mut self, fg: Color, bg: Color) {
         self.object_style.foreground_color = Some(fg);
@@ -119,6 +204,57 @@ impl CompoundStyle {
         self.object_style.attributes.unset(attr);
     }
 
+    /// Add the `Blink` attribute (`SlowBlink` in crossterm)
+    pub fn set_blink(&mut self) {
+        self.add_attr(Attribute::SlowBlink);
+    }
+
+    /// Remove the `Blink` attribute (`SlowBlink` in crossterm)
+    pub fn remove_blink(&mut self) {
+        self.remove_attr(Attribute::SlowBlink);
+    }
+
+    /// Add the `Hidden` attribute, concealing the text
+    pub fn set_conceal(&mut self) {
+        self.add_attr(Attribute::Hidden);
+    }
+
+    /// Remove the `Hidden` attribute
+    pub fn remove_conceal(&mut self) {
+        self.remove_attr(Attribute::Hidden);
+    }
+
+    /// Add the `Dim` attribute
+    pub fn set_dim(&mut self) {
+        self.add_attr(Attribute::Dim);
+    }
+
+    /// Remove the `Dim` attribute
+    pub fn remove_dim(&mut self) {
+        self.remove_attr(Attribute::Dim);
+    }
+
+    /// Add the `Underlined` attribute
+    pub fn set_underlined(&mut self) {
+        self.add_attr(Attribute::Underlined);
+    }
+
+    /// Remove the `Underlined` attribute
+    pub fn remove_underlined(&mut self) {
+        self.remove_attr(Attribute::Underlined);
+    }
+
+    /// Add the `Reverse` attribute, swapping foreground and
+    ///  background colors
+    pub fn set_reverse(&mut self) {
+        self.add_attr(Attribute::Reverse);
+    }
+
+    /// Remove the `Reverse` attribute
+    pub fn remove_reverse(&mut self) {
+        self.remove_attr(Attribute::Reverse);
+    }
+
     /// Add the defined characteristics of `other` to self, overwriting
     ///  its own one when defined
     pub fn overwrite_with(&mut self, other: &CompoundStyle) {
@@ -225,3 +361,80 @@ impl CompoundStyle {
         StyledChar::new(self.clone(), nude_char)
     }
 }
+
+#[cfg(test)]
+mod compound_style_parsing_tests {
+
+    use {
+        super::*,
+        crate::color::gray,
+    };
+
+    #[test]
+    fn check_style_parsing() {
+        let style: CompoundStyle = "yellow bold on #202020".parse().unwrap();
+        assert_eq!(style.get_fg(), Some(Color::Yellow));
+        assert_eq!(style.get_bg(), Some(rgb(32, 32, 32)));
+        assert!(style.object_style.attributes.has(Attribute::Bold));
+        let style: CompoundStyle = "gray(10) Italic crossed".parse().unwrap();
+        assert_eq!(style.get_fg(), Some(gray(10)));
+        assert_eq!(style.get_bg(), None);
+        assert!(style.object_style.attributes.has(Attribute::Italic));
+        assert!(style.object_style.attributes.has(Attribute::CrossedOut));
+        let style: CompoundStyle = "on ansi(17)".parse().unwrap();
+        assert_eq!(style.get_fg(), None);
+        assert_eq!(style.get_bg(), Some(ansi(17)));
+        let style: CompoundStyle = "yellow on red bold".parse().unwrap();
+        assert_eq!(style.get_fg(), Some(Color::Yellow));
+        assert_eq!(style.get_bg(), Some(Color::Red));
+        assert!(style.object_style.attributes.has(Attribute::Bold));
+        let style: CompoundStyle = "on #202020 bold".parse().unwrap();
+        assert_eq!(style.get_fg(), None);
+        assert_eq!(style.get_bg(), Some(rgb(32, 32, 32)));
+        assert!(style.object_style.attributes.has(Attribute::Bold));
+        let style: CompoundStyle = "on red blue".parse().unwrap();
+        assert_eq!(style.get_fg(), Some(Color::Blue));
+        assert_eq!(style.get_bg(), Some(Color::Red));
+        for invalid in &[
+            "yelow bold",
+            "red on",
+            "red on bold",
+            "on red on blue",
+            "red green",
+            "red bold green",
+            "on red on",
+        ] {
+            assert!(invalid.parse::<CompoundStyle>().is_err(), "{:?} should be invalid", invalid);
+        }
+        assert_eq!(
+            "red blink".parse::<CompoundStyle>().unwrap_err().to_string(),
+            "Invalid style: unrecognized token \"blink\"",
+        );
+    }
+
+    #[test]
+    fn check_attribute_helpers() {
+        let mut style = CompoundStyle::with_fg(Color::Red);
+        style.set_blink();
+        style.set_conceal();
+        style.set_dim();
+        style.set_underlined();
+        style.set_reverse();
+        let attributes = style.object_style.attributes;
+        for attr in &[
+            Attribute::SlowBlink,
+            Attribute::Hidden,
+            Attribute::Dim,
+            Attribute::Underlined,
+            Attribute::Reverse,
+        ] {
+            assert!(attributes.has(*attr));
+        }
+        style.remove_blink();
+        style.remove_conceal();
+        style.remove_dim();
+        style.remove_underlined();
+        style.remove_reverse();
+        assert!(style.object_style.attributes.is_empty());
+    }
+}
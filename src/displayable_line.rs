@@ -1,7 +1,7 @@
 use {
     crate::{
         line::FmtLine,
-        skin::MadSkin,
+        skin::{MadSkin, Overlay},
     },
     std::fmt,
 };
@@ -27,3 +27,17 @@ impl fmt::Display for DisplayableLine<'_, '_, '_> {
         self.skin.write_fmt_line(f, self.line, self.width, true)
     }
 }
+
+/// A line written with an overlay
+pub(crate) struct OverlaidLine<'s, 'l, 'p, 'o> {
+    pub skin: &'s MadSkin,
+    pub line: &'p FmtLine<'l>,
+    pub width: Option<usize>,
+    pub overlay: Overlay<'o>,
+}
+
+impl fmt::Display for OverlaidLine<'_, '_, '_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.skin.write_overlaid_fmt_line(f, self.line, self.width, true, Some(&self.overlay))
+    }
+}
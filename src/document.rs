@@ -0,0 +1,123 @@
+use {
+    crate::{
+        render_options,
+        toc::{self, TocEntry},
+    },
+    minimad::{Composite, CompositeStyle, Compound, Line},
+    std::ops::Range,
+};
+
+/// A parsed markdown text, that is the structure the rendering
+/// functions consume.
+///
+/// It's built with `MadSkin::parse`, according to the render options
+/// of the skin, and may be walked (for example to check its structure
+/// or to list its headings) then rendered with `MadSkin::document_text`
+/// without being parsed again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownDocument<'s> {
+    /// the markdown the document was parsed from
+    pub src: &'s str,
+    /// the parsed lines. Code fences aren't kept: the lines
+    /// of fenced blocks are code lines.
+    pub lines: Vec<Line<'s>>,
+}
+
+/// A header of a markdown document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Heading<'d, 's> {
+    /// the index of the line in the document
+    pub line_idx: usize,
+    /// the level of the header, starting at 1
+    pub level: u8,
+    pub composite: &'d Composite<'s>,
+}
+
+impl Heading<'_, '_> {
+    /// Return the text of the header, without the markdown marks
+    pub fn text(&self) -> String {
+        self.composite.compounds.iter().map(|c| c.src).collect()
+    }
+}
+
+impl<'s> MarkdownDocument<'s> {
+    pub(crate) fn new(src: &'s str, lines: Vec<Line<'s>>) -> Self {
+        Self { src, lines }
+    }
+    /// Return the blocks of the document, that is the sequences
+    /// of lines separated by blank lines (the blank lines of code
+    /// blocks being part of the blocks)
+    pub fn blocks(&self) -> Vec<&[Line<'s>]> {
+        self.lines
+            .split(render_options::is_blank)
+            .filter(|block| !block.is_empty())
+            .collect()
+    }
+    /// Return the headers of the document, in order
+    pub fn headings(&self) -> Vec<Heading<'_, 's>> {
+        self.lines
+            .iter()
+            .enumerate()
+            .filter_map(|(line_idx, line)| match line {
+                Line::Normal(composite) => match composite.style {
+                    CompositeStyle::Header(level) => Some(Heading {
+                        line_idx,
+                        level,
+                        composite,
+                    }),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+    /// Return the entries of the table of contents, one per header
+    pub fn table_of_contents(&self) -> Vec<TocEntry> {
+        toc::entries(&self.headings())
+    }
+    /// Return the byte range, in the source, of a compound of
+    /// the document, or None if it isn't a part of the source
+    pub fn span(&self, compound: &Compound<'_>) -> Option<Range<usize>> {
+        let src_start = self.src.as_ptr() as usize;
+        let start = (compound.src.as_ptr() as usize).checked_sub(src_start)?;
+        let end = start + compound.src.len();
+        if end > self.src.len() {
+            return None;
+        }
+        Some(start..end)
+    }
+}
+
+#[cfg(test)]
+mod document_tests {
+
+    use crate::*;
+
+    #[test]
+    fn check_parsed_document() {
+        let md = "# Title\nsome **text**\n\n## Sub *title*\n```\na\n\nb\n```\n";
+        let skin = MadSkin::no_style();
+        let doc = skin.parse(md);
+        let headings: Vec<(usize, u8, String)> = doc
+            .headings()
+            .iter()
+            .map(|h| (h.line_idx, h.level, h.text()))
+            .collect();
+        assert_eq!(
+            headings,
+            vec![(0, 1, "Title".to_string()), (3, 2, "Sub title".to_string())],
+        );
+        let blocks = doc.blocks();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[1].len(), 4); // the header and the code lines
+        let text = &doc.headings()[1].composite.compounds[1];
+        assert_eq!(doc.span(text), Some(31..36));
+        assert_eq!(&md[31..36], "title");
+        assert_eq!(doc.span(&minimad::Compound::raw_str("x")), None);
+        // rendering the document is the same as rendering the markdown
+        assert_eq!(
+            skin.document_text(&doc, Some(10)).to_string(),
+            skin.text(md, Some(10)).to_string(),
+        );
+    }
+}
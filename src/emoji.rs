@@ -0,0 +1,178 @@
+use minimad::Compound;
+
+/// The emoji shortcodes, sorted by name
+static EMOJIS: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("angry", "😠"),
+    ("apple", "🍎"),
+    ("arrow_down", "⬇️"),
+    ("arrow_left", "⬅️"),
+    ("arrow_right", "➡️"),
+    ("arrow_up", "⬆️"),
+    ("beer", "🍺"),
+    ("bell", "🔔"),
+    ("bomb", "💣"),
+    ("book", "📖"),
+    ("bug", "🐛"),
+    ("bulb", "💡"),
+    ("cake", "🍰"),
+    ("calendar", "📆"),
+    ("cat", "🐱"),
+    ("check", "✔️"),
+    ("clap", "👏"),
+    ("clock", "🕐"),
+    ("cloud", "☁️"),
+    ("coffee", "☕"),
+    ("confused", "😕"),
+    ("cry", "😢"),
+    ("dog", "🐶"),
+    ("eyes", "👀"),
+    ("fire", "🔥"),
+    ("flag", "🚩"),
+    ("gear", "⚙️"),
+    ("ghost", "👻"),
+    ("gift", "🎁"),
+    ("grin", "😁"),
+    ("hammer", "🔨"),
+    ("heart", "❤️"),
+    ("hourglass", "⌛"),
+    ("house", "🏠"),
+    ("joy", "😂"),
+    ("key", "🔑"),
+    ("laughing", "😆"),
+    ("link", "🔗"),
+    ("lock", "🔒"),
+    ("mag", "🔍"),
+    ("memo", "📝"),
+    ("moon", "🌙"),
+    ("muscle", "💪"),
+    ("no_entry", "⛔"),
+    ("ok", "🆗"),
+    ("ok_hand", "👌"),
+    ("package", "📦"),
+    ("pencil", "📝"),
+    ("pizza", "🍕"),
+    ("pray", "🙏"),
+    ("question", "❓"),
+    ("rainbow", "🌈"),
+    ("rocket", "🚀"),
+    ("rofl", "🤣"),
+    ("sad", "😞"),
+    ("scream", "😱"),
+    ("see_no_evil", "🙈"),
+    ("skull", "💀"),
+    ("sleeping", "😴"),
+    ("smile", "😄"),
+    ("smiley", "😃"),
+    ("snake", "🐍"),
+    ("snowflake", "❄️"),
+    ("sob", "😭"),
+    ("sparkles", "✨"),
+    ("star", "⭐"),
+    ("sunglasses", "😎"),
+    ("sunny", "☀️"),
+    ("tada", "🎉"),
+    ("thinking", "🤔"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("tired_face", "😫"),
+    ("trophy", "🏆"),
+    ("unlock", "🔓"),
+    ("warning", "⚠️"),
+    ("wave", "👋"),
+    ("white_check_mark", "✅"),
+    ("wink", "😉"),
+    ("wrench", "🔧"),
+    ("x", "❌"),
+    ("zap", "⚡"),
+    ("zzz", "💤"),
+];
+
+/// Return the emoji whose name is given (without colons)
+pub(crate) fn emoji(name: &str) -> Option<&'static str> {
+    EMOJIS
+        .binary_search_by_key(&name, |&(n, _)| n)
+        .ok()
+        .map(|idx| EMOJIS[idx].1)
+}
+
+const fn is_name_char(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '+' | '-')
+}
+
+/// find the first known shortcode in the string, returning
+/// its start, its end (after the closing colon) and its emoji
+fn find_shortcode(src: &str) -> Option<(usize, usize, &'static str)> {
+    let mut start = None;
+    for (idx, c) in src.char_indices() {
+        if c == ':' {
+            if let Some(start) = start {
+                if let Some(emoji) = emoji(&src[start + 1..idx]) {
+                    return Some((start, idx + 1, emoji));
+                }
+            }
+            // this colon may open another shortcode
+            start = Some(idx);
+        } else if !is_name_char(c) {
+            start = None;
+        }
+    }
+    None
+}
+
+/// replace the known `:name:` shortcodes with their emoji.
+///
+/// Unknown shortcodes and code compounds are left as is.
+pub(crate) fn expand_shortcodes(compounds: &mut Vec<Compound<'_>>) {
+    if !compounds.iter().any(|c| !c.code && c.src.contains(':')) {
+        return;
+    }
+    let src_compounds = std::mem::take(compounds);
+    for mut compound in src_compounds {
+        if compound.code {
+            compounds.push(compound);
+            continue;
+        }
+        while let Some((start, end, emoji)) = find_shortcode(compound.src) {
+            if start > 0 {
+                compounds.push(compound.sub(0, start));
+            }
+            compounds.push(Compound {
+                src: emoji,
+                ..compound.clone()
+            });
+            compound = compound.tail(end);
+        }
+        if !compound.is_empty() {
+            compounds.push(compound);
+        }
+    }
+}
+
+#[cfg(test)]
+mod emoji_tests {
+
+    use {
+        super::*,
+        crate::*,
+    };
+
+    #[test]
+    fn check_table_is_sorted() {
+        for pair in EMOJIS.windows(2) {
+            assert!(pair[0].0 < pair[1].0, "{:?} isn't before {:?}", pair[0].0, pair[1].0);
+        }
+    }
+
+    #[test]
+    fn check_shortcode_expansion() {
+        let mut skin = MadSkin::no_style();
+        let md = "Done :tada: *really:+1::* `:x:` :not_a_code: 10:30:00";
+        assert_eq!(skin.inline(md).to_string(), "Done :tada: really:+1:: :x: :not_a_code: 10:30:00");
+        skin.set_emoji_shortcodes(true);
+        assert_eq!(skin.inline(md).to_string(), "Done 🎉 really👍: :x: :not_a_code: 10:30:00");
+        // the emoji are two columns wide
+        assert_eq!(skin.visible_width(":smile:rocket:"), 9);
+    }
+}
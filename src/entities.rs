@@ -0,0 +1,187 @@
+use {
+    crate::composite::{CompoundKind, KindedCompound},
+    minimad::Compound,
+    unicode_width::UnicodeWidthChar,
+};
+
+/// The named html entities, sorted by name
+static ENTITIES: &[(&str, &str)] = &[
+    ("amp", "&"),
+    ("apos", "'"),
+    ("bull", "•"),
+    ("cent", "¢"),
+    ("copy", "©"),
+    ("darr", "↓"),
+    ("deg", "°"),
+    ("divide", "÷"),
+    ("euro", "€"),
+    ("gt", ">"),
+    ("harr", "↔"),
+    ("hearts", "♥"),
+    ("hellip", "…"),
+    ("laquo", "«"),
+    ("larr", "←"),
+    ("ldquo", "“"),
+    ("lsquo", "‘"),
+    ("lt", "<"),
+    ("mdash", "—"),
+    ("middot", "·"),
+    ("nbsp", "\u{a0}"),
+    ("ndash", "–"),
+    ("para", "¶"),
+    ("plusmn", "±"),
+    ("pound", "£"),
+    ("quot", "\""),
+    ("raquo", "»"),
+    ("rarr", "→"),
+    ("rdquo", "”"),
+    ("reg", "®"),
+    ("rsquo", "’"),
+    ("sect", "§"),
+    ("times", "×"),
+    ("trade", "™"),
+    ("uarr", "↑"),
+    ("yen", "¥"),
+];
+
+/// the longest entity we look for, ampersand and semicolon included
+const MAX_ENTITY_LENGTH: usize = 12;
+
+/// What an entity is decoded to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decoded {
+    /// a named entity, with its string
+    Str(&'static str),
+    /// a numeric entity
+    Char(char),
+}
+
+/// Return a string of the width of the char, to be the source of
+/// the compound of a numeric entity (the char being displayed
+/// in its place)
+fn placeholder(c: char) -> &'static str {
+    match (c.width().unwrap_or(0), c.is_whitespace()) {
+        (0, _) => "\u{200b}",
+        (1, true) => " ",
+        (1, false) => "\u{fffd}",
+        (_, true) => "\u{3000}",
+        (_, false) => "\u{ff1f}",
+    }
+}
+
+/// Return what an entity (without its ampersand and semicolon)
+/// stands for, if it's a known or valid numeric one
+fn decode(entity: &str) -> Option<Decoded> {
+    if let Some(number) = entity.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16),
+            None => number.parse(),
+        };
+        return code
+            .ok()
+            .filter(|&code| code != 0)
+            .and_then(char::from_u32)
+            .map(Decoded::Char);
+    }
+    ENTITIES
+        .binary_search_by_key(&entity, |&(n, _)| n)
+        .ok()
+        .map(|idx| Decoded::Str(ENTITIES[idx].1))
+}
+
+/// find the first valid entity in the string, returning its
+/// start, its end (after the semicolon) and what it stands for
+fn find_entity(src: &str) -> Option<(usize, usize, Decoded)> {
+    for (start, _) in src.match_indices('&') {
+        let tail = &src[start + 1..];
+        let end = match tail.find(|c: char| !(c.is_ascii_alphanumeric() || c == '#')) {
+            Some(len) if len > 0 && len < MAX_ENTITY_LENGTH && tail[len..].starts_with(';') => len,
+            _ => { continue; }
+        };
+        if let Some(decoded) = decode(&tail[..end]) {
+            return Some((start, start + end + 2, decoded));
+        }
+    }
+    None
+}
+
+/// replace the html entities like `&amp;` or `&#x1F600;` with
+/// the chars they stand for.
+///
+/// The compound of a numeric entity is given the `Char` kind, as
+/// there's no static string for its char.
+///
+/// Invalid entities and code compounds are left as is.
+pub(crate) fn decode_entities(compounds: &mut Vec<KindedCompound<'_>>) {
+    if !compounds.iter().any(|(c, _)| !c.code && c.src.contains('&')) {
+        return;
+    }
+    let src_compounds = std::mem::take(compounds);
+    for (mut compound, kinds) in src_compounds {
+        if compound.code {
+            compounds.push((compound, kinds));
+            continue;
+        }
+        while let Some((start, end, decoded)) = find_entity(compound.src) {
+            if start > 0 {
+                compounds.push((compound.sub(0, start), kinds.clone()));
+            }
+            let mut decoded_kinds = kinds.clone();
+            let src = match decoded {
+                Decoded::Str(s) => s,
+                Decoded::Char(c) => {
+                    decoded_kinds.push(CompoundKind::Char(c));
+                    placeholder(c)
+                }
+            };
+            compounds.push((Compound { src, ..compound.clone() }, decoded_kinds));
+            compound = compound.tail(end);
+        }
+        if !compound.is_empty() {
+            compounds.push((compound, kinds));
+        }
+    }
+}
+
+#[cfg(test)]
+mod entities_tests {
+
+    use {
+        super::*,
+        crate::*,
+    };
+
+    #[test]
+    fn check_table_is_sorted() {
+        for pair in ENTITIES.windows(2) {
+            assert!(pair[0].0 < pair[1].0, "{:?} isn't before {:?}", pair[0].0, pair[1].0);
+        }
+    }
+
+    #[test]
+    fn check_entity_decoding() {
+        let mut skin = MadSkin::no_style();
+        let md = "a &amp; *b &lt;&gt;* `&amp;` &#x1F600; &#65;&#0; &nope; & &amp";
+        assert_eq!(skin.inline(md).to_string(), "a & b <> &amp; 😀 A&#0; &nope; & &amp");
+        // the width is the one of the decoded chars
+        assert_eq!(skin.visible_width("&#x1F600;&hellip;"), 3);
+        skin.set_html_entities(false);
+        assert_eq!(skin.inline("&amp;").to_string(), "&amp;");
+        skin.set_html_entities(true);
+        skin.set_render_options(RenderOptions {
+            allow_html: false,
+            ..Default::default()
+        });
+        assert_eq!(skin.inline("&amp;").to_string(), "&amp;");
+    }
+
+    #[test]
+    fn check_numeric_entities() {
+        let skin = MadSkin::no_style();
+        // the decoded chars have their width, even when wrapping
+        assert_eq!(skin.visible_width("e&#x301;&#x3000;&#65;"), 4);
+        let text = skin.text("ab&#x301; &#x4E00;&#x4E00; &#x1F600;", Some(5));
+        assert_eq!(text.to_string(), "ab\u{301} 一\n一 😀\n");
+        assert_eq!(text.stats().chars, 6);
+    }
+}
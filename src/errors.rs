@@ -4,6 +4,8 @@
 pub enum Error {
     #[error("IO error: {0}")]
     IO(#[from] std::io::Error),
+    #[error("Invalid style: {0}")]
+    InvalidStyle(String),
 }
 
 pub(crate) type Result<T> = std::result::Result<T, Error>;
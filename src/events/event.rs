@@ -19,6 +19,14 @@ pub enum Event {
 
     DoubleClick(u16, u16),
 
+    TripleClick(u16, u16),
+
+    /// the left button was pressed (a Click is sent when it's released)
+    MouseDown(u16, u16),
+
+    /// the mouse was moved with the left button pressed
+    Drag(u16, u16),
+
     /// terminal was resized. Contains the new dimensions
     Resize(u16, u16),
 
@@ -31,7 +39,7 @@ impl Event {
     ///
     /// normalize \r and \n into Enter (useful for key combinations)
     ///
-    /// To get a double-click you'll either need to use a termimad event-source
+    /// To get a double-click or a triple-click you'll either need to use a termimad event-source
     /// or to do the computation yourself.
     pub fn from_crossterm_event(
         crossterm_event: crossterm::event::Event
@@ -61,6 +69,26 @@ impl Event {
                     _ => None
                 }
             }
+            crossterm::event::Event::Mouse(
+                crossterm::event::MouseEvent {
+                    kind: MouseEventKind::Down(crossterm::event::MouseButton::Left),
+                    column,
+                    row,
+                    ..
+                }
+            ) => {
+                Some(Event::MouseDown(column, row))
+            }
+            crossterm::event::Event::Mouse(
+                crossterm::event::MouseEvent {
+                    kind: MouseEventKind::Drag(crossterm::event::MouseButton::Left),
+                    column,
+                    row,
+                    ..
+                }
+            ) => {
+                Some(Event::Drag(column, row))
+            }
             crossterm::event::Event::Mouse(
                 crossterm::event::MouseEvent { kind: MouseEventKind::ScrollUp, .. }
             ) => {
@@ -96,6 +124,9 @@ impl Event {
             Event::Click(x, y, _) => Some((x, y)),
             Event::RightClick(x, y, _) => Some((x, y)),
             Event::DoubleClick(x, y) => Some((x, y)),
+            Event::TripleClick(x, y) => Some((x, y)),
+            Event::MouseDown(x, y) => Some((x, y)),
+            Event::Drag(x, y) => Some((x, y)),
             _ => None,
         }
     }
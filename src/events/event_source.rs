@@ -33,6 +33,8 @@ struct TimedClick {
     time: Instant,
     x: u16,
     y: u16,
+    /// number of clicks in the series (1 for a simple click)
+    count: usize,
 }
 
 /// a thread backed event listener emmiting events on a channel.
@@ -79,10 +81,7 @@ impl EventSource {
                 if tx_events.send(event).is_err() {
                     true // broken channel
                 } else {
-                    match rx_quit.recv() {
-                        Ok(false) => false,
-                        _ => true,
-                    }
+                    !matches!(rx_quit.recv(), Ok(false))
                 }
             };
             loop {
@@ -126,17 +125,28 @@ impl EventSource {
                 }
                 if let Some(mut event) = Event::from_crossterm_event(ct_event) {
                     // save the event, and maybe change it
-                    // (may change a click into a double-click)
+                    // (may change a click into a double-click or a triple-click)
                     if let Event::Click(x, y, ..) = event {
-                        if let Some(TimedClick { time, x: last_x, y: last_y }) = last_click {
+                        let mut count = 1;
+                        if let Some(TimedClick { time, x: last_x, y: last_y, count: last_count }) = last_click {
                             if
                                 last_x == x && last_y == y
                                 && time.elapsed() < DOUBLE_CLICK_MAX_DURATION
                             {
-                                event = Event::DoubleClick(x, y);
+                                match last_count {
+                                    1 => {
+                                        event = Event::DoubleClick(x, y);
+                                        count = 2;
+                                    }
+                                    2 => {
+                                        event = Event::TripleClick(x, y);
+                                        count = 3;
+                                    }
+                                    _ => {} // we start a new series
+                                }
                             }
                         }
-                        last_click = Some(TimedClick { time: Instant::now(), x, y });
+                        last_click = Some(TimedClick { time: Instant::now(), x, y, count });
                     }
                     // we send the event to the receiver in the main event loop
                     if send_and_wait(event) {
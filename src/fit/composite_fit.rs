@@ -1,5 +1,8 @@
 use {
-    crate::*,
+    crate::{
+        composite::CompoundKind,
+        *,
+    },
     minimad::*,
     unicode_width::{
         UnicodeWidthChar,
@@ -7,8 +10,33 @@ use {
     },
 };
 
+/// The default string replacing the removed parts
 pub static ELLIPSIS: &str = "…";
 
+/// Where the removed part of a too long content is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FitMode {
+    /// keep the start, removing the end
+    End,
+    /// keep both ends, removing the center (fine for paths,
+    /// like `/home/…/project/file.rs`)
+    Middle,
+    /// keep the end, removing the start
+    Start,
+}
+
+impl FitMode {
+    /// return the widths of the kept start and end parts, when
+    /// `width` columns are available for them
+    pub(crate) const fn kept_widths(self, width: usize) -> (usize, usize) {
+        match self {
+            Self::End => (width, 0),
+            Self::Middle => (width - width / 2, width / 2),
+            Self::Start => (0, width),
+        }
+    }
+}
+
 /// A fitter can shorten a composite to make it fit a target width
 /// without wrapping (by removing parts and replacing them with
 /// ellipsis)
@@ -22,6 +50,14 @@ pub struct Fitter {
     mid_compound_ellision: bool,
 
     align: Alignment,
+
+    /// where to remove, when it's not decided from the alignment
+    /// and the content
+    mode: Option<FitMode>,
+
+    /// the string replacing the removed parts, when it's not
+    /// the one of the skin
+    ellipsis: Option<&'static str>,
 }
 
 impl Default for Fitter {
@@ -30,6 +66,8 @@ impl Default for Fitter {
             mid_token_ellision: true,
             mid_compound_ellision: true,
             align: Alignment::Unspecified,
+            mode: None,
+            ellipsis: None,
         }
     }
 }
@@ -37,14 +75,12 @@ impl Default for Fitter {
 #[derive(Debug, Clone, Copy)]
 struct CharInfo {
     byte_idx: usize,
-    char: char, // virer
-    width: usize, // virer
+    width: usize,
 }
 fn str_char_infos(s: &str) -> Vec<CharInfo> {
     s.char_indices()
         .map(|(byte_idx, char)| CharInfo {
             byte_idx,
-            char,
             width: char.width().unwrap_or(0),
         })
         .collect()
@@ -54,7 +90,6 @@ fn str_char_infos(s: &str) -> Vec<CharInfo> {
 struct Zone {
     compound_idx: usize,
     byte_start_idx: usize,
-    byte_end_idx: usize,
     char_infos: Vec<CharInfo>,
     removable_width: usize, // cell width of string minus one character each end
 }
@@ -78,7 +113,6 @@ impl Zone {
                                 zones.push(Zone {
                                     compound_idx,
                                     byte_start_idx,
-                                    byte_end_idx: byte_idx,
                                     char_infos,
                                     removable_width,
                                 });
@@ -91,8 +125,7 @@ impl Zone {
                 }
             }
             if let Some(byte_start_idx) = byte_start {
-                let byte_end_idx = s.len();
-                if byte_end_idx - byte_start_idx >= min_removable_width + 2 {
+                if s.len() - byte_start_idx >= min_removable_width + 2 {
                     let zs = &s[byte_start_idx..];
                     let removable_width = zs.width();
                     if removable_width >= min_removable_width {
@@ -100,7 +133,6 @@ impl Zone {
                         zones.push(Zone {
                             compound_idx,
                             byte_start_idx,
-                            byte_end_idx,
                             char_infos,
                             removable_width,
                         });
@@ -132,7 +164,6 @@ impl Zone {
                     Some(Zone {
                         compound_idx,
                         byte_start_idx: 0,
-                        byte_end_idx: compound.src.len(),
                         char_infos,
                         removable_width,
                     })
@@ -145,19 +176,20 @@ impl Zone {
             .drain(..)
             .max_by_key(|z| z.removable_width)
     }
-    /// return the gain (that is the removed minus 1 for the ellipsis length)
-    fn cut(&self, composite: &mut Composite, to_remove: usize) -> usize {
-        if self.removable_width < 2 {
+    /// return the gain (that is the removed width minus the ellipsis one)
+    fn cut<'s>(&self, fc: &mut FmtComposite<'s>, to_remove: usize, ellipsis: &'s str) -> usize {
+        let ellipsis_width = ellipsis.width();
+        if self.removable_width <= ellipsis_width {
             return 0;
         }
-        let compound = &composite.compounds[self.compound_idx];
+        let compound = &fc.composite.compounds[self.compound_idx];
         let len = self.char_infos.len();
         let mut start_char_idx = len / 2;
         let mut end_char_idx = start_char_idx;
         let mut removed_width = 0;
         loop {
             // we alternatively grow left and right
-            if (end_char_idx-start_char_idx)%2 == 0 {
+            if (end_char_idx-start_char_idx) % 2 == 0 {
                 if end_char_idx + 1 >= len {
                     break;
                 }
@@ -170,7 +202,7 @@ impl Zone {
             }
             let start_byte_idx = self.byte_start_idx + self.char_infos[start_char_idx].byte_idx;
             let end_byte_idx = self.byte_start_idx + self.char_infos[end_char_idx].byte_idx;
-            removed_width = (&compound.src[start_byte_idx..end_byte_idx]).width();
+            removed_width = compound.src[start_byte_idx..end_byte_idx].width();
             if removed_width >= to_remove {
                 break;
             }
@@ -179,11 +211,12 @@ impl Zone {
         let end_byte_idx = self.byte_start_idx + self.char_infos[end_char_idx].byte_idx;
         let head = compound.sub(0, start_byte_idx);
         let tail = compound.tail(end_byte_idx);
-        composite.compounds[self.compound_idx] = head;
-        composite.compounds.insert(self.compound_idx+1, Compound::raw_str(ELLIPSIS));
-        composite.compounds.insert(self.compound_idx+2, tail);
+        let kinds = fc.compound_kinds(self.compound_idx);
+        fc.composite.compounds[self.compound_idx] = head;
+        fc.insert_compound(self.compound_idx+1, Compound::raw_str(ellipsis), vec![CompoundKind::Ellipsis]);
+        fc.insert_compound(self.compound_idx+2, tail, kinds);
 
-        removed_width - 1
+        removed_width.saturating_sub(ellipsis_width)
     }
 }
 
@@ -199,28 +232,59 @@ impl Fitter {
             mid_token_ellision: internal_ellision,
             mid_compound_ellision: internal_ellision,
             align,
+            mode: None,
+            ellipsis: None,
         }
     }
 
+    /// create a fitter always removing the same part, whatever
+    /// the alignment and the content
+    pub fn for_mode(mode: FitMode) -> Self {
+        Self {
+            mid_token_ellision: false,
+            mid_compound_ellision: false,
+            align: Alignment::Unspecified,
+            mode: Some(mode),
+            ellipsis: None,
+        }
+    }
+
+    /// set the string replacing the removed parts, instead of the
+    /// `ellipsis_str` of the skin
+    pub const fn with_ellipsis(mut self, ellipsis: &'static str) -> Self {
+        self.ellipsis = Some(ellipsis);
+        self
+    }
+
     /// ensure the composite fits the max_width, by replacing some parts
     /// with ellisions
+    ///
+    /// The skin must outlive the composite, which may borrow its ellipsis.
     pub fn fit<'s>(
         self,
         fc: &mut FmtComposite<'s>,
         max_width: usize,
-        skin: &MadSkin
+        skin: &'s MadSkin
     ) {
+        let ellipsis: &'s str = self.ellipsis.unwrap_or(&skin.ellipsis_str);
+        let ellipsis_width = ellipsis.width();
         // some special cases because they're hard to check after
         if fc.visible_length <= max_width {
             return;
-        } else if max_width == 0 {
-            fc.composite.compounds.clear();
+        } else if max_width < ellipsis_width.max(1) {
+            fc.set_compounds(Vec::new());
             fc.visible_length = 0;
             return;
-        } else if max_width == 1 {
-            fc.composite.compounds.clear();
-            fc.composite.compounds.push(Compound::raw_str(ELLIPSIS));
-            fc.visible_length = 1;
+        } else if max_width == ellipsis_width {
+            fc.set_compounds(vec![(Compound::raw_str(ellipsis), vec![CompoundKind::Ellipsis])]);
+            fc.visible_length = ellipsis_width;
+            return;
+        }
+
+        if let Some(mode) = self.mode {
+            let (head_width, tail_width) = mode.kept_widths(max_width - ellipsis_width);
+            elide(fc, head_width, tail_width, ellipsis);
+            fc.recompute_width(skin);
             return;
         }
 
@@ -233,8 +297,8 @@ impl Fitter {
             // cutting in the middle of big no space parts
             while excess > 0 {
                 let mut gain = 0;
-                if let Some(zone) = Zone::biggest_token(&fc.composite, 3) {
-                    gain = zone.cut(&mut fc.composite, excess + 1);
+                if let Some(zone) = Zone::biggest_token(&fc.composite, ellipsis_width + 2) {
+                    gain = zone.cut(fc, excess + ellipsis_width, ellipsis);
                 }
                 if gain == 0 {
                     break;
@@ -247,10 +311,10 @@ impl Fitter {
             // cutting in the middle of big compounds
             while excess > 0 {
                 let mut gain = 0;
-                // we'll look for zones of removable width at least 2
-                // (because we put the ellipsis in place)
-                if let Some(zone) = Zone::biggest_compound(&fc.composite, 2) {
-                    gain = zone.cut(&mut fc.composite, excess + 1);
+                // we'll look for zones of removable width greater than
+                // the ellipsis one (because we put the ellipsis in place)
+                if let Some(zone) = Zone::biggest_compound(&fc.composite, ellipsis_width + 1) {
+                    gain = zone.cut(fc, excess + ellipsis_width, ellipsis);
                 }
                 if gain == 0 {
                     break;
@@ -264,27 +328,26 @@ impl Fitter {
             return;
         }
 
-        let compounds = &mut fc.composite.compounds;
         // we'll have to compensate with 1 or 2 ellipsis, so the "excess" is
         // increased accordingly we increase
-        let (mut excess_left, mut excess_right) = match self.align {
-            Alignment::Right => (excess + 1, 0),
-            Alignment::Left | Alignment:: Unspecified => (0, excess + 1),
+        let (mut excess_left, mut excess_right) = match skin.directed_align(self.align) {
+            Alignment::Right => (excess + ellipsis_width, 0),
+            Alignment::Left | Alignment:: Unspecified => (0, excess + ellipsis_width),
             Alignment::Center => {
                 let left = excess / 2;
                 let right = excess - left;
                 if left > 0 {
-                    (left + 1, right + 1)
+                    (left + ellipsis_width, right + ellipsis_width)
                 } else {
-                    (0, right + 1)
+                    (0, right + ellipsis_width)
                 }
             },
         };
 
         if excess_left > 0 {
             // left truncating
-            while excess_left > 0 && !compounds.is_empty() {
-                let compound = &mut compounds[0];
+            while excess_left > 0 && !fc.composite.compounds.is_empty() {
+                let compound = &mut fc.composite.compounds[0];
                 let char_infos = str_char_infos(compound.src);
                 let mut last_removed_char_idx = 0;
                 let mut removed_width = 0;
@@ -297,7 +360,7 @@ impl Fitter {
                 }
                 if last_removed_char_idx  + 1 == char_infos.len() {
                     // we remove the whole compound
-                    compounds.remove(0);
+                    fc.remove_compound(0);
                     excess_left -= removed_width.min(excess_left);
                 } else {
                     // we cut the left part
@@ -305,14 +368,14 @@ impl Fitter {
                     excess_left = 0;
                 }
             }
-            compounds.insert(0, Compound::raw_str(ELLIPSIS));
+            fc.insert_compound(0, Compound::raw_str(ellipsis), vec![CompoundKind::Ellipsis]);
         }
 
         if excess_right > 0 {
             // right truncating
-            while excess_right > 0 && !compounds.is_empty() {
-                let last_idx = compounds.len()-1;
-                let compound = &mut compounds[last_idx];
+            while excess_right > 0 && !fc.composite.compounds.is_empty() {
+                let last_idx = fc.composite.compounds.len()-1;
+                let compound = &mut fc.composite.compounds[last_idx];
                 let char_infos = str_char_infos(compound.src);
                 let mut removed_width = 0;
                 let mut end_byte_idx = compound.src.len();
@@ -325,7 +388,7 @@ impl Fitter {
                 }
                 if end_byte_idx == 0 {
                     // we remove the whole compound
-                    compounds.pop();
+                    fc.remove_compound(last_idx);
                     excess_right -= removed_width.min(excess_right);
                 } else {
                     // we cut the right part
@@ -333,7 +396,8 @@ impl Fitter {
                     excess_right = 0;
                 }
             }
-            compounds.push(Compound::raw_str(ELLIPSIS));
+            let len = fc.composite.compounds.len();
+            fc.insert_compound(len, Compound::raw_str(ellipsis), vec![CompoundKind::Ellipsis]);
         }
 
         fc.recompute_width(skin);
@@ -341,6 +405,63 @@ impl Fitter {
 
 }
 
+/// replace the chars of the composite not in the first `head_width`
+/// columns nor in the last `tail_width` ones with the ellipsis.
+///
+/// The composite must be wider than the kept parts.
+fn elide<'s>(
+    fc: &mut FmtComposite<'s>,
+    head_width: usize,
+    tail_width: usize,
+    ellipsis: &'s str,
+) {
+    let mut compounds = fc.take_compounds();
+    // the head is cut in the compound at head_idx
+    let mut head_idx = 0;
+    let mut head_end = 0;
+    let mut width = 0;
+    'head: for (idx, (compound, _)) in compounds.iter().enumerate() {
+        head_idx = idx;
+        for (byte_idx, c) in compound.src.char_indices() {
+            width += c.width().unwrap_or(0);
+            if width > head_width {
+                head_end = byte_idx;
+                break 'head;
+            }
+        }
+        head_end = compound.src.len();
+    }
+    // the tail starts in the compound at tail_idx
+    let mut tail_idx = compounds.len() - 1;
+    let mut tail_start = compounds[tail_idx].0.src.len();
+    let mut width = 0;
+    'tail: for (idx, (compound, _)) in compounds.iter().enumerate().rev() {
+        tail_idx = idx;
+        tail_start = compound.src.len();
+        for (byte_idx, c) in compound.src.char_indices().rev() {
+            width += c.width().unwrap_or(0);
+            if width > tail_width {
+                break 'tail;
+            }
+            tail_start = byte_idx;
+        }
+        tail_start = 0;
+    }
+    let head = (compounds[head_idx].0.sub(0, head_end), compounds[head_idx].1.clone());
+    let tail = (compounds[tail_idx].0.tail(tail_start), compounds[tail_idx].1.clone());
+    let after: Vec<_> = compounds.drain(tail_idx + 1..).collect();
+    compounds.truncate(head_idx);
+    if !head.0.is_empty() {
+        compounds.push(head);
+    }
+    compounds.push((Compound::raw_str(ellipsis), vec![CompoundKind::Ellipsis]));
+    if !tail.0.is_empty() {
+        compounds.push(tail);
+    }
+    compounds.extend(after);
+    fc.set_compounds(compounds);
+}
+
 /// Tests of fitting, that is cutting the composite at best to make it
 ///  fit a given width (if possible)
 ///
@@ -355,6 +476,7 @@ mod fit_tests {
         Composite,
     };
     use crate::{
+        FitMode,
         Fitter,
         FmtComposite,
     };
@@ -362,9 +484,9 @@ mod fit_tests {
     fn check_fit_align(src: &str, target_width: usize, align: Alignment) {
         dbg!((target_width, align));
         let skin = crate::get_default_skin();
-        let mut fc = FmtComposite::from(Composite::from_inline(src), &skin);
+        let mut fc = FmtComposite::from(Composite::from_inline(src), skin);
         let fitter = Fitter::for_align(align);
-        fitter.fit(&mut fc, target_width, &skin);
+        fitter.fit(&mut fc, target_width, skin);
         dbg!(&fc);
         assert!(fc.visible_length <= target_width); // can be smaller
     }
@@ -392,4 +514,81 @@ mod fit_tests {
         check_fit(status, 2);
     }
 
+    #[test]
+    fn test_rtl_truncation() {
+        let mut skin = crate::MadSkin::no_style();
+        let fitter = Fitter {
+            mid_token_ellision: false,
+            mid_compound_ellision: false,
+            align: Alignment::Unspecified,
+            mode: None,
+            ellipsis: None,
+        };
+        let fitted = |skin: &crate::MadSkin| {
+            let mut fc = FmtComposite::from(Composite::from_inline("abc def ghi"), skin);
+            fitter.fit(&mut fc, 6, skin);
+            crate::FmtInline { skin, composite: fc }.to_string()
+        };
+        assert_eq!(fitted(&skin), "abc d…");
+        skin.set_rtl(true);
+        assert_eq!(fitted(&skin), "…f ghi");
+    }
+
+    #[test]
+    fn test_custom_ellipsis() {
+        let mut skin = crate::MadSkin::no_style();
+        skin.set_ellipsis_str("...");
+        let fitted = |fitter: Fitter, src: &str, width: usize| {
+            let mut fc = FmtComposite::from(Composite::from_inline(src), &skin);
+            fitter.fit(&mut fc, width, &skin);
+            assert!(fc.visible_length <= width);
+            crate::FmtInline { skin: &skin, composite: fc }.to_string()
+        };
+        assert_eq!(fitted(Fitter::for_align(Alignment::Left), "abc def ghi", 8), "abc d...");
+        assert_eq!(fitted(Fitter::for_align(Alignment::Right), "abc def ghi", 8), "...f ghi");
+        assert_eq!(fitted(Fitter::default(), "abcdefghijkl", 8), "abc...kl");
+        assert_eq!(fitted(Fitter::default(), "abcdefghijkl", 3), "...");
+        assert_eq!(fitted(Fitter::default(), "abcdefghijkl", 2), "");
+        // a wide ellipsis, given to the fitter
+        let fitter = Fitter::for_align(Alignment::Left).with_ellipsis("⋯⋯");
+        assert_eq!(fitted(fitter, "一曰道，二曰天", 9), "一曰道⋯⋯");
+    }
+
+    #[test]
+    fn test_ellipsis_style() {
+        use crossterm::style::Color;
+        let mut skin = crate::MadSkin::no_style();
+        skin.set_ellipsis_str(String::from("..."));
+        skin.ellipsis.set_fg(Color::Red);
+        let mut fc = FmtComposite::from(Composite::from_inline("*...* cdefghij"), &skin);
+        Fitter::for_align(Alignment::Left).fit(&mut fc, 10, &skin);
+        let rendered = crate::FmtInline { skin: &skin, composite: fc }.to_string();
+        // only the ellipsis put by the fitter is styled, not the one of the text
+        let styled = format!("{}", skin.ellipsis.apply_to("..."));
+        assert_eq!(rendered, format!("... cde{}", styled));
+    }
+
+    #[test]
+    fn test_fit_modes() {
+        let skin = crate::MadSkin::no_style();
+        let fitted = |mode: FitMode, src: &str, width: usize| {
+            let mut fc = FmtComposite::from(Composite::from_inline(src), &skin);
+            fc.fit_width_with_mode(width, mode, &skin);
+            assert!(fc.visible_length <= width);
+            crate::FmtInline { skin: &skin, composite: fc }.to_string()
+        };
+        let path = "/home/someone/dev/project/src/file.rs";
+        assert_eq!(fitted(FitMode::Middle, path, 25), "/home/someon…/src/file.rs");
+        assert_eq!(fitted(FitMode::Start, path, 20), "…project/src/file.rs");
+        assert_eq!(fitted(FitMode::End, path, 20), "/home/someone/dev/p…");
+        assert_eq!(fitted(FitMode::Middle, path, 37), path);
+        // the cuts go over the compounds
+        assert_eq!(fitted(FitMode::Middle, "**abc**def*ghi*", 5), "ab…hi");
+        assert_eq!(fitted(FitMode::Start, "**abc**def*ghi*", 5), "…fghi");
+        // a wide char isn't split
+        assert_eq!(fitted(FitMode::Middle, "一曰道，二曰天", 8), "一曰…天");
+        assert_eq!(fitted(FitMode::Middle, "一曰道，二曰天", 7), "一…天");
+        assert_eq!(fitted(FitMode::Start, "一曰道，二曰天", 2), "…");
+    }
+
 }
@@ -13,6 +13,7 @@ pub use {
     crop_writer::*,
     filling::*,
     str_fit::*,
+    wrap::WrapAlgorithm,
 };
 use {
     crossterm::{
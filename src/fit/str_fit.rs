@@ -67,7 +67,7 @@ impl StrFit {
     pub fn make_string(s: &str, cols_max: usize) -> (String, usize) {
         let fit = StrFit::from(s, cols_max);
         if fit.has_tab {
-            let string = (&s[0..fit.bytes_count]).replace('\t', TAB_REPLACEMENT);
+            let string = s[0..fit.bytes_count].replace('\t', TAB_REPLACEMENT);
             (string, fit.cols_count)
         } else {
             (s[0..fit.bytes_count].to_string(), fit.cols_count)
@@ -80,11 +80,11 @@ impl StrFit {
     /// if it's very big
     /// In case there's no tab in the input string, we can return a pointer over
     /// part of the original str)
-    pub fn make_cow(s: &str, cols_max: usize) -> (Cow<str>, usize) {
+    pub fn make_cow(s: &str, cols_max: usize) -> (Cow<'_, str>, usize) {
         let fit = StrFit::from(s, cols_max);
         if fit.has_tab {
             // we can't just borrow, as we insert chars
-            let string = (&s[0..fit.bytes_count]).replace('\t', TAB_REPLACEMENT);
+            let string = s[0..fit.bytes_count].replace('\t', TAB_REPLACEMENT);
             (Cow::Owned(string), fit.cols_count)
         } else {
             (Cow::Borrowed(&s[0..fit.bytes_count]), fit.cols_count)
@@ -1,17 +1,35 @@
 use {
     crate::*,
     minimad::*,
-    unicode_width::UnicodeWidthStr,
+    std::borrow::Cow,
+    unicode_width::{UnicodeWidthChar, UnicodeWidthStr},
 };
 
+/// The way the lines of paragraphs are broken when they're
+/// too long for the width
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WrapAlgorithm {
+    /// put as many words as possible on every line
+    Greedy,
+    /// minimize the differences between the line widths, for
+    /// nicer paragraphs (but this is more expensive)
+    Balanced,
+}
+
+impl Default for WrapAlgorithm {
+    fn default() -> Self {
+        Self::Greedy
+    }
+}
+
 /// build a composite which can be a new line after wrapping.
-const fn follow_up_composite<'s>(fc: &FmtComposite<'s>) -> FmtComposite<'s> {
+fn follow_up_composite<'s>(fc: &FmtComposite<'s>) -> FmtComposite<'s> {
     let style = match fc.composite.style {
         minimad::CompositeStyle::ListItem => CompositeStyle::Paragraph,
         _ => fc.composite.style,
     };
     let visible_length = match style {
-        CompositeStyle::Quote => 2,
+        CompositeStyle::Quote => 2 + fc.quote_extra_width(),
         _ => 0,
     };
     FmtComposite {
@@ -21,6 +39,11 @@ const fn follow_up_composite<'s>(fc: &FmtComposite<'s>) -> FmtComposite<'s> {
         },
         visible_length,
         spacing: fc.spacing,
+        links: fc.links.clone(),
+        list_mark: None,
+        quote_depth: fc.quote_depth,
+        continuation: true,
+        kinds: Vec::new(),
     }
 }
 
@@ -36,17 +59,61 @@ pub const fn composite_style_widths(composite_style: CompositeStyle) -> (usize,
     }
 }
 
+/// Return the composite with the marks drawn before its text reduced,
+/// when necessary, so that they leave at least 3 columns for the text
+/// (or 1 when the width is too small): the marks of the deepest quote
+/// levels aren't drawn, and a too wide list mark (like the number of
+/// an item of a long ordered list) is replaced with the bullet.
+pub(crate) fn fit_decorations<'c, 's>(
+    fc: &'c FmtComposite<'s>,
+    width: usize,
+) -> Cow<'c, FmtComposite<'s>> {
+    let max_quote_depth = (width.saturating_sub(3) / 2).max(1);
+    if fc.composite.is_quote() && fc.quote_depth > max_quote_depth {
+        let mut fc = fc.clone();
+        fc.set_quote_depth(max_quote_depth);
+        return Cow::Owned(fc);
+    }
+    let mark_extra_width = fc.list_mark_extra_width();
+    if mark_extra_width > 0 && 2 + mark_extra_width + 3 > width {
+        let mut fc = fc.clone();
+        fc.set_list_mark(None);
+        return Cow::Owned(fc);
+    }
+    Cow::Borrowed(fc)
+}
+
 /// cut the passed composite in several composites fitting the given *visible* width
 /// (which might be bigger or smaller than the length of the underlying string).
 /// width can't be less than 3.
-pub fn hard_wrap_composite<'s, 'c>(
-    src_composite: &'c FmtComposite<'s>,
+pub fn hard_wrap_composite<'s>(
+    src_composite: &FmtComposite<'s>,
     width: usize,
+) -> Vec<FmtComposite<'s>> {
+    hard_wrap_composite_indented(src_composite, width, 0)
+}
+
+/// cut the passed composite in several composites fitting the given
+/// *visible* width, like `hard_wrap_composite`, but leaving room for
+/// an indentation of the lines after the first one (which must be
+/// added when writing them).
+/// width can't be less than 3, nor indent more than width - 3.
+pub(crate) fn hard_wrap_composite_indented<'s>(
+    src_composite: &FmtComposite<'s>,
+    width: usize,
+    indent: usize,
 ) -> Vec<FmtComposite<'s>> {
     assert!(width > 2);
-    debug_assert!(src_composite.visible_length > width); // or we shouldn't be called
+    let src_composite = fit_decorations(src_composite, width);
+    if src_composite.visible_length <= width {
+        return vec![src_composite.into_owned()];
+    }
     let mut composites: Vec<FmtComposite<'s>> = Vec::new();
     let (first_width, _other_widths) = composite_style_widths(src_composite.composite.style);
+    let first_width = first_width
+        + src_composite.list_mark_extra_width()
+        + src_composite.quote_extra_width();
+    let _other_widths = _other_widths + src_composite.quote_extra_width() + indent;
     let mut dst_composite = FmtComposite {
         composite: Composite {
             style: src_composite.composite.style,
@@ -54,6 +121,11 @@ pub fn hard_wrap_composite<'s, 'c>(
         },
         visible_length: first_width,
         spacing: src_composite.spacing,
+        links: src_composite.links.clone(),
+        list_mark: src_composite.list_mark,
+        quote_depth: src_composite.quote_depth,
+        continuation: src_composite.continuation,
+        kinds: Vec::new(),
     };
 
     // Strategy 1:
@@ -74,10 +146,14 @@ pub fn hard_wrap_composite<'s, 'c>(
             && compounds[1].src.chars().all(char::is_whitespace)
         )
     {
-        dst_composite.add_compound(compounds[0].clone());
+        let last_idx = compounds.len() - 1;
+        dst_composite.add_compound_of_kinds(compounds[0].clone(), src_composite.compound_kinds(0));
         let mut new_dst_composite = follow_up_composite(&dst_composite);
         composites.push(dst_composite);
-        new_dst_composite.add_compound(compounds[compounds.len()-1].clone());
+        new_dst_composite.add_compound_of_kinds(
+            compounds[last_idx].clone(),
+            src_composite.compound_kinds(last_idx),
+        );
         composites.push(new_dst_composite);
         return composites;
     }
@@ -85,28 +161,154 @@ pub fn hard_wrap_composite<'s, 'c>(
     let mut tokens = tokenize(&src_composite.composite, width - first_width);
     // Strategy 2:
     // we try to cut along tokens, using spaces to break
+    let mut soft_hyphen = None; // a soft hyphen just before the token
     for token in tokens.drain(..) {
-        if dst_composite.visible_length + token.width > width {
+        let line_indent = if composites.is_empty() { 0 } else { indent };
+        let compound = token.to_compound();
+        let kind = src_composite.compound_kinds(token.compound_idx);
+        if crate::breaks::is_soft_hyphen(&compound) {
+            soft_hyphen = Some(compound);
+            continue;
+        }
+        if line_indent + dst_composite.visible_length + token.width > width {
             if !token.blank { // we skip blank composite at line change
+                if let Some(soft_hyphen) = soft_hyphen.take() {
+                    if line_indent + dst_composite.visible_length < width {
+                        dst_composite.add_compound(crate::breaks::hyphen(&soft_hyphen));
+                    }
+                }
                 let mut repl_composite = follow_up_composite(&dst_composite);
                 std::mem::swap(&mut dst_composite, &mut repl_composite);
                 composites.push(repl_composite);
-                dst_composite.add_compound(token.to_compound());
+                dst_composite.add_compound_of_kinds(compound, kind);
             }
         } else {
-            dst_composite.add_compound(token.to_compound());
+            dst_composite.add_compound_of_kinds(compound, kind);
         }
+        soft_hyphen = None;
     }
     composites.push(dst_composite);
     composites
 }
 
+/// a word, made of non blank tokens, with the blank tokens following it
+struct Word {
+    start: usize, // index of the first token
+    end: usize, // index after the last non blank token
+    width: usize,
+    blank_width: usize, // width of the following blank tokens
+}
+
+/// cut the passed composite in lines fitting the given width, with
+/// widths as close as possible (the raggedness, that is the sum of the
+/// squares of the spaces at the end of lines, the last one excepted,
+/// is minimal).
+///
+/// Return None when a word is too wide for a line, in which case
+/// the greedy wrapping must be used.
+fn hard_wrap_composite_balanced<'s>(
+    src_composite: &FmtComposite<'s>,
+    width: usize,
+) -> Option<Vec<FmtComposite<'s>>> {
+    let fitted = fit_decorations(src_composite, width);
+    let src_composite: &FmtComposite<'s> = &fitted;
+    let (first_width, _) = composite_style_widths(src_composite.composite.style);
+    let first_width = first_width
+        + src_composite.list_mark_extra_width()
+        + src_composite.quote_extra_width();
+    let follow_up_width = follow_up_composite(src_composite).visible_length;
+    let first_available = width.checked_sub(first_width)?;
+    let available = width.checked_sub(follow_up_width)?;
+    let tokens: Vec<Token<'s>> = tokenize(&src_composite.composite, width)
+        .into_iter()
+        .filter(|token| !crate::breaks::is_soft_hyphen(&token.to_compound()))
+        .collect();
+    let mut words: Vec<Word> = Vec::new();
+    for (idx, token) in tokens.iter().enumerate() {
+        match words.last_mut() {
+            Some(word) if token.blank => {
+                word.blank_width += token.width;
+            }
+            Some(word) if word.blank_width == 0 => {
+                word.end = idx + 1;
+                word.width += token.width;
+            }
+            _ => {
+                // (blanks at start are part of the first word)
+                words.push(Word { start: idx, end: idx + 1, width: token.width, blank_width: 0 });
+            }
+        }
+    }
+    // best[i]: the minimal cost of the lines starting at word i,
+    // with the index of the word starting the next line
+    let n = words.len();
+    let mut best: Vec<(usize, usize)> = vec![(0, n); n + 1];
+    for i in (0..n).rev() {
+        let line_available = if i == 0 { first_available } else { available };
+        let mut line_width = 0;
+        let mut found = None;
+        for j in i + 1..=n {
+            if j > i + 1 {
+                line_width += words[j - 2].blank_width;
+            }
+            line_width += words[j - 1].width;
+            if line_width > line_available {
+                break;
+            }
+            let slack = line_available - line_width;
+            let cost = if j == n { 0 } else { slack * slack + best[j].0 };
+            if found.map_or(true, |(best_cost, _)| cost < best_cost) {
+                found = Some((cost, j));
+            }
+        }
+        best[i] = found?;
+    }
+    let mut composites = Vec::new();
+    let mut dst_composite = FmtComposite {
+        composite: Composite {
+            style: src_composite.composite.style,
+            compounds: Vec::new(),
+        },
+        visible_length: first_width,
+        kinds: Vec::new(),
+        ..src_composite.clone()
+    };
+    let mut i = 0;
+    while i < n {
+        let j = best[i].1;
+        // (the blanks ending the line aren't counted in its width, so
+        // they're not kept, even on the last line)
+        for token in &tokens[words[i].start..words[j - 1].end] {
+            dst_composite.add_compound_of_kinds(
+                token.to_compound(),
+                src_composite.compound_kinds(token.compound_idx),
+            );
+        }
+        let next_composite = follow_up_composite(&dst_composite);
+        composites.push(std::mem::replace(&mut dst_composite, next_composite));
+        i = j;
+    }
+    Some(composites)
+}
+
 /// hard_wrap all normal lines to ensure the text fits the width.
 /// width can't be less than 3.
 /// Doesn't touch table rows.
 /// Consumes the passed array and return a new one (may contain
 /// the original lines, avoiding cloning when possible)
 pub fn hard_wrap_lines<'s>(src_lines: Vec<FmtLine<'s>>, width: usize) -> Vec<FmtLine<'s>> {
+    hard_wrap_lines_with(src_lines, width, WrapAlgorithm::Greedy)
+}
+
+/// hard_wrap all normal lines to ensure the text fits the width,
+/// like `hard_wrap_lines` but with the given algorithm for the
+/// lines which aren't code.
+/// width can't be less than 3.
+pub fn hard_wrap_lines_with<'s>(
+    src_lines: Vec<FmtLine<'s>>,
+    width: usize,
+    algorithm: WrapAlgorithm,
+) -> Vec<FmtLine<'s>> {
     assert!(width > 2);
     let mut src_lines = src_lines;
     let mut lines = Vec::new();
@@ -115,7 +317,14 @@ pub fn hard_wrap_lines<'s>(src_lines: Vec<FmtLine<'s>>, width: usize) -> Vec<Fmt
             if fc.visible_length <= width {
                 lines.push(FmtLine::Normal(fc));
             } else {
-                for fc in hard_wrap_composite(&fc, width) {
+                let balanced = match algorithm {
+                    WrapAlgorithm::Balanced if !fc.composite.is_code() => {
+                        hard_wrap_composite_balanced(&fc, width)
+                    }
+                    _ => None,
+                };
+                let composites = balanced.unwrap_or_else(|| hard_wrap_composite(&fc, width));
+                for fc in composites {
                     lines.push(FmtLine::Normal(fc));
                 }
             }
@@ -126,6 +335,61 @@ pub fn hard_wrap_lines<'s>(src_lines: Vec<FmtLine<'s>>, width: usize) -> Vec<Fmt
     lines
 }
 
+/// Return the number of cells a char takes in an input: 2 for
+/// wide chars (for example CJK ones), 1 for the other ones
+pub fn char_width(c: char) -> usize {
+    c.width().unwrap_or(1).clamp(1, 2)
+}
+
+/// Compute where to cut a line made of the passed chars so that
+/// each part fits the given width, breaking after spaces when
+/// possible.
+///
+/// The cell just after the last char is reserved (it's where an
+/// input field's cursor goes).
+///
+/// Return the index of the first char of each part (the first
+/// one being always 0). The width of chars is given by `char_width`.
+pub fn wrap_chars(chars: &[char], width: usize) -> Vec<usize> {
+    wrap_chars_with(chars, width, char_width)
+}
+
+/// Compute where to cut a line made of the passed chars, like
+/// `wrap_chars`, but with the width of each char given by the
+/// passed function
+pub fn wrap_chars_with<F>(chars: &[char], width: usize, char_width: F) -> Vec<usize>
+where
+    F: Fn(char) -> usize,
+{
+    let mut starts = vec![0];
+    if width == 0 {
+        return starts;
+    }
+    let mut start = 0;
+    loop {
+        // end is the index after the last char fitting the width
+        let mut end = start;
+        let mut cols = 0;
+        while end < chars.len() && cols + char_width(chars[end]) <= width {
+            cols += char_width(chars[end]);
+            end += 1;
+        }
+        if end == chars.len() && cols < width {
+            break; // the rest fits, with the cursor cell
+        }
+        if end == start {
+            end += 1; // a char wider than the width
+        }
+        let cut = (start + 1..=end)
+            .rev()
+            .find(|&idx| chars[idx - 1].is_whitespace())
+            .unwrap_or(end);
+        starts.push(cut);
+        start = cut;
+    }
+    starts
+}
+
 /// Tests of hard wrapping
 ///
 /// The print which happens in case of failure isn't really well
@@ -157,12 +421,12 @@ mod wrap_tests {
         let text = skin.text(src, Some(width));
         println!("------- test wrapping with width: {}", width);
         for line in &text.lines {
-            let len = visible_fmt_line_length(skin, &line);
+            let len = visible_fmt_line_length(skin, line);
             println!(
                 "len:{: >4}  | {}",
                 len,
                 DisplayableLine {
-                    skin: &skin,
+                    skin,
                     line,
                     width: None,
                 }
@@ -178,10 +442,10 @@ mod wrap_tests {
         let text = skin.text(src, Some(width));
         assert_eq!(text.lines.len(), lenghts.len(), "same number of lines");
         println!("====\nwrapped text:\n{}", &text);
-        for i in 0..lenghts.len() {
+        for (i, &lenght) in lenghts.iter().enumerate() {
             assert_eq!(
                 visible_fmt_line_length(skin, &text.lines[i]),
-                lenghts[i],
+                lenght,
                 "expected length for line {} when wrapping at {}",
                 i,
                 width
@@ -200,15 +464,77 @@ mod wrap_tests {
                    * short item\n\
                    * a *somewhat longer item* (with a part in **bold**)";
         for width in 3..50 {
-            check_no_overflow(skin, &src, width);
+            check_no_overflow(skin, src, width);
+        }
+        check_line_lengths(skin, src, 25, vec![25, 19, 25, 7, 12, 25, 21]);
+    }
+
+    #[test]
+    fn check_balanced_wrapping() {
+        let mut skin = MadSkin::no_style();
+        let src = "aaa bb cc ddddd\n* a list item to wrap in a nice way";
+        let wrapped = |skin: &MadSkin, width| -> Vec<String> {
+            skin.text(src, Some(width))
+                .to_plain_string()
+                .lines()
+                .map(str::trim_end)
+                .map(String::from)
+                .collect()
+        };
+        assert_eq!(
+            wrapped(&skin, 6),
+            vec!["aaa bb", "cc", "ddddd", "• a", "list", "item", "to", "wrap", "in a", "nice", "way"],
+        );
+        assert_eq!(
+            wrapped(&skin, 12),
+            vec!["aaa bb cc", "ddddd", "• a list", "item to wrap", "in a nice", "way"],
+        );
+        skin.set_wrap_algorithm(WrapAlgorithm::Balanced);
+        assert_eq!(
+            wrapped(&skin, 6),
+            vec!["aaa", "bb cc", "ddddd", "• a", "list", "item", "to", "wrap", "in a", "nice", "way"],
+        );
+        assert_eq!(
+            wrapped(&skin, 12),
+            vec!["aaa bb cc", "ddddd", "• a list", "item to wrap", "in a nice", "way"],
+        );
+        for width in 3..50 {
+            check_no_overflow(&skin, src, width);
+        }
+        // trailing blanks mustn't make the last line overflow
+        for src in ["aa bb cccccc \t", "* aa bb cccccc \t"] {
+            for width in 3..20 {
+                check_no_overflow(&skin, src, width);
+            }
+        }
+        // the same words, in the same order
+        let words = |text: FmtText<'_, '_>| -> Vec<String> {
+            text.to_plain_string().split_whitespace().map(String::from).collect()
+        };
+        for width in 10..50 {
+            assert_eq!(words(skin.text(src, Some(width))), words(skin.text(src, None)));
         }
-        check_line_lengths(skin, &src, 25, vec![25, 19, 25, 7, 12, 25, 21]);
+    }
+
+    #[test]
+    fn check_chars_wrapping() {
+        let chars: Vec<char> = "a short text, and a loooooooong word".chars().collect();
+        assert_eq!(wrap_chars(&chars, 100), vec![0]);
+        assert_eq!(wrap_chars(&chars, 12), vec![0, 8, 20, 32]);
+        assert_eq!(wrap_chars(&[], 5), vec![0]);
+        // the cursor cell after the last char is reserved
+        assert_eq!(wrap_chars(&['a', 'b', 'c'], 3), vec![0, 3]);
+        // wide chars take two cells and aren't split
+        let chars: Vec<char> = "日本語の文".chars().collect();
+        assert_eq!(wrap_chars(&chars, 5), vec![0, 2, 4]);
+        assert_eq!(wrap_chars(&chars, 1), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(wrap_chars_with(&chars, 5, |_| 1), vec![0, 5]);
     }
 
     #[test]
     fn check_space_removing() {
         let skin = crate::get_default_skin();
-        let src = FmtComposite::from(Composite::from_inline("syntax coloring"), &skin);
+        let src = FmtComposite::from(Composite::from_inline("syntax coloring"), skin);
         println!("input:\n{:?}", &src);
         let wrapped = hard_wrap_composite(&src, 8);
         println!("wrapped: {:?}", &wrapped);
@@ -0,0 +1,295 @@
+use {
+    minimad::{
+        once_cell::sync::Lazy,
+        Composite,
+        CompositeStyle,
+        Compound,
+        Line,
+    },
+    crate::{
+        composite::{CompoundKind, FmtComposite},
+        line::FmtLine,
+        skin::MadSkin,
+    },
+    std::collections::HashMap,
+};
+
+/// The maximal number of footnotes in a text: the following
+/// references are left as is
+const MAX_FOOTNOTES: usize = 999;
+
+static SUPERSCRIPT_DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+
+/// The markers of the footnotes, that is their numbers written
+/// as superscripts (the first one being the marker of number 1)
+static MARKERS: Lazy<Vec<String>> = Lazy::new(|| {
+    (1..=MAX_FOOTNOTES)
+        .map(|number| {
+            number
+                .to_string()
+                .bytes()
+                .map(|digit| SUPERSCRIPT_DIGITS[(digit - b'0') as usize])
+                .collect()
+        })
+        .collect()
+});
+
+/// return the marker of a footnote (numbers starting at 1)
+fn marker(number: usize) -> Option<&'static str> {
+    MARKERS.get(number.wrapping_sub(1)).map(String::as_str)
+}
+
+/// if the string starts with a footnote label like `[^note]`, return
+/// the label and the length of the bracketed part
+fn parse_label(s: &str) -> Option<(&str, usize)> {
+    let tail = s.strip_prefix("[^")?;
+    let end = tail.find(']')?;
+    let label = &tail[..end];
+    if label.is_empty() || !label.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return None;
+    }
+    Some((label, end + 3))
+}
+
+/// if the composite is a footnote definition (`[^label]: text`),
+/// return its label and the compound of the bracketed label with
+/// its colon, removing them from the compounds
+fn take_definition<'s>(composite: &mut Composite<'s>) -> Option<(&'s str, Compound<'s>)> {
+    if composite.style != CompositeStyle::Paragraph {
+        return None;
+    }
+    let first = composite.compounds.first()?;
+    if first.code {
+        return None;
+    }
+    let (label, len) = parse_label(first.src)?;
+    if !first.src[len..].starts_with(':') {
+        return None;
+    }
+    let label_compound = first.sub(0, len + 1);
+    let text_start = len + 1 + (first.src[len + 1..].len() - first.src[len + 1..].trim_start().len());
+    let tail = first.tail(text_start);
+    if tail.is_empty() {
+        composite.compounds.remove(0);
+    } else {
+        composite.compounds[0] = tail;
+    }
+    Some((label, label_compound))
+}
+
+/// isolate the references to defined footnotes in their own
+/// compounds, numbering the footnotes in order of first reference
+fn isolate_references<'s>(
+    compounds: &mut Vec<Compound<'s>>,
+    definitions: &HashMap<&'s str, (Compound<'s>, Composite<'s>)>,
+    numbers: &mut Vec<&'s str>,
+) {
+    if !compounds.iter().any(|c| !c.code && c.src.contains("[^")) {
+        return;
+    }
+    let src_compounds = std::mem::take(compounds);
+    for mut compound in src_compounds {
+        if compound.code {
+            compounds.push(compound);
+            continue;
+        }
+        let mut from = 0;
+        loop {
+            let src: &'s str = compound.src;
+            let start = match src[from..].find("[^") {
+                Some(idx) => from + idx,
+                None => break,
+            };
+            let found = parse_label(&src[start..])
+                .filter(|(label, _)| definitions.contains_key(label));
+            let (label, len) = match found {
+                Some(found) => found,
+                None => {
+                    from = start + 2;
+                    continue;
+                }
+            };
+            if !numbers.contains(&label) {
+                if numbers.len() == MAX_FOOTNOTES {
+                    from = start + 2;
+                    continue;
+                }
+                numbers.push(label);
+            }
+            if start > 0 {
+                compounds.push(compound.sub(0, start));
+            }
+            compounds.push(compound.sub(start, start + len));
+            compound = compound.tail(start + len);
+            from = 0;
+        }
+        if !compound.is_empty() {
+            compounds.push(compound);
+        }
+    }
+}
+
+/// Isolate the footnote references in their own compounds, and move
+/// the definitions of the referenced footnotes at the end of the text,
+/// after an horizontal rule, and return the labels of the footnotes,
+/// in order of first reference.
+///
+/// The references and the labels starting the definitions are
+/// replaced with numbered markers by `mark_footnotes`, once the
+/// lines are formatted.
+///
+/// References to undefined footnotes are left as is while the
+/// definitions of footnotes which aren't referenced are removed.
+pub(crate) fn extract_footnotes<'s>(lines: &mut Vec<Line<'s>>) -> Vec<&'s str> {
+    let mut definitions = HashMap::new();
+    for mut line in std::mem::take(lines) {
+        if let Line::Normal(composite) = &mut line {
+            if let Some((label, label_compound)) = take_definition(composite) {
+                let definition = std::mem::replace(composite, Composite::new());
+                definitions.insert(label, (label_compound, definition));
+                continue;
+            }
+        }
+        lines.push(line);
+    }
+    if definitions.is_empty() {
+        return Vec::new();
+    }
+    let mut numbers = Vec::new();
+    for line in lines.iter_mut() {
+        match line {
+            Line::Normal(composite) if !composite.is_code() => {
+                isolate_references(&mut composite.compounds, &definitions, &mut numbers);
+            }
+            Line::TableRow(row) => {
+                for cell in &mut row.cells {
+                    isolate_references(&mut cell.compounds, &definitions, &mut numbers);
+                }
+            }
+            _ => {}
+        }
+    }
+    if numbers.is_empty() {
+        return numbers;
+    }
+    lines.push(Line::HorizontalRule);
+    for label in &numbers {
+        let (label_compound, mut definition) = definitions.remove(label).unwrap();
+        definition.compounds.insert(0, label_compound);
+        definition.compounds.insert(1, Compound::raw_str(" "));
+        lines.push(Line::Normal(definition));
+    }
+    numbers
+}
+
+/// replace the compounds of the references and definition labels
+/// of the footnotes with their markers
+fn mark_composite(fc: &mut FmtComposite<'_>, labels: &[&str], skin: &MadSkin) {
+    if fc.composite.is_code() {
+        return;
+    }
+    let mut marked = false;
+    for idx in 0..fc.composite.compounds.len() {
+        let compound = &fc.composite.compounds[idx];
+        if compound.code {
+            continue;
+        }
+        let (label, len) = match parse_label(compound.src) {
+            Some(found) => found,
+            None => continue,
+        };
+        let kind = match &compound.src[len..] {
+            "" => CompoundKind::FootnoteRef,
+            ":" if idx == 0 => CompoundKind::FootnoteDef,
+            _ => continue,
+        };
+        let number = match labels.iter().position(|&l| l == label) {
+            Some(idx) => idx + 1,
+            None => continue,
+        };
+        if let Some(marker) = marker(number) {
+            fc.composite.compounds[idx].src = marker;
+            fc.kinds.push((idx, kind));
+            marked = true;
+        }
+    }
+    if marked {
+        fc.recompute_width(skin);
+    }
+}
+
+/// replace the references to the footnotes with the given labels,
+/// isolated by `extract_footnotes`, and the labels starting their
+/// definitions, with the markers of the footnotes
+pub(crate) fn mark_footnotes(lines: &mut [FmtLine<'_>], labels: &[&str], skin: &MadSkin) {
+    if labels.is_empty() {
+        return;
+    }
+    for line in lines {
+        match line {
+            FmtLine::Normal(fc) => {
+                mark_composite(fc, labels, skin);
+            }
+            FmtLine::TableRow(row) => {
+                for cell in &mut row.cells {
+                    mark_composite(cell, labels, skin);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod footnote_tests {
+
+    use {
+        crate::*,
+        crossterm::style::Color,
+    };
+
+    #[test]
+    fn check_footnotes() {
+        let skin = MadSkin::no_style();
+        let md = "Some text[^note] and more[^1].\n\
+            [^1]: the **first** one\n\
+            An undefined[^x], then the note again[^note].\n\
+            [^note]:   a note\n\
+            [^unused]: never referenced";
+        assert_eq!(
+            skin.text(md, None).to_plain_string(),
+            "Some text¹ and more².\n\
+            An undefined[^x], then the note again¹.\n\
+            \n\
+            ¹ a note\n\
+            ² the first one\n",
+        );
+    }
+
+    #[test]
+    fn check_footnote_styles() {
+        let mut skin = MadSkin::no_style();
+        skin.footnote_ref.set_fg(Color::Red);
+        skin.footnote_def.set_fg(Color::Blue);
+        let text = skin.text("a[^1]\n[^1]: b", None).to_string();
+        assert!(text.starts_with("a\u{1b}[38;5;9m¹\u{1b}[39m\n"));
+        assert!(text.contains("\u{1b}[38;5;12m¹\u{1b}[39m b"));
+    }
+
+    #[test]
+    fn check_footnote_styles_are_kept() {
+        let mut skin = MadSkin::no_style();
+        skin.footnote_ref.set_fg(Color::Red);
+        let red_marker = "\u{1b}[38;5;9m¹\u{1b}[39m";
+        // a superscript written in the markdown isn't a marker
+        let text = skin.text("x¹ a[^1]\n[^1]: b", None).to_string();
+        assert!(text.starts_with(&format!("x¹ a{}", red_marker)));
+        // the marker is still styled when wrapped, or when the text
+        // comes from a parsed document
+        let md = "some words and a[^1] reference\n[^1]: b";
+        let text = skin.text(md, Some(12)).to_string();
+        assert_eq!(text.matches(red_marker).count(), 1);
+        let text = skin.document_text(&skin.parse(md), Some(12)).to_string();
+        assert_eq!(text.matches(red_marker).count(), 1);
+    }
+}
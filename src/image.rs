@@ -0,0 +1,145 @@
+use {
+    crate::{
+        composite::{CompoundKind, KindedCompound},
+        link,
+    },
+    minimad::Compound,
+    std::collections::VecDeque,
+};
+
+/// The way markdown images (`![alt](url)`) are rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageMode {
+    /// images aren't interpreted: the markdown is displayed as is
+    Raw,
+    /// a placeholder made of a mark, the alt text and the url
+    /// between parenthesis, like `🖼 alt (url)`
+    Placeholder,
+    /// only the alt text is displayed
+    Alt,
+}
+
+impl Default for ImageMode {
+    fn default() -> Self {
+        Self::Placeholder
+    }
+}
+
+/// the mark starting the placeholder of an image
+static IMAGE_MARK: &str = "🖼 ";
+
+/// add a compound from the markdown of an image, marking it as such
+fn add_image_compound<'s>(
+    compounds: &mut Vec<KindedCompound<'s>>,
+    (compound, mut kinds): KindedCompound<'s>,
+) {
+    if compound.is_empty() {
+        return;
+    }
+    kinds.push(CompoundKind::Image);
+    compounds.push((compound, kinds));
+}
+
+/// find the images in the compounds, and replace their markdown with
+/// what must be displayed according to the mode, marked as images
+pub(crate) fn extract_images(
+    compounds: &mut Vec<KindedCompound<'_>>,
+    mode: ImageMode,
+) {
+    if mode == ImageMode::Raw || !compounds.iter().any(|(c, _)| c.src.contains("![")) {
+        return;
+    }
+    let mut queue: VecDeque<KindedCompound<'_>> = std::mem::take(compounds).into();
+    while let Some((first, first_kinds)) = queue.pop_front() {
+        let marks = match link::find_link(&first, queue.iter().map(|(c, _)| c), true) {
+            Some(marks) => marks,
+            None => {
+                compounds.push((first, first_kinds));
+                continue;
+            }
+        };
+        if marks.open > 1 {
+            compounds.push((first.sub(0, marks.open - 1), first_kinds.clone()));
+        }
+        if mode == ImageMode::Placeholder {
+            compounds.push((Compound::raw_str(IMAGE_MARK), vec![CompoundKind::Image]));
+        }
+        let alt_start = compounds.len();
+        let (last, last_kinds) = match marks.close_idx {
+            None => (first, first_kinds),
+            Some(close_idx) => {
+                add_image_compound(compounds, (first.tail(marks.open + 1), first_kinds));
+                for _ in 0..close_idx {
+                    let compound = queue.pop_front().unwrap();
+                    add_image_compound(compounds, compound);
+                }
+                queue.pop_front().unwrap()
+            }
+        };
+        let text_start = if marks.close_idx.is_none() { marks.open + 1 } else { 0 };
+        add_image_compound(compounds, (last.sub(text_start, marks.close), last_kinds.clone()));
+        if mode == ImageMode::Placeholder {
+            if compounds.len() > alt_start {
+                compounds.push((Compound::raw_str(" "), Vec::new()));
+            }
+            // the url with its parenthesis
+            add_image_compound(compounds, (last.sub(marks.close + 1, marks.end), last_kinds.clone()));
+        }
+        if marks.end < last.src.len() {
+            queue.push_front((last.tail(marks.end), last_kinds));
+        }
+    }
+}
+
+#[cfg(test)]
+mod image_tests {
+
+    use crate::*;
+
+    fn rendered(skin: &MadSkin, md: &str) -> String {
+        skin.inline(md).to_string()
+    }
+
+    #[test]
+    fn check_image_modes() {
+        let mut skin = MadSkin::no_style();
+        let md = "see ![the *logo*](img/logo.png) or ![](x.png)!";
+        assert_eq!(rendered(&skin, md), "see 🖼 the logo (img/logo.png) or 🖼 (x.png)!");
+        skin.set_image_mode(ImageMode::Alt);
+        assert_eq!(rendered(&skin, md), "see the logo or !");
+        skin.set_image_mode(ImageMode::Raw);
+        assert_eq!(rendered(&skin, md), "see ![the logo](img/logo.png) or ![](x.png)!");
+        skin.set_image_mode(ImageMode::Placeholder);
+        skin.set_render_options(RenderOptions {
+            allow_images: false,
+            ..Default::default()
+        });
+        assert_eq!(rendered(&skin, md), "see ![the logo](img/logo.png) or ![](x.png)!");
+        // links aren't images
+        skin.set_render_options(RenderOptions::default());
+        skin.set_hyperlinks(HyperlinkMode::Text);
+        assert_eq!(rendered(&skin, "[a](b) ![c](d)"), "a 🖼 c (d)");
+    }
+
+    #[test]
+    fn check_image_style() {
+        let mut skin = MadSkin::no_style();
+        skin.image = CompoundStyle::with_attr(crossterm::style::Attribute::Underlined);
+        assert_eq!(
+            rendered(&skin, "a ![b](c) d"),
+            "a \u{1b}[4m🖼 \u{1b}[0m\u{1b}[4mb\u{1b}[0m \u{1b}[4m(c)\u{1b}[0m d",
+        );
+    }
+
+    #[test]
+    fn check_image_style_after_wrapping() {
+        let mut skin = MadSkin::no_style();
+        skin.image = CompoundStyle::with_attr(crossterm::style::Attribute::Underlined);
+        skin.set_image_mode(ImageMode::Alt);
+        let text = skin.text("abc ![def ghi](x) jkl", Some(8));
+        assert_eq!(
+            text.to_string(),
+            "abc \u{1b}[4mdef\u{1b}[0m\u{1b}[4m \u{1b}[0m\n\u{1b}[4mghi\u{1b}[0m jkl\n",
+        );
+    }
+}
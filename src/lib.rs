@@ -27,7 +27,7 @@ skin.strikeout = CompoundStyle::new(Some(Red), None, Bold.into());
 
 **Beware:**
 * you may define colors in full [`rgb`](fn.rgb.html) but this will limit compatibility with old
-terminals. It's recommended to stick to [Ansi colors](fn.ansi.html), [gray levels](fn.gray.html), or [Crossterm predefined values](https://docs.rs/crossterm/0.9.6/crossterm/enum.Color.html).
+  terminals. It's recommended to stick to [Ansi colors](fn.ansi.html), [gray levels](fn.gray.html), or [Crossterm predefined values](https://docs.rs/crossterm/0.9.6/crossterm/enum.Color.html).
 * styles are composed. For example a word may very well be italic, bold and striked out. It might not be wise to have them differ only by their background color for example.
 
 # Display a simple inline snippet
@@ -106,48 +106,73 @@ The repository contains several other examples, which hopefully cover the whole
 
 mod ask;
 mod area;
+mod block_spacing;
+mod breaks;
 mod code;
 mod color;
 mod composite;
 mod compound_style;
 mod displayable_line;
+mod document;
+#[cfg(feature="emoji")]
+mod emoji;
+mod entities;
 mod errors;
 mod events;
 mod fit;
+mod footnote;
+mod image;
 mod inline;
 mod line;
 mod line_style;
+mod link;
+mod list;
 mod macros;
+mod quote;
+mod render_options;
 mod scrollbar_style;
 mod skin;
+mod source_map;
 mod spacing;
+mod stream;
 mod styled_char;
+mod tab;
 mod tbl;
 mod text;
+mod toc;
 mod tokens;
 mod views;
 
 pub use {
     ask::*,
     area::{compute_scrollbar, terminal_size, Area},
+    block_spacing::BlockSpacing,
     color::{ansi, gray, rgb},
     composite::FmtComposite,
     compound_style::CompoundStyle,
+    document::{Heading, MarkdownDocument},
     errors::Error,
     events::{Event, EventSource},
     fit::*,
+    image::ImageMode,
     inline::FmtInline,
-    line::FmtLine,
+    line::{FmtLine, HorizontalRuleMode},
     line_style::LineStyle,
+    link::{FmtLink, HyperlinkMode, Link},
+    list::ListItemMark,
+    render_options::RenderOptions,
     minimad::Alignment,
-    scrollbar_style::ScrollBarStyle,
+    scrollbar_style::{ScrollBarSide, ScrollBarStyle},
     skin::MadSkin,
     spacing::Spacing,
     styled_char::StyledChar,
-    text::FmtText,
+    tbl::ColWidth,
+    text::{FmtText, TextStats},
+    toc::TocEntry,
     views::{
-        InputField, ListView, ListViewCell, ListViewColumn,
-        MadView, ProgressBar, TextView,
+        CursorShape, InputField, InputFieldAction, InputFieldContent, ListView, ListViewCell,
+        ListViewColumn,
+        MadView, Pos, ProgressBar, Spinner, TextView,
     },
 };
 pub use minimad;
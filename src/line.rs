@@ -1,9 +1,27 @@
 use minimad::{Line, TableRule};
 
 use crate::composite::FmtComposite;
+use crate::list;
+use crate::quote;
 use crate::skin::MadSkin;
 use crate::tbl::{FmtTableRow, FmtTableRule, RelativePosition};
 
+/// The way horizontal rules (`---`) are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HorizontalRuleMode {
+    /// the rule takes the whole width of the text
+    Full,
+    /// the rule has the given width (or the one of the text if
+    /// it's smaller) and is centered
+    Centered(usize),
+}
+
+impl Default for HorizontalRuleMode {
+    fn default() -> Self {
+        Self::Full
+    }
+}
+
 /// A line in a text. This structure should normally not be
 /// used outside of the lib.
 #[derive(Debug)]
@@ -20,7 +38,15 @@ impl<'s> FmtLine<'s> {
     /// in the future
     pub fn from(mline: Line<'s>, skin: &MadSkin) -> Self {
         match mline {
-            Line::Normal(composite) => FmtLine::Normal(FmtComposite::from(composite, skin)),
+            Line::Normal(mut composite) => {
+                let quote_depth = quote::extract_quote_depth(&mut composite);
+                let list_mark = list::extract_task_mark(&mut composite)
+                    .or_else(|| list::extract_number_mark(&mut composite));
+                let mut fc = FmtComposite::from(composite, skin);
+                fc.set_list_mark(list_mark);
+                fc.set_quote_depth(quote_depth);
+                FmtLine::Normal(fc)
+            }
             Line::TableRow(table_row) => FmtLine::TableRow(FmtTableRow::from(table_row, skin)),
             Line::TableRule(TableRule { cells }) => FmtLine::TableRule(FmtTableRule {
                 position: RelativePosition::Other,
@@ -31,10 +57,14 @@ impl<'s> FmtLine<'s> {
             Line::CodeFence(..) => FmtLine::HorizontalRule, // we're not supposed to get code fence in clean texts
         }
     }
+    /// Return the width the line takes when written, including the
+    /// padding (for example of code blocks) and the table borders
     pub fn visible_length(&self) -> usize {
         match self {
-            FmtLine::Normal(composite) => composite.visible_length,
-            FmtLine::TableRow(row) => row.cells.iter().fold(0, |s, c| s + c.visible_length), // Is that right ? no spacing ?
+            FmtLine::Normal(fc) => fc.spacing.map_or(fc.visible_length, |sp| sp.width),
+            FmtLine::TableRow(row) => 1 + row.cells.iter().fold(0, |s, c| {
+                s + c.spacing.map_or(c.visible_length, |sp| sp.width) + 1
+            }),
             FmtLine::TableRule(rule) => 1 + rule.widths.iter().fold(0, |s, w| s + w + 1),
             FmtLine::HorizontalRule => 0, // No intrinsic width
         }
@@ -0,0 +1,287 @@
+use {
+    crate::composite::{CompoundKind, KindedCompound},
+    minimad::Compound,
+    std::{
+        collections::VecDeque,
+        ops::Range,
+    },
+};
+
+/// The way markdown links (`[text](url)`) are rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HyperlinkMode {
+    /// links aren't interpreted: the markdown is displayed as is
+    Raw,
+    /// only the text of the link is displayed
+    Text,
+    /// the text is followed by the url between parenthesis
+    TextAndUrl,
+    /// the text is displayed, wrapped in OSC 8 escape sequences
+    /// so that it's clickable in the terminals supporting them
+    Osc8,
+}
+
+impl Default for HyperlinkMode {
+    fn default() -> Self {
+        Self::Raw
+    }
+}
+
+/// A link found in a composite.
+///
+/// The compounds of the text of the link are marked with its
+/// index, which makes it possible to find them back after the
+/// composite was wrapped or fitted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FmtLink<'s> {
+    pub url: &'s str,
+    text: String,
+}
+
+/// A link of a text, with where it's rendered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    /// the displayed text
+    pub text: String,
+    pub url: String,
+    /// the index of every line the link is displayed on, with
+    /// the range of the columns it takes there
+    pub spans: Vec<(usize, Range<usize>)>,
+}
+
+impl FmtLink<'_> {
+    /// the text of the link, without its styles
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    pub(crate) fn push_text(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+}
+
+/// add a compound of the text of the link with the given index
+fn add_text_compound<'s>(
+    compounds: &mut Vec<KindedCompound<'s>>,
+    link_idx: usize,
+    (compound, mut kinds): KindedCompound<'s>,
+) {
+    if compound.is_empty() {
+        return;
+    }
+    kinds.push(CompoundKind::Link(link_idx));
+    compounds.push((compound, kinds));
+}
+
+/// where a link was found: the position of its opening bracket
+/// in the first compound, the index (in the following compounds)
+/// of the compound holding the closing bracket, the position of
+/// this bracket and the position just after the closing parenthesis
+pub(crate) struct LinkMarks<'s> {
+    pub open: usize,
+    pub close_idx: Option<usize>,
+    pub close: usize,
+    pub end: usize,
+    pub url: &'s str,
+}
+
+/// if there's a closing bracket immediately followed by an url
+/// between parenthesis, return the position of the bracket, the
+/// position after the parenthesis and the url
+fn find_link_end(src: &str, from: usize) -> Option<Option<(usize, usize, &str)>> {
+    let close = from + src[from..].find(']')?;
+    let tail = &src[close + 1..];
+    let found = tail.strip_prefix('(')
+        .and_then(|tail| tail.find(')').map(|l| &tail[..l]))
+        .filter(|url| !url.is_empty() && !url.contains(char::is_whitespace))
+        .map(|url| (close, close + url.len() + 3, url));
+    Some(found)
+}
+
+/// find a link, or an image (whose opening bracket follows
+/// an exclamation mark) when `image` is true
+pub(crate) fn find_link<'c, 's: 'c, I>(
+    first: &Compound<'s>,
+    next: I,
+    image: bool,
+) -> Option<LinkMarks<'s>>
+where
+    I: IntoIterator<Item = &'c Compound<'s>> + Clone,
+{
+    if first.code {
+        return None;
+    }
+    let src = first.src;
+    for (open, _) in src.match_indices('[') {
+        if src[..open].ends_with('!') != image {
+            continue; // an image when looking for a link, or the reverse
+        }
+        if let Some(found) = find_link_end(src, open + 1) {
+            if let Some((close, end, url)) = found {
+                return Some(LinkMarks { open, close_idx: None, close, end, url });
+            }
+            continue;
+        }
+        for (idx, compound) in next.clone().into_iter().enumerate() {
+            if compound.code {
+                continue;
+            }
+            if let Some(found) = find_link_end(compound.src, 0) {
+                if let Some((close, end, url)) = found {
+                    return Some(LinkMarks { open, close_idx: Some(idx), close, end, url });
+                }
+                break;
+            }
+        }
+    }
+    None
+}
+
+/// find the links in the compounds, replace their markdown with what
+/// must be displayed according to the mode, and return them.
+///
+/// The texts of the returned links are empty, as they're made of
+/// the compounds marked with their index once fully processed.
+pub(crate) fn extract_links<'s>(
+    compounds: &mut Vec<KindedCompound<'s>>,
+    mode: HyperlinkMode,
+) -> Vec<FmtLink<'s>> {
+    let mut links = Vec::new();
+    if mode == HyperlinkMode::Raw {
+        return links;
+    }
+    let mut queue: VecDeque<KindedCompound<'s>> = std::mem::take(compounds).into();
+    while let Some((first, first_kinds)) = queue.pop_front() {
+        let marks = match find_link(&first, queue.iter().map(|(c, _)| c), false) {
+            Some(marks) => marks,
+            None => {
+                compounds.push((first, first_kinds));
+                continue;
+            }
+        };
+        if marks.open > 0 {
+            compounds.push((first.sub(0, marks.open), first_kinds.clone()));
+        }
+        let link_idx = links.len();
+        let (last, last_kinds) = match marks.close_idx {
+            None => (first, first_kinds),
+            Some(close_idx) => {
+                add_text_compound(
+                    compounds,
+                    link_idx,
+                    (first.tail(marks.open + 1), first_kinds),
+                );
+                for _ in 0..close_idx {
+                    let compound = queue.pop_front().unwrap();
+                    add_text_compound(compounds, link_idx, compound);
+                }
+                queue.pop_front().unwrap()
+            }
+        };
+        let text_start = if marks.close_idx.is_none() { marks.open + 1 } else { 0 };
+        add_text_compound(
+            compounds,
+            link_idx,
+            (last.sub(text_start, marks.close), last_kinds.clone()),
+        );
+        if mode == HyperlinkMode::TextAndUrl {
+            compounds.push((Compound::raw_str(" ("), Vec::new()));
+            compounds.push((Compound::raw_str(marks.url), Vec::new()));
+            compounds.push((Compound::raw_str(")"), Vec::new()));
+        }
+        if marks.end < last.src.len() {
+            queue.push_front((last.tail(marks.end), last_kinds));
+        }
+        links.push(FmtLink {
+            url: marks.url,
+            text: String::new(),
+        });
+    }
+    links
+}
+
+#[cfg(test)]
+mod link_tests {
+
+    use {
+        super::*,
+        crate::*,
+        minimad::Composite,
+    };
+
+    fn skin_with(mode: HyperlinkMode) -> MadSkin {
+        let mut skin = MadSkin::no_style();
+        skin.set_hyperlinks(mode);
+        skin
+    }
+
+    fn rendered(skin: &MadSkin, md: &str) -> String {
+        skin.inline(md).to_string()
+    }
+
+    #[test]
+    fn check_link_modes() {
+        let md = "see [the *doc*](https://dystroy.org) or [that](x)!";
+        assert_eq!(
+            rendered(&skin_with(HyperlinkMode::Raw), md),
+            "see [the doc](https://dystroy.org) or [that](x)!",
+        );
+        assert_eq!(
+            rendered(&skin_with(HyperlinkMode::Text), md),
+            "see the doc or that!",
+        );
+        assert_eq!(
+            rendered(&skin_with(HyperlinkMode::TextAndUrl), md),
+            "see the doc (https://dystroy.org) or that (x)!",
+        );
+        assert_eq!(
+            rendered(&skin_with(HyperlinkMode::Osc8), md),
+            "see \u{1b}]8;;https://dystroy.org\u{1b}\\the doc\u{1b}]8;;\u{1b}\\ \
+            or \u{1b}]8;;x\u{1b}\\that\u{1b}]8;;\u{1b}\\!",
+        );
+    }
+
+    #[test]
+    fn check_not_links() {
+        let skin = skin_with(HyperlinkMode::Text);
+        for md in &["a[0] and b", "[no url]()", "![image](img.png)", "`[a](b)`"] {
+            let fc = FmtComposite::from(Composite::from_inline(md), &skin);
+            assert!(fc.links.is_empty(), "no link expected in {:?}", md);
+        }
+    }
+
+    #[test]
+    fn check_link_with_entities() {
+        let skin = skin_with(HyperlinkMode::Osc8);
+        assert_eq!(
+            rendered(&skin, "[A &amp; B](http://x) &amp; [&#x1F600;](y)"),
+            "\u{1b}]8;;http://x\u{1b}\\A & B\u{1b}]8;;\u{1b}\\ & \
+            \u{1b}]8;;y\u{1b}\\😀\u{1b}]8;;\u{1b}\\",
+        );
+        let links = skin.text("[A &amp; B](http://x) &amp; [&#x1F600;](y)", None).links();
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].text, "A & B");
+        assert_eq!(links[0].spans, vec![(0, 0..5)]);
+        assert_eq!(links[1].text, "😀");
+        assert_eq!(links[1].spans, vec![(0, 8..10)]);
+        // identical links are still distinct
+        assert_eq!(skin.text("[a](u) [a](u)", None).links().len(), 2);
+    }
+
+    #[test]
+    fn check_osc8_wrapped_link() {
+        let skin = skin_with(HyperlinkMode::Osc8);
+        let text = skin.text("a [link with several words](u) here", Some(12));
+        let lines: Vec<String> = text.to_string().lines().map(String::from).collect();
+        assert_eq!(lines.len(), 3);
+        for line in &lines {
+            // each line opens and closes its link part
+            assert_eq!(line.matches("\u{1b}]8;;u\u{1b}\\").count(), 1);
+            assert_eq!(line.matches("\u{1b}]8;;\u{1b}\\").count(), 1);
+        }
+        for line in &text.lines {
+            if let FmtLine::Normal(fc) = line {
+                assert!(fc.visible_length <= 12);
+            }
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use {
+    crate::line::FmtLine,
+    minimad::{Composite, CompositeStyle},
+};
+
+/// A mark written at the start of a list item in place of the bullet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListItemMark {
+    /// the checkbox of a task list item
+    Checkbox { checked: bool },
+    /// the number of an item in an ordered list, right aligned in
+    /// a gutter of the given width, after `indent` spaces
+    Number {
+        number: usize,
+        width: usize,
+        indent: usize,
+    },
+}
+
+impl ListItemMark {
+    /// return the width of the mark, including the space after it
+    pub const fn width(self) -> usize {
+        match self {
+            Self::Checkbox { .. } => 2,
+            Self::Number { width, indent, .. } => indent + width + 2,
+        }
+    }
+}
+
+fn digits_count(mut number: usize) -> usize {
+    let mut count = 1;
+    while number >= 10 {
+        number /= 10;
+        count += 1;
+    }
+    count
+}
+
+/// If the composite is a task list item (like `* [ ] todo` or
+/// `- [x] done`), remove the markdown of the checkbox, make sure
+/// the composite is a list item, and return the mark
+pub(crate) fn extract_task_mark(composite: &mut Composite<'_>) -> Option<ListItemMark> {
+    let first = composite.compounds.first()?;
+    if first.code {
+        return None;
+    }
+    let src = match composite.style {
+        CompositeStyle::ListItem => first.src,
+        CompositeStyle::Paragraph => first.src.strip_prefix("- ")?,
+        _ => { return None; }
+    };
+    let checked = match src.get(..4)? {
+        "[ ] " => false,
+        "[x] " | "[X] " => true,
+        _ => { return None; }
+    };
+    let tail = first.tail(first.src.len() - src.len() + 4);
+    if tail.is_empty() {
+        composite.compounds.remove(0);
+    } else {
+        composite.compounds[0] = tail;
+    }
+    composite.style = CompositeStyle::ListItem;
+    Some(ListItemMark::Checkbox { checked })
+}
+
+/// If the composite is an item of an ordered list (like `1. first`),
+/// remove the markdown of the number, make sure the composite is a
+/// list item, and return the mark, with the number as written
+pub(crate) fn extract_number_mark(composite: &mut Composite<'_>) -> Option<ListItemMark> {
+    if composite.style != CompositeStyle::Paragraph {
+        return None;
+    }
+    let first = composite.compounds.first()?;
+    if first.code {
+        return None;
+    }
+    let src = first.src;
+    let indent = src.len() - src.trim_start_matches(' ').len();
+    let digits = src[indent..].len() - src[indent..].trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if indent > 3 || digits == 0 || digits > 9 || !src[indent + digits..].starts_with(". ") {
+        return None;
+    }
+    let number = src[indent..indent + digits].parse().ok()?;
+    let tail = first.tail(indent + digits + 2);
+    if tail.is_empty() {
+        composite.compounds.remove(0);
+    } else {
+        composite.compounds[0] = tail;
+    }
+    composite.style = CompositeStyle::ListItem;
+    Some(ListItemMark::Number { number, width: digits_count(number), indent })
+}
+
+fn number_mark(line: &FmtLine<'_>) -> Option<(usize, usize)> {
+    match line {
+        FmtLine::Normal(fc) => match fc.list_mark {
+            Some(ListItemMark::Number { number, indent, .. }) => Some((number, indent)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// an ordered list being numbered
+struct OrderedList {
+    indent: usize,
+    start: usize,
+    items: Vec<usize>, // indices of the lines
+}
+
+impl OrderedList {
+    fn finish(self, lines: &mut [FmtLine<'_>]) {
+        let width = digits_count(self.start + self.items.len() - 1);
+        for (idx, number) in self.items.into_iter().zip(self.start..) {
+            if let FmtLine::Normal(fc) = &mut lines[idx] {
+                fc.set_list_mark(Some(ListItemMark::Number {
+                    number,
+                    width,
+                    indent: self.indent,
+                }));
+            }
+        }
+    }
+}
+
+/// number the items of the ordered lists from their position,
+/// starting at the number of the first item, with a restart for
+/// every nested list, and align the numbers of every list
+pub(crate) fn number_lists(lines: &mut [FmtLine<'_>]) {
+    let mut lists: Vec<OrderedList> = Vec::new();
+    for idx in 0..lines.len() {
+        let (number, indent) = match number_mark(&lines[idx]) {
+            Some(mark) => mark,
+            None => {
+                while let Some(list) = lists.pop() {
+                    list.finish(lines);
+                }
+                continue;
+            }
+        };
+        while lists.last().map_or(false, |list| list.indent > indent) {
+            lists.pop().unwrap().finish(lines);
+        }
+        match lists.last_mut() {
+            Some(list) if list.indent == indent => {
+                list.items.push(idx);
+            }
+            _ => {
+                lists.push(OrderedList {
+                    indent,
+                    start: number,
+                    items: vec![idx],
+                });
+            }
+        }
+    }
+    while let Some(list) = lists.pop() {
+        list.finish(lines);
+    }
+}
+
+#[cfg(test)]
+mod list_tests {
+
+    use crate::*;
+
+    fn list_mark(skin: &MadSkin, md: &str) -> Option<ListItemMark> {
+        match skin.text(md, None).lines.into_iter().next() {
+            Some(FmtLine::Normal(fc)) => fc.list_mark,
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn check_task_list_items() {
+        let skin = MadSkin::no_style();
+        let unchecked = Some(ListItemMark::Checkbox { checked: false });
+        let checked = Some(ListItemMark::Checkbox { checked: true });
+        assert_eq!(list_mark(&skin, "* [ ] todo"), unchecked);
+        assert_eq!(list_mark(&skin, "- [ ] todo"), unchecked);
+        assert_eq!(list_mark(&skin, "* [x] done"), checked);
+        assert_eq!(list_mark(&skin, "- [X] **done**"), checked);
+        assert_eq!(list_mark(&skin, "* normal item"), None);
+        assert_eq!(list_mark(&skin, "[ ] not in a list"), None);
+        assert_eq!(list_mark(&skin, "* [y] not a checkbox"), None);
+        assert_eq!(
+            skin.text("- [ ] todo\n* [x] *done*", None).to_string(),
+            "☐ todo\n☑ done\n",
+        );
+    }
+
+    #[test]
+    fn check_ordered_lists() {
+        let skin = MadSkin::no_style();
+        let md = "1. one\n1. two\n  1. sub one\n  1. sub two\n3. three\n  4. new sub\n\
+            4. four\n5. five\n6. six\n7. seven\n8. eight\n9. nine\n10. ten";
+        assert_eq!(
+            skin.text(md, None).to_string(),
+            " 1. one\n 2. two\n  1. sub one\n  2. sub two\n 3. three\n  4. new sub\n\
+            \x204. four\n 5. five\n 6. six\n 7. seven\n 8. eight\n 9. nine\n10. ten\n",
+        );
+        // the list starts at the number of its first item
+        assert_eq!(
+            skin.text("5. five\n1. six\nnot in list\n1. one", None).to_string(),
+            "5. five\n6. six\nnot in list\n1. one\n",
+        );
+        // the width of the number is taken into account when wrapping
+        let text = skin.text("10. a b c d e f\n11. g", Some(7));
+        assert_eq!(text.to_string(), "10. a b\nc d e f\n11. g\n");
+    }
+
+    #[test]
+    fn check_wide_numbers_in_narrow_widths() {
+        let skin = MadSkin::no_style();
+        for md in ["10. ten", "100. hundred and more words", "10000. big and more words"] {
+            for width in 3..30 {
+                skin.text(md, Some(width));
+                skin.text(md, None).to_wrapped_plain_string(width);
+            }
+        }
+        // a number too wide for the width is replaced with the bullet
+        assert_eq!(skin.text("10. ten", Some(6)).to_string(), "• ten\n");
+    }
+}
@@ -0,0 +1,117 @@
+use minimad::{Composite, CompositeStyle};
+
+/// If the composite is a nested quote (like `> > text` or `>> text`),
+/// remove the additional quote marks, make sure the composite is a
+/// quote, and return the nesting depth.
+///
+/// Return 1 for a simple quote and 0 for a composite which isn't a quote.
+pub(crate) fn extract_quote_depth(composite: &mut Composite<'_>) -> usize {
+    let depth = match composite.style {
+        CompositeStyle::Quote => 1,
+        CompositeStyle::Paragraph => 0,
+        _ => { return 0; }
+    };
+    let first = match composite.compounds.first() {
+        Some(first) if !first.code => first,
+        _ => { return depth; }
+    };
+    let src = first.src;
+    let mut marks = 0;
+    let mut prefix_len = 0;
+    for (idx, c) in src.char_indices() {
+        match c {
+            '>' => {
+                marks += 1;
+                prefix_len = idx + 1;
+            }
+            ' ' if marks > 0 => {
+                prefix_len = idx + 1;
+            }
+            _ => { break; }
+        }
+    }
+    // the prefix must end with a space, and a paragraph is a quote
+    // only when it starts with `>>` (minimad already recognizing `> `)
+    if marks == 0 || !src[..prefix_len].ends_with(' ') {
+        return depth;
+    }
+    if depth == 0 && !src.starts_with(">>") {
+        return depth;
+    }
+    let tail = first.tail(prefix_len);
+    if tail.is_empty() {
+        composite.compounds.remove(0);
+    } else {
+        composite.compounds[0] = tail;
+    }
+    composite.style = CompositeStyle::Quote;
+    depth + marks
+}
+
+#[cfg(test)]
+mod quote_tests {
+
+    use {
+        super::*,
+        crate::*,
+    };
+
+    fn depth(md: &str) -> (usize, String) {
+        let mut composite = minimad::Text::from(md).lines.remove(0);
+        match &mut composite {
+            minimad::Line::Normal(composite) => {
+                let depth = extract_quote_depth(composite);
+                let text = composite.compounds.iter().map(|c| c.src).collect();
+                (depth, text)
+            }
+            _ => panic!("not a composite"),
+        }
+    }
+
+    #[test]
+    fn check_quote_depths() {
+        assert_eq!(depth("text"), (0, "text".to_string()));
+        assert_eq!(depth("> text"), (1, "text".to_string()));
+        assert_eq!(depth("> > text"), (2, "text".to_string()));
+        assert_eq!(depth(">> text"), (2, "text".to_string()));
+        assert_eq!(depth("> >> > **text**"), (4, "text".to_string()));
+        assert_eq!(depth("a > b"), (0, "a > b".to_string()));
+        assert_eq!(depth("> >text"), (1, ">text".to_string()));
+        assert_eq!(depth("> `> code`"), (1, "> code".to_string()));
+    }
+
+    #[test]
+    fn check_nested_quotes_rendering() {
+        let mut skin = MadSkin::no_style();
+        skin.quote_mark.set_char('|');
+        assert_eq!(
+            skin.text("> a\n> > b c d\n>> e", Some(8)).to_plain_string(),
+            "| a\n| | b c \n| | d\n| | e\n",
+        );
+        let mut dep2 = LineStyle::default();
+        dep2.set_fg(crossterm::style::Color::Red);
+        skin.set_quote_styles(vec![LineStyle::default(), dep2]);
+        let text = skin.text("> a\n> > b\n> > > c", None).to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "| a");
+        assert_eq!(lines[1], "| | \u{1b}[38;5;9mb\u{1b}[39m");
+        // depths beyond the styles get the last one
+        assert_eq!(lines[2], "| | | \u{1b}[38;5;9mc\u{1b}[39m");
+    }
+
+    #[test]
+    fn check_deep_quotes_in_narrow_widths() {
+        let mut skin = MadSkin::no_style();
+        skin.quote_mark.set_char('|');
+        let deep = format!("{} deep", ">".repeat(30));
+        for width in 3..70 {
+            skin.text(&deep, Some(width));
+            skin.text("> > > > > deep quote with words", Some(width));
+        }
+        // the deepest levels aren't drawn, so that the text fits
+        assert_eq!(
+            skin.text("> > > > > deep quote", Some(10)).to_plain_string(),
+            "| | | deep\n| | | quot\n| | | e\n",
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use minimad::{Compound, Line, LineParser, Text};
+
+/// Which markdown constructs are interpreted, for example to
+/// neutralize some of them in user-supplied markdown.
+///
+/// A construct which isn't allowed is rendered as literal text.
+/// All constructs are allowed by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// whether table rows and rules are rendered as tables
+    pub allow_tables: bool,
+    /// whether images are rendered as images (or placeholders)
+    pub allow_images: bool,
+    /// whether html, for example entities, is interpreted
+    pub allow_html: bool,
+    /// whether code fences and indented lines make code blocks
+    pub allow_code_blocks: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            allow_tables: true,
+            allow_images: true,
+            allow_html: true,
+            allow_code_blocks: true,
+        }
+    }
+}
+
+/// Return a line displaying the markdown as is
+fn literal_line(md_line: &str) -> Line<'_> {
+    if md_line.is_empty() {
+        Line::new_paragraph(Vec::new())
+    } else {
+        Line::new_paragraph(vec![Compound::raw_str(md_line)])
+    }
+}
+
+/// Tell whether the markdown line, out of a fenced block, is a line
+/// of an indented code block
+pub(crate) fn is_indented_code(md_line: &str) -> bool {
+    md_line.starts_with("    ") || md_line.starts_with('\t')
+}
+
+pub(crate) fn is_blank(line: &Line<'_>) -> bool {
+    match line {
+        Line::Normal(composite) => {
+            !composite.is_code() && composite.compounds.iter().all(|c| c.src.trim().is_empty())
+        }
+        _ => false,
+    }
+}
+
+/// Parse the markdown, the constructs which aren't allowed being
+/// kept as literal text.
+///
+/// Blank lines between the lines of an indented code block are
+/// parsed as empty code lines, so that the block isn't split.
+pub(crate) fn parse_text<'s>(md: &'s str, options: &RenderOptions) -> Text<'s> {
+    let mut lines = Vec::new();
+    let mut between_fences = false;
+    // the index after the last line of the current indented code block
+    let mut indented_code_end: Option<usize> = None;
+    for md_line in md.lines() {
+        if between_fences {
+            match LineParser::from(md_line).as_code() {
+                Line::CodeFence(..) => {
+                    between_fences = false;
+                }
+                line => {
+                    lines.push(line);
+                }
+            }
+            continue;
+        }
+        let line = Line::from(md_line);
+        if indented_code_end.is_some() && is_blank(&line) {
+            lines.push(line);
+            continue;
+        }
+        let code_end = indented_code_end.take();
+        match line {
+            Line::CodeFence(..) if options.allow_code_blocks => {
+                between_fences = true;
+            }
+            Line::CodeFence(..) => {
+                lines.push(literal_line(md_line));
+            }
+            Line::TableRow(_) | Line::TableRule(_) if !options.allow_tables => {
+                lines.push(literal_line(md_line));
+            }
+            Line::Normal(ref composite) if composite.is_code() && !options.allow_code_blocks => {
+                lines.push(literal_line(md_line));
+            }
+            Line::Normal(ref composite) if composite.is_code() => {
+                if let Some(end) = code_end {
+                    for blank in &mut lines[end..] {
+                        *blank = Line::new_code(Compound::raw_str("").code());
+                    }
+                }
+                lines.push(line);
+                indented_code_end = Some(lines.len());
+            }
+            _ => {
+                lines.push(line);
+            }
+        }
+    }
+    Text { lines }
+}
+
+#[cfg(test)]
+mod render_options_tests {
+
+    use crate::*;
+
+    #[test]
+    fn check_disallowed_constructs() {
+        let md = "|a|*b*|\n|-|-|\n```\nlet a = 1;\n```\n    indented\ntext";
+        let mut skin = MadSkin::no_style();
+        skin.code_block.align = Alignment::Left;
+        assert_eq!(
+            skin.text(md, None).to_plain_string(),
+            "│a│b│\n└─┴─┘\nlet a = 1;\nindented  \ntext\n",
+        );
+        skin.set_render_options(RenderOptions {
+            allow_tables: false,
+            ..Default::default()
+        });
+        assert_eq!(
+            skin.text(md, None).to_plain_string(),
+            "|a|*b*|\n|-|-|\nlet a = 1;\nindented  \ntext\n",
+        );
+        skin.set_render_options(RenderOptions {
+            allow_code_blocks: false,
+            ..Default::default()
+        });
+        assert_eq!(
+            skin.text(md, None).to_plain_string(),
+            "│a│b│\n└─┴─┘\n```\nlet a = 1;\n```\n    indented\ntext\n",
+        );
+        // the streamed lines are the same
+        let streamed: Vec<String> = skin
+            .render_lines(md, 20)
+            .map(|line| FmtText { skin: &skin, lines: vec![line], width: Some(20) }.to_plain_string())
+            .collect();
+        assert_eq!(streamed.concat(), skin.text(md, Some(20)).to_plain_string());
+    }
+}
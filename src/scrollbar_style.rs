@@ -6,8 +6,22 @@ use {
     crossterm::style::Color,
 };
 
+/// The side of the area where a scrollbar is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollBarSide {
+    Left,
+    Right,
+}
+
+impl Default for ScrollBarSide {
+    fn default() -> Self {
+        Self::Right
+    }
+}
+
 /// A scrollbar style defined by two styled chars, one
-///  for the track, and one for the thumb.
+///  for the track, and one for the thumb, and the side
+///  of the scrollbar.
 ///
 /// For the default styling only the fg color is defined
 ///  and the char is ▐ but everything can be changed.
@@ -15,6 +29,7 @@ use {
 pub struct ScrollBarStyle {
     pub track: StyledChar,
     pub thumb: StyledChar,
+    pub side: ScrollBarSide,
 }
 
 impl ScrollBarStyle {
@@ -23,6 +38,33 @@ impl ScrollBarStyle {
         Self {
             track: StyledChar::from_fg_char(gray(5), char),
             thumb: StyledChar::from_fg_char(gray(21), char),
+            side: ScrollBarSide::default(),
+        }
+    }
+    /// change the chars of the track and of the thumb,
+    /// keeping their styles
+    pub fn set_chars(&mut self, track: char, thumb: char) {
+        self.track.set_char(track);
+        self.thumb.set_char(thumb);
+    }
+    pub fn set_side(&mut self, side: ScrollBarSide) {
+        self.side = side;
+    }
+    pub fn is_left(&self) -> bool {
+        self.side == ScrollBarSide::Left
+    }
+    /// write the char of the row at `y`, given the top and
+    /// bottom of the thumb
+    pub(crate) fn queue_row<W: std::io::Write>(
+        &self,
+        w: &mut W,
+        y: u16,
+        (thumb_top, thumb_bottom): (u16, u16),
+    ) -> crate::errors::Result<()> {
+        if thumb_top <= y && y <= thumb_bottom {
+            self.thumb.queue(w)
+        } else {
+            self.track.queue(w)
         }
     }
     pub fn set_bg(&mut self, bg: Color) {
@@ -1,23 +1,38 @@
 use {
     crate::{
+        block_spacing::{BlockSpacer, BlockSpacing},
+        breaks,
+        image::ImageMode,
+        render_options::{self, RenderOptions},
         area::{terminal_size, Area},
         color::*,
-        composite::FmtComposite,
+        composite::{CompoundKind, FmtComposite},
         compound_style::CompoundStyle,
+        document::MarkdownDocument,
         errors::Result,
+        fit::{FitMode, WrapAlgorithm},
+        footnote,
         inline::FmtInline,
-        line::FmtLine,
+        line::{FmtLine, HorizontalRuleMode},
+        link::HyperlinkMode,
+        list::ListItemMark,
         line_style::LineStyle,
         scrollbar_style::ScrollBarStyle,
         spacing::Spacing,
+        stream,
         styled_char::StyledChar,
+        tab,
         tbl::*,
         text::FmtText,
+        toc::TocEntry,
         views::TextView,
+        SPACE_FILLING,
     },
     crossterm::{
+        cursor::MoveTo,
         queue,
         style::{Attribute, Color, Print},
+        QueueableCommand,
     },
     minimad::{
         Alignment,
@@ -31,9 +46,11 @@ use {
         TextTemplateExpander,
     },
     std::{
+        borrow::Cow,
         fmt,
         io::Write,
         collections::HashMap,
+        ops::Range,
     },
     unicode_width::UnicodeWidthStr,
 };
@@ -48,13 +65,77 @@ pub struct MadSkin {
     pub strikeout: CompoundStyle,
     pub inline_code: CompoundStyle,
     pub code_block: LineStyle,
+    /// alignment of block quotes, as a unit. When unspecified,
+    /// the alignment of the paragraph applies to every line
+    pub quote_align: Alignment,
+    /// the styles of quotes, by nesting depth (the paragraph
+    /// style is used when there's none)
+    pub quote_styles: Vec<LineStyle>,
     pub headers: [LineStyle; MAX_HEADER_DEPTH],
+    /// the marks displayed before the headers, per level
+    /// (empty by default)
+    pub header_marks: [&'static str; MAX_HEADER_DEPTH],
     pub scrollbar: ScrollBarStyle,
     pub table: LineStyle, // the compound style is for border chars
+    /// alignment of the cells of the columns whose alignment isn't
+    /// specified in the markdown
+    pub table_cell_align: Alignment,
+    /// constraints on the widths of the table columns, the first
+    /// one applying to the first column, etc.
+    pub table_column_constraints: Vec<ColWidth>,
     pub bullet: StyledChar,
+    pub checkbox_unchecked: StyledChar,
+    pub checkbox_checked: StyledChar,
+    pub list_number: CompoundStyle,
+    /// style of the numbers referencing footnotes
+    pub footnote_ref: CompoundStyle,
+    /// style of the numbers starting footnote definitions
+    pub footnote_def: CompoundStyle,
+    /// style of what's displayed for images
+    pub image: CompoundStyle,
     pub quote_mark: StyledChar,
     pub horizontal_rule: StyledChar,
+    /// whether horizontal rules take the whole width
+    pub horizontal_rule_mode: HorizontalRuleMode,
     pub ellipsis: CompoundStyle,
+    /// the string replacing the parts removed when fitting
+    pub ellipsis_str: Cow<'static, str>,
+    /// how markdown links are rendered
+    pub hyperlinks: HyperlinkMode,
+    /// how markdown images are rendered
+    pub image_mode: ImageMode,
+    /// whether the base direction of the text is right to left
+    pub rtl: bool,
+    /// whether the cells too wide for their column are wrapped
+    /// over several rows (or truncated)
+    pub table_wrap: bool,
+    /// which part of the cells is removed when they're truncated
+    /// (when None, it depends on their content)
+    pub table_fit_mode: Option<FitMode>,
+    /// the distance between tab stops when expanding tabs
+    pub tab_width: usize,
+    /// the blank lines around paragraphs, headers, code blocks and
+    /// lists (when None, the ones of the markdown are kept)
+    pub paragraph_spacing: Option<BlockSpacing>,
+    pub headers_spacing: Option<BlockSpacing>,
+    pub code_block_spacing: Option<BlockSpacing>,
+    pub list_spacing: Option<BlockSpacing>,
+    /// whether the lines of a code block are padded to the
+    /// width of the widest one
+    pub code_block_justify: bool,
+    /// how the lines too long for the width are wrapped
+    pub wrap_algorithm: WrapAlgorithm,
+    /// whether headers too long for the width are wrapped
+    /// (or truncated with an ellipsis)
+    pub headers_wrap: bool,
+    /// which markdown constructs are interpreted
+    pub render_options: RenderOptions,
+    /// whether html entities like `&amp;` are decoded (when html
+    /// is allowed by the render options)
+    pub html_entities: bool,
+    /// whether `:name:` shortcodes are replaced with emoji
+    #[cfg(feature="emoji")]
+    pub emoji_shortcodes: bool,
 
     /// compounds which should be replaced with special
     /// renders.
@@ -65,6 +146,48 @@ pub struct MadSkin {
     pub special_chars: HashMap<Compound<'static>, StyledChar>,
 }
 
+/// A style applied over the normal one on some ranges of chars of
+/// a line (for example to highlight search matches)
+pub(crate) struct Overlay<'o> {
+    pub style: &'o CompoundStyle,
+    pub ranges: &'o [Range<usize>],
+}
+
+impl Overlay<'_> {
+    /// write the text of a compound starting at the given char index
+    /// of the line, applying the overlay style to the chars in its ranges
+    fn write_compound(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        src: &str,
+        style: &CompoundStyle,
+        start: usize,
+    ) -> fmt::Result {
+        let end = start + src.chars().count();
+        // the byte index of the char at the given index of the line
+        let byte_idx = |char_idx: usize| {
+            src.char_indices().nth(char_idx - start).map_or(src.len(), |(idx, _)| idx)
+        };
+        let mut overlaid_style = style.clone();
+        overlaid_style.overwrite_with(self.style);
+        let mut idx = start;
+        while idx < end {
+            let (part_end, in_range) = match self.ranges.iter().find(|r| r.end > idx && r.start < end) {
+                Some(r) if r.start <= idx => (r.end.min(end), true),
+                Some(r) => (r.start, false),
+                None => (end, false),
+            };
+            // (not Compound::sub_chars, which can't give a part ending
+            // at the end of the compound)
+            let part = &src[byte_idx(idx)..byte_idx(part_end)];
+            let part_style = if in_range { &overlaid_style } else { style };
+            write!(f, "{}", part_style.apply_to(part))?;
+            idx = part_end;
+        }
+        Ok(())
+    }
+}
+
 impl Default for MadSkin {
     /// Build a customizable skin.
     ///
@@ -82,19 +205,49 @@ impl Default for MadSkin {
             strikeout: CompoundStyle::with_attr(Attribute::CrossedOut),
             inline_code: CompoundStyle::with_fgbg(gray(17), gray(3)),
             code_block: LineStyle::default(),
+            quote_align: Alignment::Unspecified,
+            quote_styles: Vec::new(),
             headers: Default::default(),
+            header_marks: [""; MAX_HEADER_DEPTH],
             scrollbar: ScrollBarStyle::new(),
             table: LineStyle {
                 compound_style: CompoundStyle::with_fg(gray(7)),
                 align: Alignment::Unspecified,
             },
+            table_cell_align: Alignment::Unspecified,
+            table_column_constraints: Vec::new(),
             bullet: StyledChar::from_fg_char(gray(8), '•'),
+            checkbox_unchecked: StyledChar::from_fg_char(gray(8), '☐'),
+            checkbox_checked: StyledChar::from_fg_char(gray(12), '☑'),
+            list_number: CompoundStyle::with_fg(gray(12)),
+            footnote_ref: CompoundStyle::with_fg(ansi(178)),
+            footnote_def: CompoundStyle::with_fg(gray(12)),
+            image: CompoundStyle::with_fg(gray(12)),
             quote_mark: StyledChar::new(
                 CompoundStyle::new(Some(gray(12)), None, Attribute::Bold.into()),
                 '▐',
             ),
             horizontal_rule: StyledChar::from_fg_char(gray(6), '―'),
+            horizontal_rule_mode: HorizontalRuleMode::default(),
             ellipsis: CompoundStyle::default(),
+            ellipsis_str: Cow::Borrowed(crate::fit::ELLIPSIS),
+            hyperlinks: HyperlinkMode::default(),
+            image_mode: ImageMode::default(),
+            rtl: false,
+            table_wrap: true,
+            table_fit_mode: None,
+            tab_width: 4,
+            html_entities: true,
+            paragraph_spacing: None,
+            headers_spacing: None,
+            code_block_spacing: None,
+            list_spacing: None,
+            code_block_justify: true,
+            wrap_algorithm: WrapAlgorithm::default(),
+            headers_wrap: true,
+            render_options: RenderOptions::default(),
+            #[cfg(feature="emoji")]
+            emoji_shortcodes: false,
             #[cfg(feature="special-renders")]
             special_chars: HashMap::new(),
         };
@@ -124,18 +277,114 @@ impl MadSkin {
             strikeout: CompoundStyle::default(),
             inline_code: CompoundStyle::default(),
             code_block: LineStyle::default(),
+            quote_align: Alignment::Unspecified,
+            quote_styles: Vec::new(),
             headers: Default::default(),
+            header_marks: [""; MAX_HEADER_DEPTH],
             scrollbar: ScrollBarStyle::new(),
             table: LineStyle::default(),
+            table_cell_align: Alignment::Unspecified,
+            table_column_constraints: Vec::new(),
             bullet: StyledChar::nude('•'),
+            checkbox_unchecked: StyledChar::nude('☐'),
+            checkbox_checked: StyledChar::nude('☑'),
+            list_number: CompoundStyle::default(),
+            footnote_ref: CompoundStyle::default(),
+            footnote_def: CompoundStyle::default(),
+            image: CompoundStyle::default(),
             quote_mark: StyledChar::nude('▐'),
             horizontal_rule: StyledChar::nude('―'),
+            horizontal_rule_mode: HorizontalRuleMode::default(),
             ellipsis: CompoundStyle::default(),
+            ellipsis_str: Cow::Borrowed(crate::fit::ELLIPSIS),
+            hyperlinks: HyperlinkMode::default(),
+            image_mode: ImageMode::default(),
+            rtl: false,
+            table_wrap: true,
+            table_fit_mode: None,
+            tab_width: 4,
+            html_entities: true,
+            paragraph_spacing: None,
+            headers_spacing: None,
+            code_block_spacing: None,
+            list_spacing: None,
+            code_block_justify: true,
+            wrap_algorithm: WrapAlgorithm::default(),
+            headers_wrap: true,
+            render_options: RenderOptions::default(),
+            #[cfg(feature="emoji")]
+            emoji_shortcodes: false,
             #[cfg(feature="special-renders")]
             special_chars: HashMap::new(),
         }
     }
 
+    /// Return a skin with the same chars, alignments and modes than this
+    /// one, but without any color or attribute.
+    ///
+    /// Texts written with this skin are laid out exactly the same, and
+    /// contain no escape sequence.
+    pub fn without_styles(&self) -> Self {
+        let mut skin = Self::no_style();
+        skin.paragraph.align = self.paragraph.align;
+        skin.code_block.align = self.code_block.align;
+        skin.quote_align = self.quote_align;
+        skin.quote_styles = self.quote_styles
+            .iter()
+            .map(|ls| LineStyle {
+                compound_style: CompoundStyle::default(),
+                align: ls.align,
+            })
+            .collect();
+        skin.rtl = self.rtl;
+        skin.table_wrap = self.table_wrap;
+        skin.table_fit_mode = self.table_fit_mode;
+        skin.tab_width = self.tab_width;
+        skin.html_entities = self.html_entities;
+        skin.paragraph_spacing = self.paragraph_spacing;
+        skin.headers_spacing = self.headers_spacing;
+        skin.code_block_spacing = self.code_block_spacing;
+        skin.list_spacing = self.list_spacing;
+        skin.code_block_justify = self.code_block_justify;
+        skin.wrap_algorithm = self.wrap_algorithm;
+        skin.headers_wrap = self.headers_wrap;
+        skin.render_options = self.render_options;
+        skin.ellipsis_str = self.ellipsis_str.clone();
+        skin.header_marks = self.header_marks;
+        for (header, src_header) in skin.headers.iter_mut().zip(&self.headers) {
+            header.align = src_header.align;
+        }
+        skin.table.align = self.table.align;
+        skin.table_cell_align = self.table_cell_align;
+        skin.table_column_constraints = self.table_column_constraints.clone();
+        skin.scrollbar.track = StyledChar::nude(self.scrollbar.track.get_char());
+        skin.scrollbar.thumb = StyledChar::nude(self.scrollbar.thumb.get_char());
+        skin.scrollbar.side = self.scrollbar.side;
+        skin.bullet = StyledChar::nude(self.bullet.get_char());
+        skin.checkbox_unchecked = StyledChar::nude(self.checkbox_unchecked.get_char());
+        skin.checkbox_checked = StyledChar::nude(self.checkbox_checked.get_char());
+        skin.quote_mark = StyledChar::nude(self.quote_mark.get_char());
+        skin.horizontal_rule = StyledChar::nude(self.horizontal_rule.get_char());
+        skin.horizontal_rule_mode = self.horizontal_rule_mode;
+        skin.image_mode = self.image_mode;
+        skin.hyperlinks = match self.hyperlinks {
+            HyperlinkMode::Osc8 => HyperlinkMode::Text,
+            mode => mode,
+        };
+        #[cfg(feature="emoji")]
+        {
+            skin.emoji_shortcodes = self.emoji_shortcodes;
+        }
+        #[cfg(feature="special-renders")]
+        {
+            skin.special_chars = self.special_chars
+                .iter()
+                .map(|(compound, sc)| (compound.clone(), StyledChar::nude(sc.get_char())))
+                .collect();
+        }
+        skin
+    }
+
     /// Build a customizable skin with gray levels suitable when the terminal has
     /// a dark background
     ///
@@ -166,6 +415,24 @@ impl MadSkin {
         skin
     }
 
+    /// Build a customizable skin suited to the background of the
+    /// terminal, that is the one of [MadSkin::default_light] when the
+    /// background looks light and the one of [MadSkin::default_dark]
+    /// otherwise.
+    ///
+    /// The background is guessed from the `COLORFGBG` environment
+    /// variable, which some terminals set. When it's missing or can't
+    /// be understood, the background is assumed to be dark. For a more
+    /// reliable detection you may query the terminal with the
+    /// [terminal-light](https://docs.rs/terminal-light/) crate.
+    pub fn adaptive() -> Self {
+        if terminal_has_light_background().unwrap_or(false) {
+            Self::default_light()
+        } else {
+            Self::default_dark()
+        }
+    }
+
     /// Change the foreground of most styles (the ones which commonly
     /// have a default or uniform baground, don't change code styles
     /// for example).
@@ -179,6 +446,12 @@ impl MadSkin {
         self.strikeout.set_fg(fg);
         self.set_headers_fg(fg);
         self.bullet.set_fg(fg);
+        self.checkbox_unchecked.set_fg(fg);
+        self.checkbox_checked.set_fg(fg);
+        self.list_number.set_fg(fg);
+        self.footnote_ref.set_fg(fg);
+        self.footnote_def.set_fg(fg);
+        self.image.set_fg(fg);
         self.quote_mark.set_fg(fg);
         self.horizontal_rule.set_fg(fg);
         self.ellipsis.set_fg(fg);
@@ -204,6 +477,12 @@ impl MadSkin {
         self.set_headers_bg(bg);
         self.table.compound_style.set_bg(bg);
         self.bullet.set_bg(bg);
+        self.checkbox_unchecked.set_bg(bg);
+        self.checkbox_checked.set_bg(bg);
+        self.list_number.set_bg(bg);
+        self.footnote_ref.set_bg(bg);
+        self.footnote_def.set_bg(bg);
+        self.image.set_bg(bg);
         self.quote_mark.set_bg(bg);
         self.horizontal_rule.set_bg(bg);
         self.ellipsis.set_bg(bg);
@@ -216,6 +495,23 @@ impl MadSkin {
         }
     }
 
+    /// Set the mark displayed before the headers of the given level
+    /// (starting at 1), for example `"▌ "`, with the style of the header.
+    pub fn set_header_mark(&mut self, level: usize, mark: &'static str) {
+        if level > 0 && level <= MAX_HEADER_DEPTH {
+            self.header_marks[level - 1] = mark;
+        }
+    }
+
+    /// Display the level of headers with their markdown marks,
+    /// `"# "` for the first level, `"## "` for the second one, etc.
+    pub fn set_hash_header_marks(&mut self) {
+        static HASHES: &str = "######## ";
+        for (level, mark) in self.header_marks.iter_mut().enumerate() {
+            *mark = &HASHES[MAX_HEADER_DEPTH - level - 1..];
+        }
+    }
+
     /// Set a common foreground color for all header levels
     ///
     /// (it's still possible to change them individually with
@@ -244,6 +540,203 @@ impl MadSkin {
         self.horizontal_rule.set_bg(c);
     }
 
+    /// set the way markdown links (`[text](url)`) are rendered.
+    ///
+    /// By default they're not interpreted. With `HyperlinkMode::Osc8`,
+    /// the link text is wrapped in escape sequences making it
+    /// clickable in the terminals supporting them.
+    pub fn set_hyperlinks(&mut self, mode: HyperlinkMode) {
+        self.hyperlinks = mode;
+    }
+
+    /// set the way markdown images (`![alt](url)`) are rendered.
+    ///
+    /// By default they're replaced with a placeholder showing their
+    /// alt text and url, with the `image` style.
+    pub fn set_image_mode(&mut self, mode: ImageMode) {
+        self.image_mode = mode;
+    }
+
+    /// Set the styles of quotes by nesting depth, the first one being
+    /// the style of simple quotes, the second one the style of quotes
+    /// in quotes, etc. Quotes deeper than the number of styles get
+    /// the last one.
+    pub fn set_quote_styles(&mut self, styles: Vec<LineStyle>) {
+        self.quote_styles = styles;
+    }
+
+    /// Set the char and style of horizontal rules, for example
+    /// `StyledChar::from_fg_char(Color::Blue, '═')`
+    pub fn set_horizontal_rule(&mut self, rule: StyledChar) {
+        self.horizontal_rule = rule;
+    }
+
+    /// Set whether horizontal rules take the whole width of the
+    /// text or only a centered part of it
+    pub fn set_horizontal_rule_mode(&mut self, mode: HorizontalRuleMode) {
+        self.horizontal_rule_mode = mode;
+    }
+
+    /// Set whether the base direction of the text is right to left
+    /// (for example for Arabic or Hebrew).
+    ///
+    /// This only changes the defaults: lines whose alignment is
+    /// unspecified are aligned to the right and, when a composite
+    /// must be truncated, the ellipsis is put at its start.
+    /// There's no bidirectional reordering nor shaping, this is
+    /// left to the terminal.
+    pub fn set_rtl(&mut self, b: bool) {
+        self.rtl = b;
+    }
+
+    /// Set the alignment of the cells of the table columns whose
+    /// alignment isn't specified in the markdown (with `:--`, `:-:`
+    /// or `--:` in the separator line).
+    ///
+    /// Header cells are centered unless an alignment is specified
+    /// for their column, either in the markdown or with this setting.
+    pub fn set_table_cell_align(&mut self, align: Alignment) {
+        self.table_cell_align = align;
+    }
+
+    /// Set constraints on the widths of the table columns, the first
+    /// one applying to the first column, etc. Columns without
+    /// constraint are `ColWidth::Auto`.
+    ///
+    /// When a table is too wide, the `Auto` columns are reduced
+    /// first, then the `Min` (down to their minimum) and `Max` ones.
+    /// The `Fixed` columns are only reduced when there's no other
+    /// way to fit the table.
+    pub fn set_table_column_constraints(&mut self, constraints: Vec<ColWidth>) {
+        self.table_column_constraints = constraints;
+    }
+
+    /// Set whether the content of table cells too wide for their
+    /// column is wrapped over several rows, which is the default,
+    /// or truncated with an ellipsis, keeping one row per table row.
+    ///
+    /// When wrapping, the vertical separators are repeated on all the
+    /// rows of a cell and the following rule comes after the last one.
+    pub fn set_table_wrap(&mut self, b: bool) {
+        self.table_wrap = b;
+    }
+
+    /// Set which part of the table cells is removed when they're
+    /// truncated instead of wrapped, for example `FitMode::Middle`
+    /// for cells containing paths.
+    pub fn set_table_fit_mode(&mut self, mode: Option<FitMode>) {
+        self.table_fit_mode = mode;
+    }
+
+    /// Set the distance between tab stops (4 by default), tabs being
+    /// replaced with the spaces needed to reach the next stop.
+    ///
+    /// It can't be more than 32.
+    pub fn set_tab_width(&mut self, n: usize) {
+        self.tab_width = n.min(tab::MAX_TAB_WIDTH);
+    }
+
+    /// Set the number of blank lines before and after paragraphs,
+    /// replacing the ones of the markdown.
+    ///
+    /// Between two blocks, the biggest of their spacings is used, so
+    /// zeros everywhere give a compact output.
+    pub fn set_paragraph_spacing(&mut self, before: usize, after: usize) {
+        self.paragraph_spacing = Some(BlockSpacing::new(before, after));
+    }
+
+    /// Set the number of blank lines before and after headers
+    pub fn set_headers_spacing(&mut self, before: usize, after: usize) {
+        self.headers_spacing = Some(BlockSpacing::new(before, after));
+    }
+
+    /// Set the number of blank lines before and after code blocks
+    pub fn set_code_block_spacing(&mut self, before: usize, after: usize) {
+        self.code_block_spacing = Some(BlockSpacing::new(before, after));
+    }
+
+    /// Set the number of blank lines before and after lists
+    pub fn set_list_spacing(&mut self, before: usize, after: usize) {
+        self.list_spacing = Some(BlockSpacing::new(before, after));
+    }
+
+    /// Set whether the lines of code blocks are padded so that the
+    /// block is a rectangle (which is the default)
+    pub fn set_code_block_justify(&mut self, b: bool) {
+        self.code_block_justify = b;
+    }
+
+    /// Set the algorithm used to wrap the lines too long for the width:
+    /// `WrapAlgorithm::Greedy` by default, or `WrapAlgorithm::Balanced`
+    /// for lines of more similar widths.
+    pub fn set_wrap_algorithm(&mut self, algo: WrapAlgorithm) {
+        self.wrap_algorithm = algo;
+    }
+
+    /// Set whether headers too long for the width are wrapped, which
+    /// is the default, or truncated with an ellipsis
+    pub fn set_headers_wrap(&mut self, b: bool) {
+        self.headers_wrap = b;
+    }
+
+    /// Set whether texts are rendered in a compact way, for example
+    /// for status panels: no blank line between blocks, code blocks
+    /// which aren't padded, and single-line headers.
+    ///
+    /// This is a shortcut for the spacing, justification and wrapping
+    /// settings, `set_compact(false)` restoring their defaults.
+    pub fn set_compact(&mut self, b: bool) {
+        let spacing = if b { Some(BlockSpacing::new(0, 0)) } else { None };
+        self.paragraph_spacing = spacing;
+        self.headers_spacing = spacing;
+        self.code_block_spacing = spacing;
+        self.list_spacing = spacing;
+        self.code_block_justify = !b;
+        self.headers_wrap = !b;
+    }
+
+    /// Set which markdown constructs are interpreted, the other
+    /// ones being rendered as literal text.
+    ///
+    /// This is useful when rendering markdown coming from untrusted
+    /// sources. The options are applied when the markdown is parsed,
+    /// so not to a `minimad::Text` given to `FmtText::from_text`.
+    pub fn set_render_options(&mut self, options: RenderOptions) {
+        self.render_options = options;
+    }
+
+    /// Set the string replacing the parts removed when a composite
+    /// must be shortened to fit a width (`"…"` by default), for
+    /// example `"..."` for terminals without a good unicode support.
+    ///
+    /// It's also used by the input fields built afterwards.
+    pub fn set_ellipsis_str<S: Into<Cow<'static, str>>>(&mut self, ellipsis: S) {
+        self.ellipsis_str = ellipsis.into();
+    }
+
+    /// Return the alignment to apply for the given one, taking
+    /// the base direction into account
+    pub(crate) fn directed_align(&self, align: Alignment) -> Alignment {
+        match align {
+            Alignment::Unspecified if self.rtl => Alignment::Right,
+            _ => align,
+        }
+    }
+
+    /// Set whether html entities, named like `&lt;` or numeric like
+    /// `&#x1F600;`, are replaced with the chars they stand for, which
+    /// is the default (invalid entities are left as is).
+    pub fn set_html_entities(&mut self, b: bool) {
+        self.html_entities = b;
+    }
+
+    /// Set whether shortcodes like `:smile:` are replaced with
+    /// the corresponding emoji (unknown ones are left as is)
+    #[cfg(feature="emoji")]
+    pub fn set_emoji_shortcodes(&mut self, b: bool) {
+        self.emoji_shortcodes = b;
+    }
+
     /// Return the number of visible chars in a composite
     pub fn visible_composite_length(&self, composite: &Composite<'_>) -> usize {
         let compounds_width: usize = composite.compounds
@@ -257,6 +750,21 @@ impl MadSkin {
         }) + compounds_width
     }
 
+    /// Return the width the markdown would take when rendered without
+    /// wrapping, that is the width of its widest line.
+    ///
+    /// This takes into account the bullets, quote marks, table borders
+    /// and code block paddings, and can be used to size an area
+    /// to fit the content.
+    pub fn visible_width(&self, markdown: &str) -> usize {
+        self.text(markdown, None)
+            .lines
+            .iter()
+            .map(FmtLine::visible_length)
+            .max()
+            .unwrap_or(0)
+    }
+
     pub fn visible_line_length(&self, line: &Line<'_>) -> usize {
         match line {
             Line::Normal(composite) => self.visible_composite_length(composite),
@@ -275,12 +783,19 @@ impl MadSkin {
         }
     }
 
+    /// return the style to apply to a given composite, which
+    /// depends on the nesting depth for a quote
+    fn fmt_composite_line_style(&self, fc: &FmtComposite<'_>) -> &LineStyle {
+        if fc.composite.is_quote() && !self.quote_styles.is_empty() {
+            let idx = fc.quote_depth.clamp(1, self.quote_styles.len()) - 1;
+            return &self.quote_styles[idx];
+        }
+        self.line_style(&fc.composite.style)
+    }
+
     /// return the style appliable to a given compound.
     /// It's a composition of the various appliable base styles.
     fn compound_style(&self, line_style: &LineStyle, compound: &Compound<'_>) -> CompoundStyle {
-        if *compound.src == *crate::fit::ELLIPSIS {
-            return self.ellipsis.clone();
-        }
         let mut os = line_style.compound_style.clone();
         if compound.italic {
             os.overwrite_with(&self.italic);
@@ -291,7 +806,8 @@ impl MadSkin {
         if compound.bold {
             os.overwrite_with(&self.bold);
         }
-        if compound.code {
+        // the inline_code style doesn't apply to the content of code blocks
+        if compound.code && !std::ptr::eq(line_style, &self.code_block) {
             os.overwrite_with(&self.inline_code);
         }
         os
@@ -308,18 +824,74 @@ impl MadSkin {
         }
     }
 
+    /// parse the markdown according to the render options of the
+    /// skin, into a document which can be walked, then rendered
+    /// with `document_text`
+    pub fn parse<'s>(&self, markdown: &'s str) -> MarkdownDocument<'s> {
+        let text = render_options::parse_text(markdown, &self.render_options);
+        MarkdownDocument::new(markdown, text.lines)
+    }
+
+    /// return the table of contents of the markdown, with the level,
+    /// text and anchor of every header
+    pub fn table_of_contents(&self, markdown: &str) -> Vec<TocEntry> {
+        self.parse(markdown).table_of_contents()
+    }
+
+    /// return a formatted text from an already parsed document
+    pub fn document_text<'k: 's, 's>(
+        &'k self,
+        document: &MarkdownDocument<'s>,
+        width: Option<usize>,
+    ) -> FmtText<'k, 's> {
+        FmtText::from_document(self, document, width)
+    }
+
     /// return a formatted text.
     ///
     /// Code blocs will be right justified
-    pub fn text<'k, 's>(&'k self, src: &'s str, width: Option<usize>) -> FmtText<'k, 's> {
+    pub fn text<'k: 's, 's>(&'k self, src: &'s str, width: Option<usize>) -> FmtText<'k, 's> {
         FmtText::from(self, src, width)
     }
 
+    /// return the lines of a formatted text, lazily laid out for the
+    /// given width.
+    ///
+    /// The markdown is cut into blocks separated by blank lines (outside
+    /// of code blocks) which are formatted one after the other, so that
+    /// displaying the start of a huge document doesn't need the whole of it
+    /// to be parsed. Lists, tables and code blocks being aligned per block,
+    /// the lines are the same than the ones of `text(markdown, Some(width))`
+    /// but footnotes must be defined in the block referencing them.
+    ///
+    /// width must be greater than 2.
+    pub fn render_lines<'a>(
+        &'a self,
+        markdown: &'a str,
+        width: usize,
+    ) -> impl Iterator<Item = FmtLine<'a>> + 'a {
+        let mut spacer = BlockSpacer::new();
+        stream::Blocks::new(markdown)
+            .map(Some)
+            .chain(std::iter::once(None)) // to get the blank lines at the end
+            .flat_map(move |block| {
+                let (lines, footnotes) = match block {
+                    Some(block) => {
+                        let mut lines = render_options::parse_text(block, &self.render_options).lines;
+                        let footnotes = footnote::extract_footnotes(&mut lines);
+                        (spacer.space_lines(self, lines), footnotes)
+                    }
+                    None => (spacer.finish(), Vec::new()),
+                };
+                FmtText::from_prepared_lines(self, lines, &footnotes, Some(width), width).lines
+            })
+    }
+
     /// return a formatted text, with lines wrapped or justified for the current terminal
     /// width.
     ///
     /// Code blocs will be right justified
-    pub fn term_text<'k, 's>(&'k self, src: &'s str) -> FmtText<'k, 's> {
+    pub fn term_text<'k: 's, 's>(&'k self, src: &'s str) -> FmtText<'k, 's> {
         let (width, _) = terminal_size();
         FmtText::from(self, src, Some(width as usize))
     }
@@ -328,7 +900,7 @@ impl MadSkin {
     /// passed area width (with space for a scrollbar).
     ///
     /// Code blocs will be right justified
-    pub fn area_text<'k, 's>(&'k self, src: &'s str, area: &Area) -> FmtText<'k, 's> {
+    pub fn area_text<'k: 's, 's>(&'k self, src: &'s str, area: &Area) -> FmtText<'k, 's> {
         FmtText::from(self, src, Some(area.width as usize - 1))
     }
 
@@ -339,14 +911,26 @@ impl MadSkin {
         Ok(())
     }
 
-    /// queue the rendered markdown in the specified area, without flush
+    /// queue the rendered markdown in the specified area, without flush.
+    ///
+    /// The text is wrapped to the width of the area (no column is kept
+    /// for a scrollbar), the rows after its end are filled with spaces
+    /// and the lines which don't fit the height of the area are dropped.
     pub fn write_in_area_on<W: Write>(
         &self,
         w: &mut W,
         markdown: &str,
         area: &Area,
     ) -> Result<()> {
-        let text = self.area_text(markdown, area);
+        if area.width < 3 {
+            // too narrow for the text to be wrapped
+            for y in area.top..area.top + area.height {
+                w.queue(MoveTo(area.left, y))?;
+                SPACE_FILLING.queue_styled(w, &self.paragraph.compound_style, area.width as usize)?;
+            }
+            return Ok(());
+        }
+        let text = FmtText::from(self, markdown, Some(area.width as usize));
         let mut view = TextView::from(area, &text);
         view.show_scrollbar = false;
         view.write_on(w)
@@ -475,33 +1059,104 @@ impl MadSkin {
         outer_width: Option<usize>,
         with_right_completion: bool,
     ) -> fmt::Result {
-        let ls = self.line_style(&fc.composite.style);
+        self.write_overlaid_fmt_composite(f, fc, outer_width, with_right_completion, None)
+    }
+
+    /// Write a composite, with an optional overlay whose ranges start
+    /// at the given char index in the composite
+    fn write_overlaid_fmt_composite(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        fc: &FmtComposite<'_>,
+        outer_width: Option<usize>,
+        with_right_completion: bool,
+        overlay: Option<(&Overlay<'_>, usize)>,
+    ) -> fmt::Result {
+        let ls = self.fmt_composite_line_style(fc);
         let (lpi, rpi) = fc.completions(); // inner completion
         let inner_width = fc.spacing.map_or(fc.visible_length, |sp| sp.width);
-        let (lpo, rpo) = Spacing::optional_completions(ls.align, inner_width, outer_width);
+        let align = match self.quote_align {
+            Alignment::Unspecified => ls.align,
+            _ if fc.composite.is_quote() => self.quote_align,
+            _ => ls.align,
+        };
+        let align = self.directed_align(align);
+        let (lpo, rpo) = Spacing::optional_completions(align, inner_width, outer_width);
         self.paragraph.repeat_space(f, lpo)?;
         ls.compound_style.repeat_space(f, lpi)?;
         if fc.composite.is_list_item() {
-            write!(f, "{}", self.bullet)?;
+            match fc.list_mark {
+                Some(ListItemMark::Checkbox { checked: true }) => {
+                    write!(f, "{}", self.checkbox_checked)?;
+                }
+                Some(ListItemMark::Checkbox { checked: false }) => {
+                    write!(f, "{}", self.checkbox_unchecked)?;
+                }
+                Some(ListItemMark::Number { number, width, indent }) => {
+                    self.paragraph.repeat_space(f, indent)?;
+                    let number = format!("{:>width$}.", number, width = width);
+                    write!(f, "{}", self.list_number.apply_to(number))?;
+                }
+                None => {
+                    write!(f, "{}", self.bullet)?;
+                }
+            }
             write!(f, "{}", self.paragraph.compound_style.apply_to(' '))?;
         }
         if fc.composite.is_quote() {
-            write!(f, "{}", self.quote_mark)?;
-            write!(f, "{}", self.paragraph.compound_style.apply_to(' '))?;
+            for _ in 0..fc.quote_depth.max(1) {
+                write!(f, "{}", self.quote_mark)?;
+                write!(f, "{}", self.paragraph.compound_style.apply_to(' '))?;
+            }
         }
-        #[cfg(feature="special-renders")]
-        for c in &fc.composite.compounds {
+        let osc8 = self.hyperlinks == HyperlinkMode::Osc8;
+        let mut current_url = None;
+        let mut char_idx = overlay.map_or(0, |(_, start)| start);
+        for (idx, c) in fc.composite.compounds.iter().enumerate() {
+            if osc8 {
+                let url = fc.link_url(idx);
+                if url != current_url {
+                    if current_url.is_some() {
+                        write!(f, "\u{1b}]8;;\u{1b}\\")?;
+                    }
+                    if let Some(url) = url {
+                        write!(f, "\u{1b}]8;;{}\u{1b}\\", url)?;
+                    }
+                    current_url = url;
+                }
+            }
+            if breaks::is_soft_hyphen(c) {
+                char_idx += c.char_length();
+                continue;
+            }
+            let mut os = if fc.has_kind(idx, CompoundKind::Ellipsis) {
+                self.ellipsis.clone()
+            } else {
+                self.compound_style(ls, c)
+            };
+            if fc.is_image_part(idx) {
+                os.overwrite_with(&self.image);
+            }
+            if fc.has_kind(idx, CompoundKind::FootnoteRef) {
+                os.overwrite_with(&self.footnote_ref);
+            } else if fc.has_kind(idx, CompoundKind::FootnoteDef) {
+                os.overwrite_with(&self.footnote_def);
+            }
+            #[cfg(feature="special-renders")]
             if let Some(replacement) = self.special_chars.get(c) {
                 write!(f, "{}", replacement)?;
-            } else {
-                let os = self.compound_style(ls, c);
-                write!(f, "{}", os.apply_to(c.as_str()))?;
+                char_idx += c.char_length();
+                continue;
+            }
+            let text = fc.compound_text(idx);
+            match overlay {
+                Some((overlay, _)) => overlay.write_compound(f, &text, &os, char_idx)?,
+                None => write!(f, "{}", os.apply_to(&*text))?,
             }
+            char_idx += c.char_length();
         }
-        #[cfg(not(feature="special-renders"))]
-        for c in &fc.composite.compounds {
-            let os = self.compound_style(ls, c);
-            write!(f, "{}", os.apply_to(c.as_str()))?;
+        if current_url.is_some() {
+            write!(f, "\u{1b}]8;;\u{1b}\\")?;
         }
         ls.compound_style.repeat_space(f, rpi)?;
         if with_right_completion {
@@ -523,10 +1178,23 @@ impl MadSkin {
         line: &FmtLine<'_>,
         width: Option<usize>,
         with_right_completion: bool,
+    ) -> fmt::Result {
+        self.write_overlaid_fmt_line(f, line, width, with_right_completion, None)
+    }
+
+    /// Write a line, with an optional overlay
+    pub(crate) fn write_overlaid_fmt_line(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        line: &FmtLine<'_>,
+        width: Option<usize>,
+        with_right_completion: bool,
+        overlay: Option<&Overlay<'_>>,
     ) -> fmt::Result {
         match line {
             FmtLine::Normal(fc) => {
-                self.write_fmt_composite(f, fc, width, with_right_completion)?;
+                let overlay = overlay.map(|overlay| (overlay, 0));
+                self.write_overlaid_fmt_composite(f, fc, width, with_right_completion, overlay)?;
             }
             FmtLine::TableRow(FmtTableRow { cells }) => {
                 let tbl_width = 1 + cells.iter().fold(0, |sum, cell| {
@@ -538,9 +1206,12 @@ impl MadSkin {
                 });
                 let (lpo, rpo) = Spacing::optional_completions(self.table.align, tbl_width, width);
                 self.paragraph.repeat_space(f, lpo)?;
+                let mut char_idx = 0;
                 for cell in cells {
                     write!(f, "{}", self.table.compound_style.apply_to("│"))?;
-                    self.write_fmt_composite(f, cell, None, false)?;
+                    let cell_overlay = overlay.map(|overlay| (overlay, char_idx));
+                    self.write_overlaid_fmt_composite(f, cell, None, false, cell_overlay)?;
+                    char_idx += cell.composite.char_length() + 1;
                 }
                 write!(f, "{}", self.table.compound_style.apply_to("│"))?;
                 if with_right_completion {
@@ -589,10 +1260,108 @@ impl MadSkin {
             }
             FmtLine::HorizontalRule => {
                 if let Some(w) = width {
-                    write!(f, "{}", self.horizontal_rule.repeated(w))?;
+                    let rule_width = match self.horizontal_rule_mode {
+                        HorizontalRuleMode::Full => w,
+                        HorizontalRuleMode::Centered(rule_width) => rule_width.min(w),
+                    };
+                    let (lp, rp) = Spacing::completions(Alignment::Center, rule_width, w);
+                    self.paragraph.repeat_space(f, lp)?;
+                    write!(f, "{}", self.horizontal_rule.repeated(rule_width))?;
+                    if with_right_completion {
+                        self.paragraph.repeat_space(f, rp)?;
+                    }
                 }
             }
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod skin_tests {
+
+    use {
+        super::*,
+        crossterm::style::Color,
+    };
+
+    #[test]
+    fn check_strikeout_composition() {
+        let mut skin = MadSkin::default();
+        skin.strikeout.set_fg(Color::Red);
+        let composite = Composite::from_inline("***~~nested~~*** ~~alone~~");
+        assert_eq!(skin.visible_composite_length(&composite), 12);
+        let nested = skin.compound_style(&skin.paragraph, &composite.compounds[0]);
+        let attributes = nested.object_style.attributes;
+        assert!(attributes.has(Attribute::CrossedOut));
+        assert!(attributes.has(Attribute::Bold));
+        assert!(attributes.has(Attribute::Italic));
+        assert_eq!(nested.get_fg(), Some(Color::Red));
+        let alone = skin.compound_style(&skin.paragraph, &composite.compounds[2]);
+        assert!(alone.object_style.attributes.has(Attribute::CrossedOut));
+        assert!(!alone.object_style.attributes.has(Attribute::Bold));
+    }
+
+    #[test]
+    fn check_visible_width() {
+        let skin = MadSkin::default();
+        assert_eq!(skin.visible_width(""), 0);
+        assert_eq!(skin.visible_width("**bold** and 漢字"), 13);
+        assert_eq!(skin.visible_width("* item\n> quote"), 7);
+        assert_eq!(skin.visible_width("|a|bcd|\n|-|-|\n|efg|h|"), 9);
+        // code blocks are padded to the width of their widest line
+        assert_eq!(skin.visible_width("a\n```\nxxxxxxxxxx\nx\n```"), 10);
+    }
+    #[test]
+    fn check_write_in_area() {
+        let skin = MadSkin::no_style();
+        let written = |md: &str, area: Area| {
+            let mut w = Vec::new();
+            skin.write_in_area_on(&mut w, md, &area).unwrap();
+            String::from_utf8(w).unwrap()
+        };
+        // wrapped on the whole width, the remaining rows being filled
+        assert_eq!(
+            written("one two", Area::new(2, 1, 4, 3)),
+            "\u{1b}[2;3Hone \u{1b}[3;3Htwo \u{1b}[4;3H    ",
+        );
+        // the lines which don't fit aren't written
+        assert_eq!(
+            written("a\nb\nc", Area::new(0, 0, 3, 2)),
+            "\u{1b}[1;1Ha  \u{1b}[2;1Hb  ",
+        );
+        assert_eq!(written("abc", Area::new(0, 0, 2, 1)), "\u{1b}[1;1H  ");
+    }
+
+    #[test]
+    fn check_horizontal_rules() {
+        let mut skin = MadSkin::no_style();
+        skin.set_horizontal_rule(StyledChar::nude('═'));
+        assert_eq!(skin.text("a\n---\nb", Some(7)).to_string(), "a\n═══════\nb\n");
+        skin.set_horizontal_rule_mode(HorizontalRuleMode::Centered(3));
+        assert_eq!(skin.text("---", Some(7)).to_string(), "  ═══\n");
+        skin.set_horizontal_rule_mode(HorizontalRuleMode::Centered(5));
+        assert_eq!(skin.text("---", Some(4)).to_string(), "════\n");
+    }
+
+    #[test]
+    fn check_inline_code_style() {
+        let mut skin = MadSkin::no_style();
+        skin.inline_code.set_fg(Color::Red);
+        skin.code_block.set_fg(Color::Blue);
+        let red = "\u{1b}[38;5;9m";
+        let blue = "\u{1b}[38;5;12m";
+        let text = skin.text("a `b` c\n```\nfn d() {}\n```\n    e `f`", None).to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], format!("a {}b\u{1b}[39m c", red));
+        assert!(lines[1].contains(blue) && !lines[1].contains(red));
+        assert!(lines[2].contains(blue) && !lines[2].contains(red));
+        // inline code is measured and wrapped like normal text
+        let composite = Composite::from_inline("a `bcd` e");
+        assert_eq!(skin.visible_composite_length(&composite), 7);
+        assert_eq!(
+            skin.text("aaa `bb cc` dd", Some(7)).to_plain_string(),
+            skin.text("aaa bb cc dd", Some(7)).to_plain_string(),
+        );
+    }
+}
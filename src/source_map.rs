@@ -0,0 +1,203 @@
+use {
+    crate::{
+        composite::FmtComposite,
+        displayable_line::DisplayableLine,
+        line::FmtLine,
+        skin::MadSkin,
+    },
+    minimad::Compound,
+    std::ops::Range,
+    unicode_width::UnicodeWidthChar,
+};
+
+/// Return the byte range of the source of the compound in the
+/// markdown, if it's a part of it (and not for example an ellipsis)
+fn source_range(markdown: &str, compound: &Compound<'_>) -> Option<(usize, usize)> {
+    let md_start = markdown.as_ptr() as usize;
+    let start = (compound.src.as_ptr() as usize).checked_sub(md_start)?;
+    let end = start + compound.src.len();
+    if compound.src.is_empty() || end > markdown.len() {
+        None
+    } else {
+        Some((start, end))
+    }
+}
+
+/// A compound of a line, found in its rendering
+pub(crate) struct CompoundColumn<'l, 's> {
+    /// the composite (the line or a table cell) of the compound
+    pub fc: &'l FmtComposite<'s>,
+    /// the index of the compound in the composite
+    pub idx: usize,
+    /// the column the compound starts at
+    pub col: usize,
+}
+
+impl<'s> CompoundColumn<'_, 's> {
+    pub fn compound(&self) -> &Compound<'s> {
+        &self.fc.composite.compounds[self.idx]
+    }
+}
+
+/// Return the compounds of the line found in its rendering with the
+/// given width, with the column each one starts at.
+///
+/// The compounds are looked for, in order, in the rendered line
+/// (a compound may be missing when its text isn't what's displayed,
+/// for example an emoji shortcode).
+pub(crate) fn compound_columns<'l, 's>(
+    skin: &MadSkin,
+    line: &'l FmtLine<'s>,
+    width: Option<usize>,
+) -> Vec<CompoundColumn<'l, 's>> {
+    let composites = match line {
+        FmtLine::Normal(fc) => std::slice::from_ref(fc),
+        FmtLine::TableRow(row) => &row.cells[..],
+        _ => { return Vec::new(); }
+    };
+    let skin = skin.without_styles();
+    let rendered = DisplayableLine::new(&skin, line, width).to_string();
+    // the column at which each char of the rendered line starts
+    let mut char_cols = Vec::new();
+    let mut rendered_width = 0;
+    for (idx, c) in rendered.char_indices() {
+        char_cols.push((idx, rendered_width));
+        rendered_width += c.width().unwrap_or(0);
+    }
+    let col_of = |byte_idx: usize| {
+        char_cols
+            .iter()
+            .find(|(idx, _)| *idx >= byte_idx)
+            .map_or(rendered_width, |(_, col)| *col)
+    };
+    let mut searched = 0; // where to search the next compound in the rendered line
+    let mut found_compounds = Vec::new();
+    for fc in composites {
+        for idx in 0..fc.composite.compounds.len() {
+            let text = fc.compound_text(idx);
+            if text.is_empty() {
+                continue;
+            }
+            if let Some(found) = rendered[searched..].find(&*text) {
+                let found = searched + found;
+                searched = found + text.len();
+                found_compounds.push(CompoundColumn { fc, idx, col: col_of(found) });
+            }
+        }
+    }
+    found_compounds
+}
+
+/// Return the ranges, in chars of the line as counted by overlays,
+/// of the compounds rendered in the given columns
+pub(crate) fn char_ranges_in_columns(
+    skin: &MadSkin,
+    line: &FmtLine<'_>,
+    width: Option<usize>,
+    cols: &Range<usize>,
+) -> Vec<Range<usize>> {
+    let columns = compound_columns(skin, line, width);
+    let in_cols = |fc: &FmtComposite<'_>, idx: usize| {
+        columns
+            .iter()
+            .any(|cc| std::ptr::eq(cc.fc, fc) && cc.idx == idx && cols.contains(&cc.col))
+    };
+    let composites = match line {
+        FmtLine::Normal(fc) => std::slice::from_ref(fc),
+        FmtLine::TableRow(row) => &row.cells[..],
+        _ => { return Vec::new(); }
+    };
+    let mut ranges = Vec::new();
+    let mut char_idx = 0;
+    for fc in composites {
+        for (idx, compound) in fc.composite.compounds.iter().enumerate() {
+            let char_length = compound.char_length();
+            if in_cols(fc, idx) {
+                ranges.push(char_idx..char_idx + char_length);
+            }
+            char_idx += char_length;
+        }
+        char_idx += 1; // the table border
+    }
+    ranges
+}
+
+/// Return the approximate byte offset in the markdown of what's
+/// rendered at the given column of the line, when written with the
+/// given width.
+///
+/// A column which isn't in a compound (a margin, a list bullet, a
+/// table border, etc.) gets the start of the following one, or the
+/// end of the last one.
+pub(crate) fn source_offset_at(
+    skin: &MadSkin,
+    line: &FmtLine<'_>,
+    width: Option<usize>,
+    markdown: &str,
+    col: usize,
+) -> Option<usize> {
+    let mut last_end = None;
+    for cc in compound_columns(skin, line, width) {
+        let compound = cc.compound();
+        let start_col = cc.col;
+        let (src_start, src_end) = match source_range(markdown, compound) {
+            Some(range) => range,
+            None => { continue; }
+        };
+        if col < start_col {
+            return Some(src_start);
+        }
+        let mut char_col = start_col;
+        for (idx, c) in compound.src.char_indices() {
+            char_col += c.width().unwrap_or(0);
+            if col < char_col {
+                return Some(src_start + idx);
+            }
+        }
+        last_end = Some(src_end);
+    }
+    last_end
+}
+
+#[cfg(test)]
+mod source_map_tests {
+
+    use crate::*;
+
+    #[test]
+    fn check_text_view_source_offsets() {
+        let skin = MadSkin::no_style();
+        let md = "# Title\nsome *text* here\n* item\n|a|bc|\n|-|-|\n---";
+        let area = Area::new(2, 1, 20, 10);
+        let text = skin.area_text(md, &area);
+        let view = TextView::from(&area, &text);
+        let offset_at = |x, y| view.source_offset_at(md, x, y);
+        assert_eq!(offset_at(2, 1), Some(md.find("Title").unwrap()));
+        assert_eq!(offset_at(3, 1), Some(md.find("itle").unwrap()));
+        assert_eq!(offset_at(7, 2), Some(md.find("text").unwrap()));
+        assert_eq!(offset_at(8, 2), Some(md.find("ext").unwrap()));
+        // after the end of the line
+        assert_eq!(offset_at(20, 2), Some(md.find("\n* item").unwrap()));
+        // on the bullet
+        assert_eq!(offset_at(2, 3), Some(md.find("item").unwrap()));
+        // in a table
+        assert_eq!(offset_at(5, 4), Some(md.find("bc").unwrap()));
+        // on a rule, or out of the area
+        assert_eq!(offset_at(4, 5), None);
+        assert_eq!(offset_at(4, 6), None);
+        assert_eq!(offset_at(4, 0), None);
+        assert_eq!(offset_at(1, 2), None);
+    }
+
+    #[test]
+    fn check_mad_view_source_offsets() {
+        let md = "some text, long enough to be wrapped";
+        let area = Area::new(0, 0, 12, 2);
+        let mut view = MadView::from(md.to_string(), area, MadSkin::no_style());
+        assert_eq!(view.source_offset_at(0, 0), Some(0));
+        assert_eq!(view.source_offset_at(5, 0), Some(5));
+        view.try_scroll_lines(1);
+        assert_eq!(view.source_offset_at(0, 0), Some(md.find("long").unwrap()));
+        assert_eq!(view.source_offset_at(11, 0), None); // on the scrollbar
+    }
+}
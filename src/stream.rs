@@ -0,0 +1,142 @@
+use crate::render_options::is_indented_code;
+
+/// An iterator over the blocks of a markdown text, that is the parts
+/// which can be laid out independently.
+///
+/// Blocks are separated by blank lines which aren't in a code block.
+/// Each block ends with its separating blank line, so that the blocks
+/// concatenated are the whole text.
+pub(crate) struct Blocks<'s> {
+    src: &'s str,
+}
+
+impl<'s> Blocks<'s> {
+    pub(crate) const fn new(src: &'s str) -> Self {
+        Self { src }
+    }
+}
+
+impl<'s> Iterator for Blocks<'s> {
+    type Item = &'s str;
+    fn next(&mut self) -> Option<&'s str> {
+        if self.src.is_empty() {
+            return None;
+        }
+        let mut in_code_block = false;
+        let mut in_indented_code = false;
+        let mut end = self.src.len();
+        let mut line_start = 0;
+        for line in self.src.split_inclusive('\n') {
+            let line_end = line_start + line.len();
+            let trimmed = line.trim();
+            if trimmed.starts_with("```") {
+                in_code_block = !in_code_block;
+            } else if trimmed.is_empty() && !in_code_block {
+                // blank lines inside an indented code block don't end it
+                let code_follows = self.src[line_end..]
+                    .lines()
+                    .find(|line| !line.trim().is_empty())
+                    .map_or(false, is_indented_code);
+                if !(in_indented_code && code_follows) {
+                    end = line_end;
+                    break;
+                }
+            } else if !in_code_block {
+                in_indented_code = is_indented_code(line);
+            }
+            line_start = line_end;
+        }
+        let (block, tail) = self.src.split_at(end);
+        self.src = tail;
+        Some(block)
+    }
+}
+
+#[cfg(test)]
+mod stream_tests {
+
+    use {
+        super::*,
+        crate::{
+            displayable_line::DisplayableLine,
+            *,
+        },
+    };
+
+    #[test]
+    fn check_blocks() {
+        let md = "# Title\nsome text\n\n* a\n* b\n\n\n```\ncode\n\nmore code\n```\nend";
+        let blocks: Vec<&str> = Blocks::new(md).collect();
+        assert_eq!(
+            blocks,
+            vec![
+                "# Title\nsome text\n\n",
+                "* a\n* b\n\n",
+                "\n",
+                "```\ncode\n\nmore code\n```\nend",
+            ],
+        );
+        assert_eq!(Blocks::new("").count(), 0);
+        // blank lines don't split an indented code block
+        let md = "text\n\n    code\n\n\n    more code\n\nend";
+        let blocks: Vec<&str> = Blocks::new(md).collect();
+        assert_eq!(blocks, vec!["text\n\n", "    code\n\n\n    more code\n\n", "end"]);
+    }
+
+    #[test]
+    fn check_streamed_lines_are_the_same() {
+        let skin = MadSkin::default();
+        let md = "# The title\n\
+            A paragraph long enough to be wrapped on several lines.\n\
+            \n\
+            1. first\n\
+            2. second\n\
+            \n\
+            |a|b|\n\
+            |-|:-:|\n\
+            |some content|x|\n\
+            \n\
+            ```\n\
+            fn main() {\n\
+            \n\
+                println!(\"hi\");\n\
+            }\n\
+            ```\n\
+            > a quote\n\
+            > on two lines\n\
+            \n\
+            ---\n\
+            the end";
+        let width = 20;
+        let rendered = |lines: &[FmtLine<'_>]| -> Vec<String> {
+            lines
+                .iter()
+                .map(|line| DisplayableLine::new(&skin, line, Some(width)).to_string())
+                .collect()
+        };
+        let streamed: Vec<FmtLine<'_>> = skin.render_lines(md, width).collect();
+        let text = skin.text(md, Some(width));
+        assert_eq!(rendered(&streamed), rendered(&text.lines));
+        // the lines are computed lazily
+        assert_eq!(skin.render_lines(md, width).take(3).count(), 3);
+    }
+
+    #[test]
+    fn check_streamed_lines_are_spaced() {
+        let mut skin = MadSkin::no_style();
+        skin.set_headers_spacing(1, 1);
+        skin.set_paragraph_spacing(0, 0);
+        let md = "# Title\n\n\n\nsome text\n\nmore text\n## Sub\n* a\n\n\n";
+        let width = 20;
+        let rendered = |lines: &[FmtLine<'_>]| -> Vec<String> {
+            lines
+                .iter()
+                .map(|line| DisplayableLine::new(&skin, line, Some(width)).to_string())
+                .collect()
+        };
+        let streamed: Vec<FmtLine<'_>> = skin.render_lines(md, width).collect();
+        let text = skin.text(md, Some(width));
+        assert_eq!(text.lines.len(), 10);
+        assert_eq!(rendered(&streamed), rendered(&text.lines));
+    }
+}
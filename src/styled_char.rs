@@ -1,5 +1,6 @@
 use {
     crate::{
+        color::{ansi, rgb},
         compound_style::CompoundStyle,
         errors::Result,
     },
@@ -36,6 +37,14 @@ impl StyledChar {
     pub fn from_fg_char(fg: Color, nude_char: char) -> StyledChar {
         Self::new(CompoundStyle::with_fg(fg), nude_char)
     }
+    /// Build a styled char with a RGB foreground color
+    pub fn from_fg_rgb_char(r: u8, g: u8, b: u8, nude_char: char) -> StyledChar {
+        Self::from_fg_char(rgb(r, g, b), nude_char)
+    }
+    /// Build a styled char with an ANSI (256 colors) foreground color
+    pub fn from_ansi_char(fg: u8, nude_char: char) -> StyledChar {
+        Self::from_fg_char(ansi(fg), nude_char)
+    }
     /// Change the char, keeping colors and attributes
     pub fn set_char(&mut self, nude_char: char) {
         self.nude_char = nude_char;
@@ -49,6 +58,10 @@ impl StyledChar {
         self.compound_style.set_fg(color);
         self.styled_char = self.compound_style.apply_to(self.nude_char);
     }
+    /// Change the fg color to the passed RGB one
+    pub fn set_fg_rgb(&mut self, r: u8, g: u8, b: u8) {
+        self.set_fg(rgb(r, g, b));
+    }
     pub const fn get_fg(&self) -> Option<Color> {
         self.compound_style.get_fg()
     }
@@ -57,6 +70,10 @@ impl StyledChar {
         self.compound_style.set_bg(color);
         self.styled_char = self.compound_style.apply_to(self.nude_char);
     }
+    /// Change the bg color to the passed RGB one
+    pub fn set_bg_rgb(&mut self, r: u8, g: u8, b: u8) {
+        self.set_bg(rgb(r, g, b));
+    }
     pub const fn get_bg(&self) -> Option<Color> {
         self.compound_style.get_bg()
     }
@@ -0,0 +1,100 @@
+use {
+    minimad::{
+        Composite,
+        Compound,
+        Line,
+    },
+    unicode_width::UnicodeWidthStr,
+};
+
+/// The maximal width of a tab stop
+pub(crate) const MAX_TAB_WIDTH: usize = 32;
+
+static SPACES: &str = "                                ";
+
+/// replace the tabs of the compounds with the spaces needed to reach
+/// the next tab stop, columns being counted from the start of the
+/// composite
+fn expand_composite_tabs(composite: &mut Composite<'_>, tab_width: usize) {
+    if !composite.compounds.iter().any(|c| c.src.contains('\t')) {
+        return;
+    }
+    let src_compounds = std::mem::take(&mut composite.compounds);
+    let mut col = 0;
+    for mut compound in src_compounds {
+        while let Some(idx) = compound.src.find('\t') {
+            if idx > 0 {
+                let before = compound.sub(0, idx);
+                col += before.src.width();
+                composite.compounds.push(before);
+            }
+            let spaces = if tab_width == 0 { 0 } else { tab_width - col % tab_width };
+            if spaces > 0 {
+                composite.compounds.push(Compound {
+                    src: &SPACES[..spaces],
+                    ..compound.clone()
+                });
+                col += spaces;
+            }
+            compound = compound.tail(idx + 1);
+        }
+        if !compound.is_empty() {
+            col += compound.src.width();
+            composite.compounds.push(compound);
+        }
+    }
+}
+
+/// Expand the tabs of the text according to tab stops every
+/// `tab_width` columns (which can't be more than `MAX_TAB_WIDTH`).
+///
+/// Columns are counted from the start of the line, or of the table
+/// cell, after the markdown marks (so that in code blocks they're
+/// counted from the start of the code).
+pub(crate) fn expand_tabs(lines: &mut [Line<'_>], tab_width: usize) {
+    let tab_width = tab_width.min(MAX_TAB_WIDTH);
+    for line in lines {
+        match line {
+            Line::Normal(composite) => {
+                expand_composite_tabs(composite, tab_width);
+            }
+            Line::TableRow(row) => {
+                for cell in &mut row.cells {
+                    expand_composite_tabs(cell, tab_width);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tab_tests {
+
+    use crate::*;
+
+    #[test]
+    fn check_code_block_tabs() {
+        let mut skin = MadSkin::no_style();
+        skin.code_block.align = minimad::Alignment::Left;
+        let md = "```\nfn main() {\n\tlet a\t= 1;\n\tlet abcd\t= 2;\n}\n```";
+        assert_eq!(
+            skin.text(md, None).to_plain_string(),
+            "fn main() {         \n    let a   = 1;    \n    let abcd    = 2;\n}                   \n",
+        );
+        skin.set_tab_width(2);
+        assert_eq!(
+            skin.text("a\tb\ncd **e\tf**", None).to_plain_string(),
+            "a b\ncd e  f\n",
+        );
+    }
+
+    #[test]
+    fn check_table_cell_tabs() {
+        let skin = MadSkin::no_style();
+        assert_eq!(
+            skin.text("|a\tb|c|\n|-|-|", None).to_plain_string(),
+            "│a   b│c│\n└─────┴─┘\n",
+        );
+    }
+}
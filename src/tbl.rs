@@ -5,7 +5,7 @@ use {
         line::FmtLine,
         skin::MadSkin,
         spacing::Spacing,
-        fit::wrap,
+        fit::{wrap, Fitter},
     },
     minimad::{Alignment, TableRow},
     std::cmp,
@@ -18,6 +18,38 @@ pub struct FmtTableRow<'s> {
     pub cells: Vec<FmtComposite<'s>>,
 }
 
+/// A constraint on the width of a table column, not counting
+/// the borders
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColWidth {
+    /// the width of the content, reduced when the table doesn't fit
+    Auto,
+    /// exactly this width, the content being wrapped if necessary
+    Fixed(usize),
+    /// at least this width
+    Min(usize),
+    /// at most this width, the content being wrapped if necessary
+    Max(usize),
+}
+
+impl Default for ColWidth {
+    fn default() -> Self {
+        Self::Auto
+    }
+}
+
+impl ColWidth {
+    /// return the width of a column whose content has the passed width
+    pub const fn apply(self, width: usize) -> usize {
+        match self {
+            Self::Auto => width,
+            Self::Fixed(n) => n,
+            Self::Min(n) => if width < n { n } else { width },
+            Self::Max(n) => if width > n { n } else { width },
+        }
+    }
+}
+
 /// Top, Bottom, or other
 #[derive(Debug)]
 pub enum RelativePosition {
@@ -90,7 +122,7 @@ struct Col {
 /// No width can go below 3.
 /// This function should be called only when the goal is attainable
 /// and when there's reduction to be done.
-fn reduce_col_widths(widths: &mut Vec<usize>, goal: usize) {
+fn reduce_col_widths(widths: &mut [usize], goal: usize) {
     let sum: usize = widths.iter().sum();
     assert!(sum > goal);
 
@@ -145,7 +177,7 @@ fn reduce_col_widths(widths: &mut Vec<usize>, goal: usize) {
         }
     }
 
-    cols.sort_by(|a, b| b.to_remove.cmp(&a.to_remove));
+    cols.sort_by_key(|c| cmp::Reverse(c.to_remove));
 
     //- general case, which could be improved
     for col in &mut cols {
@@ -175,21 +207,73 @@ fn reduce_col_widths(widths: &mut Vec<usize>, goal: usize) {
     }
 }
 
+/// Reduce the widths of the columns to reach the goal, honoring the
+/// constraints as long as possible: the `Auto` columns are reduced
+/// first, then the `Min` and `Max` ones, and only then all columns
+/// without regard to the constraints.
+fn reduce_constrained_col_widths(
+    widths: &mut [usize],
+    constraints: &[ColWidth],
+    goal: usize,
+) {
+    let constraint = |ic: usize| constraints.get(ic).copied().unwrap_or_default();
+    let mut excess = widths.iter().sum::<usize>().saturating_sub(goal);
+    let groups = [
+        (0..widths.len())
+            .filter(|&ic| constraint(ic) == ColWidth::Auto)
+            .collect::<Vec<usize>>(),
+        (0..widths.len())
+            .filter(|&ic| matches!(constraint(ic), ColWidth::Min(_) | ColWidth::Max(_)))
+            .collect::<Vec<usize>>(),
+    ];
+    for group in &groups {
+        if excess == 0 {
+            return;
+        }
+        // reduce_col_widths doesn't go below 3, so we shift the widths
+        // of the Min columns to keep them above their minimum
+        let offsets: Vec<usize> = group
+            .iter()
+            .map(|&ic| match constraint(ic) {
+                ColWidth::Min(n) => n.saturating_sub(3).min(widths[ic]),
+                _ => 0,
+            })
+            .collect();
+        let mut sub_widths: Vec<usize> = group
+            .iter()
+            .zip(&offsets)
+            .map(|(&ic, offset)| widths[ic] - offset)
+            .collect();
+        let sub_sum: usize = sub_widths.iter().sum();
+        let room: usize = sub_widths.iter().map(|w| w.saturating_sub(3)).sum();
+        if room == 0 {
+            continue;
+        }
+        reduce_col_widths(&mut sub_widths, sub_sum - excess.min(room));
+        for ((&ic, &w), offset) in group.iter().zip(&sub_widths).zip(&offsets) {
+            widths[ic] = w + offset;
+        }
+        excess -= (sub_sum - sub_widths.iter().sum::<usize>()).min(excess);
+    }
+    if excess > 0 {
+        reduce_col_widths(widths, goal);
+    }
+}
+
 impl Table {
-    pub fn fix_columns(&mut self, lines: &mut Vec<FmtLine<'_>>, width: usize) {
+    pub fn fix_columns<'s>(&mut self, lines: &mut Vec<FmtLine<'s>>, width: usize, skin: &'s MadSkin) {
         let mut nbcols = self.nbcols;
         // let's first compute the initial widths of all columns
         // (not counting the widths of the borders)
         // We also add the missing cells
         let mut widths: Vec<usize> = vec![0; nbcols];
-        for ir in self.start..self.start + self.height {
-            let line = &mut lines[ir];
+        for line in &mut lines[self.start..self.start + self.height] {
             if let FmtLine::TableRow(FmtTableRow { cells }) = line {
-                for ic in 0..nbcols {
+                for (ic, width) in widths.iter_mut().enumerate() {
                     if cells.len() <= ic {
                         cells.push(FmtComposite::new());
                     } else {
-                        widths[ic] = widths[ic].max(cells[ic].visible_length);
+                        *width = (*width).max(cells[ic].visible_length);
                     }
                 }
             } else if let FmtLine::TableRule(rule) = line {
@@ -198,6 +282,10 @@ impl Table {
                 println!("not a table row, should not happen"); // should we panic ?
             }
         }
+        for (width, constraint) in widths.iter_mut().zip(&skin.table_column_constraints) {
+            // a column can't be so narrow that its cells can't be wrapped
+            *width = constraint.apply(*width).max((*width).min(3));
+        }
         // let's find what we must do
         let widths_sum: usize = widths.iter().sum();
         let mut cols_removed = false;
@@ -205,13 +293,17 @@ impl Table {
             // it fits, all is well
         } else if nbcols * 4 < width {
             // we can keep all columns but we'll have to wrap them
-            reduce_col_widths(&mut widths, width - nbcols - 1);
+            reduce_constrained_col_widths(
+                &mut widths,
+                &skin.table_column_constraints,
+                width - nbcols - 1,
+            );
         } else {
             // crisis behavior: we remove the columns which don't fit
             nbcols = (width - 1) / 4;
             cols_removed = true;
-            for ic in 0..nbcols {
-                widths[ic] = 3;
+            for width in &mut widths[..nbcols] {
+                *width = 3;
             }
         }
 
@@ -230,7 +322,10 @@ impl Table {
                         continue;
                     }
                     cells_to_add.push(Vec::new());
-                    if cells[ic].visible_length > widths[ic] {
+                    if cells[ic].visible_length > widths[ic] && !skin.table_wrap {
+                        let fitter = skin.table_fit_mode.map_or_else(Fitter::default, Fitter::for_mode);
+                        fitter.fit(&mut cells[ic], widths[ic], skin);
+                    } else if cells[ic].visible_length > widths[ic] {
                         // we must wrap the cell over several lines
                         let mut composites = wrap::hard_wrap_composite(&cells[ic], widths[ic]);
                         // the first composite replaces the cell, while the other
@@ -245,9 +340,9 @@ impl Table {
                 let nb_new_lines = cells_to_add.iter().fold(0, |m, cells| m.max(cells.len()));
                 for inl in (0..nb_new_lines).rev() {
                     let mut new_cells: Vec<FmtComposite<'_>> = Vec::new();
-                    for ic in 0..nbcols {
-                        new_cells.push(if cells_to_add[ic].len() > inl {
-                            cells_to_add[ic].remove(inl)
+                    for cells in cells_to_add.iter_mut().take(nbcols) {
+                        new_cells.push(if cells.len() > inl {
+                            cells.remove(inl)
                         } else {
                             FmtComposite::new()
                         });
@@ -259,14 +354,34 @@ impl Table {
             }
         }
         // Finally we iterate in normal order to specify alignment
-        // (the alignments of a row are the ones of the last rule line)
-        let mut current_aligns: Vec<Alignment> = vec![Alignment::Center; nbcols];
-        for ir in self.start..self.start + self.height {
-            let line = &mut lines[ir];
+        // (the alignments of a row are the ones of the last rule line,
+        // the header rows taking the ones of the rule following them)
+        let end = self.start + self.height;
+        let cell_align = |align: Alignment| match align {
+            Alignment::Unspecified => skin.table_cell_align,
+            _ => align,
+        };
+        let header_align = |align: Alignment| match cell_align(align) {
+            Alignment::Unspecified => Alignment::Center,
+            align => align,
+        };
+        let mut current_aligns: Vec<Alignment> = lines[self.start..end]
+            .iter()
+            .skip_while(|line| matches!(line, FmtLine::TableRule(_)))
+            .find_map(|line| match line {
+                FmtLine::TableRule(rule) => Some(rule.aligns.clone()),
+                _ => None,
+            })
+            .unwrap_or_default();
+        current_aligns.resize(nbcols, Alignment::Unspecified);
+        for align in &mut current_aligns {
+            *align = header_align(*align);
+        }
+        for (ir, line) in lines.iter_mut().enumerate().take(end).skip(self.start) {
             match line {
                 FmtLine::TableRow(FmtTableRow { cells }) => {
-                    for ic in 0..nbcols {
-                        cells[ic].spacing = Some(Spacing {
+                    for (ic, cell) in cells.iter_mut().enumerate().take(nbcols) {
+                        cell.spacing = Some(Spacing {
                             width: widths[ic],
                             align: current_aligns[ic],
                         });
@@ -278,11 +393,15 @@ impl Table {
                     }
                     if ir == self.start {
                         rule.position = RelativePosition::Top;
-                    } else if ir == self.start + self.height - 1 {
+                    } else if ir == end - 1 {
                         rule.position = RelativePosition::Bottom;
                     }
                     rule.widths[..nbcols].clone_from_slice(&widths[..nbcols]);
-                    current_aligns[..nbcols].clone_from_slice(&rule.aligns[..nbcols]);
+                    if ir > self.start {
+                        for (current, &align) in current_aligns.iter_mut().zip(&rule.aligns) {
+                            *current = cell_align(align);
+                        }
+                    }
                 }
                 _ => {
                     panic!("It should be a table part");
@@ -340,11 +459,14 @@ fn find_tables(lines: &[FmtLine<'_>]) -> Vec<Table> {
 /// Modify the rows of all tables in order to ensure it fits the widths
 /// and all cells have the widths of their column.
 ///
+/// Cells too wide for their column are wrapped or truncated depending
+/// on the `table_wrap` setting of the skin.
+///
 /// Some lines may be added to the table in the process, which means any
 ///  precedent indexing might be invalid.
-pub fn fix_all_tables(lines: &mut Vec<FmtLine<'_>>, width: usize) {
+pub fn fix_all_tables<'s>(lines: &mut Vec<FmtLine<'s>>, width: usize, skin: &'s MadSkin) {
     for tbl in find_tables(lines).iter_mut().rev() {
-        tbl.fix_columns(lines, width);
+        tbl.fix_columns(lines, width, skin);
     }
 }
 
@@ -385,3 +507,161 @@ mod col_reduction_tests {
         }
     }
 }
+
+#[cfg(test)]
+mod col_constraints_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_constraints_application() {
+        let mut skin = MadSkin::no_style();
+        skin.set_table_column_constraints(vec![
+            ColWidth::Fixed(5),
+            ColWidth::Min(4),
+            ColWidth::Max(3),
+        ]);
+        let md = "|a|b|cdefg|d|\n|-|-|-|-|";
+        assert_eq!(
+            skin.text(md, None).to_plain_string(),
+            "│  a  │ b  │cde│d│\n\
+            │     │    │fg │ │\n\
+            └─────┴────┴───┴─┘\n",
+        );
+    }
+
+    #[test]
+    fn check_constrained_reduction() {
+        let constraints = [ColWidth::Min(20), ColWidth::Auto, ColWidth::Fixed(10), ColWidth::Max(15)];
+        // the Auto column is reduced first
+        let mut widths = vec![30, 30, 10, 15];
+        reduce_constrained_col_widths(&mut widths, &constraints, 70);
+        assert_eq!(widths, [30, 15, 10, 15]);
+        // then the Min and Max ones, the Min one not below its minimum
+        let mut widths = vec![30, 30, 10, 15];
+        reduce_constrained_col_widths(&mut widths, &constraints, 45);
+        assert_eq!(widths[1], 3);
+        assert_eq!(widths[2], 10);
+        assert!(widths[0] >= 20);
+        assert!(widths.iter().sum::<usize>() <= 45);
+        // and all of them when there's no other way
+        let mut widths = vec![30, 30, 10, 15];
+        reduce_constrained_col_widths(&mut widths, &constraints, 30);
+        assert!(widths.iter().sum::<usize>() <= 30);
+        // without constraint, it's the standard reduction
+        let mut widths = vec![3, 4, 11, 5, 15, 4, 9, 5, 4, 47];
+        let mut expected = widths.clone();
+        reduce_col_widths(&mut expected, 50);
+        reduce_constrained_col_widths(&mut widths, &[], 50);
+        assert_eq!(widths, expected);
+    }
+}
+
+#[cfg(test)]
+mod table_wrap_tests {
+
+    use crate::*;
+
+    static MD: &str = "|a|b|\n|-|-|\n|some long text|x|\n|c|d|\n|-|-|";
+
+    #[test]
+    fn check_wrapped_cells() {
+        let skin = MadSkin::no_style();
+        assert_eq!(
+            skin.text(MD, Some(12)).to_plain_string(),
+            "│   a    │b│\n\
+            ├────────┼─┤\n\
+            │some    │x│\n\
+            │long    │ │\n\
+            │text    │ │\n\
+            │c       │d│\n\
+            └────────┴─┘\n",
+        );
+    }
+
+    #[test]
+    fn check_truncated_cells() {
+        let mut skin = MadSkin::no_style();
+        skin.set_table_wrap(false);
+        assert_eq!(
+            skin.text(MD, Some(12)).to_plain_string(),
+            "│   a    │b│\n\
+            ├────────┼─┤\n\
+            │some…ext│x│\n\
+            │c       │d│\n\
+            └────────┴─┘\n",
+        );
+    }
+
+    #[test]
+    fn check_truncated_cells_fit_mode() {
+        let mut skin = MadSkin::no_style();
+        skin.set_table_wrap(false);
+        skin.set_table_fit_mode(Some(FitMode::Start));
+        assert_eq!(
+            skin.text(MD, Some(12)).to_plain_string(),
+            "│   a    │b│\n\
+            ├────────┼─┤\n\
+            │…ng text│x│\n\
+            │c       │d│\n\
+            └────────┴─┘\n",
+        );
+    }
+}
+
+#[cfg(test)]
+mod table_align_tests {
+
+    use {
+        crate::*,
+        minimad::Alignment,
+    };
+
+    static MD: &str = "|left|center|right|none|\n\
+        |:-|:-:|-:|-|\n\
+        |a|b|c|d|\n\
+        |aaaaaa|bbbbbb|cccccc|dddddd|\n\
+        |-|-|-|-|";
+
+    #[test]
+    fn check_column_alignments() {
+        let skin = MadSkin::no_style();
+        assert_eq!(
+            skin.text(MD, None).to_plain_string(),
+            "│left  │center│ right│ none │\n\
+            ├──────┼──────┼──────┼──────┤\n\
+            │a     │  b   │     c│d     │\n\
+            │aaaaaa│bbbbbb│cccccc│dddddd│\n\
+            └──────┴──────┴──────┴──────┘\n",
+        );
+    }
+
+    #[test]
+    fn check_default_cell_alignment() {
+        let mut skin = MadSkin::no_style();
+        skin.set_table_cell_align(Alignment::Right);
+        assert_eq!(
+            skin.text(MD, None).to_plain_string(),
+            "│left  │center│ right│  none│\n\
+            ├──────┼──────┼──────┼──────┤\n\
+            │a     │  b   │     c│     d│\n\
+            │aaaaaa│bbbbbb│cccccc│dddddd│\n\
+            └──────┴──────┴──────┴──────┘\n",
+        );
+    }
+
+    #[test]
+    fn check_alignment_of_wrapped_cells() {
+        let skin = MadSkin::no_style();
+        let md = "|name|size|\n|-|-:|\n|x|1 234 567|\n|-|-|";
+        assert_eq!(
+            skin.text(md, Some(11)).to_plain_string(),
+            "│nam│ size│\n\
+            │ e │     │\n\
+            ├───┼─────┤\n\
+            │x  │1 234│\n\
+            │   │  567│\n\
+            └───┴─────┘\n",
+        );
+    }
+}
@@ -1,13 +1,23 @@
 use {
     crate::{
+        block_spacing::BlockSpacer,
         code,
+        composite::{CompoundKind, FmtComposite},
+        document::MarkdownDocument,
+        footnote,
         line::FmtLine,
+        link::Link,
+        list,
+        render_options,
         skin::MadSkin,
+        source_map,
+        tab,
         tbl,
-        fit::wrap,
+        fit::{wrap, FitMode},
     },
-    minimad::Text,
+    minimad::{Alignment, CompositeStyle, Line, Text},
     std::fmt,
+    unicode_width::UnicodeWidthStr,
 };
 
 /// a formatted text, implementing Display
@@ -25,37 +35,556 @@ pub struct FmtText<'k, 's> {
     pub width: Option<usize>, // available width
 }
 
-impl<'k, 's> FmtText<'k, 's> {
+/// Some statistics about a formatted text, counted on the
+/// displayed text (the markdown marks aren't counted)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextStats {
+    /// the number of words, a word being a sequence of non
+    /// whitespace chars
+    pub words: usize,
+    /// the number of chars, whitespaces excluded
+    pub chars: usize,
+    /// the number of lines, as displayed
+    pub lines: usize,
+    /// the number of headers
+    pub headings: usize,
+}
+
+impl TextStats {
+    /// count the words and chars of the compounds of the composite,
+    /// which follow a word break (header marks aren't counted)
+    fn add_compounds(&mut self, fc: &FmtComposite<'_>) {
+        let mut in_word = false;
+        for idx in 0..fc.composite.compounds.len() {
+            if fc.has_kind(idx, CompoundKind::HeaderMark) {
+                continue;
+            }
+            for c in fc.compound_text(idx).chars() {
+                if c.is_whitespace() {
+                    in_word = false;
+                } else {
+                    self.chars += 1;
+                    if !in_word {
+                        self.words += 1;
+                        in_word = true;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// truncate the headers which are too long for the width
+fn fit_headers<'s>(lines: &mut [FmtLine<'s>], width: usize, skin: &'s MadSkin) {
+    for line in lines {
+        if let FmtLine::Normal(fc) = line {
+            if matches!(fc.composite.style, CompositeStyle::Header(_)) && fc.visible_length > width {
+                fc.fit_width_with_mode(width, FitMode::End, skin);
+            }
+        }
+    }
+}
+
+impl<'k: 's, 's> FmtText<'k, 's> {
     /// build a displayable text for the specified width and skin
     ///
     /// This can be called directly or using one of the skin helper
     /// method.
     pub fn from(skin: &'k MadSkin, src: &'s str, width: Option<usize>) -> FmtText<'k, 's> {
-        let mt = Text::from(src);
+        let mt = render_options::parse_text(src, &skin.render_options);
         Self::from_text(skin, mt, width)
     }
+    /// build a fmt_text from a parsed document, which isn't consumed
+    pub fn from_document(
+        skin: &'k MadSkin,
+        document: &MarkdownDocument<'s>,
+        width: Option<usize>,
+    ) -> FmtText<'k, 's> {
+        Self::from_text(skin, Text { lines: document.lines.clone() }, width)
+    }
     /// build a fmt_text from a minimad text
-    pub fn from_text(skin: &'k MadSkin, mut text: Text<'s>, width: Option<usize>) -> FmtText<'k, 's> {
-        let mut lines = text
-            .lines
+    pub fn from_text(skin: &'k MadSkin, text: Text<'s>, width: Option<usize>) -> FmtText<'k, 's> {
+        Self::from_text_with_table_width(skin, text, width, width.unwrap_or(usize::MAX))
+    }
+    /// build a fmt_text whose tables are fitted to `table_width`
+    /// instead of the width the text is wrapped to
+    pub(crate) fn from_text_with_table_width(
+        skin: &'k MadSkin,
+        mut text: Text<'s>,
+        width: Option<usize>,
+        table_width: usize,
+    ) -> FmtText<'k, 's> {
+        let footnotes = footnote::extract_footnotes(&mut text.lines);
+        let mut spacer = BlockSpacer::new();
+        let mut lines = spacer.space_lines(skin, text.lines);
+        lines.append(&mut spacer.finish());
+        Self::from_prepared_lines(skin, lines, &footnotes, width, table_width)
+    }
+    /// build a fmt_text from lines whose footnotes, with the given
+    /// labels, are already extracted and whose blocks are spaced
+    pub(crate) fn from_prepared_lines(
+        skin: &'k MadSkin,
+        mut lines: Vec<Line<'s>>,
+        footnotes: &[&str],
+        width: Option<usize>,
+        table_width: usize,
+    ) -> FmtText<'k, 's> {
+        tab::expand_tabs(&mut lines, skin.tab_width);
+        let mut lines: Vec<FmtLine<'s>> = lines
             .drain(..)
             .map(|mline| FmtLine::from(mline, skin))
             .collect();
-        tbl::fix_all_tables(&mut lines, width.unwrap_or(std::usize::MAX));
-        code::justify_blocks(&mut lines);
+        footnote::mark_footnotes(&mut lines, footnotes, skin);
+        list::number_lists(&mut lines);
+        tbl::fix_all_tables(&mut lines, table_width, skin);
         if let Some(width) = width {
-            lines = wrap::hard_wrap_lines(lines, width);
+            if !skin.headers_wrap {
+                fit_headers(&mut lines, width, skin);
+            }
+            lines = wrap::hard_wrap_lines_with(lines, width, skin.wrap_algorithm);
+        }
+        // after wrapping, so that the lines of a code block, blank
+        // ones included, take the width of the widest wrapped one
+        if skin.code_block_justify {
+            code::justify_blocks(&mut lines);
+        }
+        if matches!(skin.quote_align, Alignment::Center | Alignment::Right) {
+            code::justify_quote_blocks(&mut lines);
         }
         FmtText { skin, lines, width }
     }
+    /// Return the number of rows the markdown takes when rendered
+    /// with the given width, that is the number of lines after wrapping
+    /// (table rows and rules, code block lines, etc. included)
+    ///
+    /// This is the content height of a `TextView` displaying this text.
+    pub fn line_count_for_width(skin: &MadSkin, markdown: &str, width: usize) -> usize {
+        FmtText::from(skin, markdown, Some(width)).lines.len()
+    }
+    /// Return the indexes of the lines starting a header, in order
+    pub(crate) fn heading_lines(&self) -> Vec<usize> {
+        self.lines
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, line)| match line {
+                FmtLine::Normal(fc) if !fc.continuation => match fc.composite.style {
+                    CompositeStyle::Header(_) => Some(idx),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+    /// Count the words, chars, lines and headings of the text.
+    ///
+    /// Words are counted as if lines of a wrapped paragraph were
+    /// separated with a space.
+    pub fn stats(&self) -> TextStats {
+        let mut stats = TextStats {
+            lines: self.lines.len(),
+            ..TextStats::default()
+        };
+        for line in &self.lines {
+            match line {
+                FmtLine::Normal(fc) => {
+                    if matches!(fc.composite.style, CompositeStyle::Header(_)) && !fc.continuation {
+                        stats.headings += 1;
+                    }
+                    stats.add_compounds(fc);
+                }
+                FmtLine::TableRow(row) => {
+                    for cell in &row.cells {
+                        stats.add_compounds(cell);
+                    }
+                }
+                _ => {}
+            }
+        }
+        stats
+    }
+    /// return the text as it would be displayed, with the same wrapping,
+    /// alignments and table borders, but without colors or attributes
+    /// (i.e. without any ANSI escape sequence)
+    pub fn to_plain_string(&self) -> String {
+        PlainText {
+            text: self,
+            skin: self.skin.without_styles(),
+        }.to_string()
+    }
+    /// Return the links of the text (only found when the skin's
+    /// `hyperlinks` mode isn't `Raw`) with where they're displayed.
+    ///
+    /// The index of a line is the row it's displayed on (when the
+    /// text isn't scrolled) and the columns are counted from the
+    /// start of the text.
+    pub fn links(&self) -> Vec<Link> {
+        self.links_for_width(self.width)
+    }
+    /// Return the links of the text, with their columns when the
+    /// lines are written with the given width
+    pub(crate) fn links_for_width(&self, width: Option<usize>) -> Vec<Link> {
+        let mut links: Vec<Link> = Vec::new();
+        // the line and index of the link which was found last, to
+        // recognize its wrapped parts
+        let mut last: Option<(usize, usize)> = None;
+        for (line_idx, line) in self.lines.iter().enumerate() {
+            let composites = match line {
+                FmtLine::Normal(fc) => std::slice::from_ref(fc),
+                FmtLine::TableRow(row) => &row.cells[..],
+                _ => { continue; }
+            };
+            if composites.iter().all(|fc| fc.links.is_empty()) {
+                continue;
+            }
+            let columns = source_map::compound_columns(self.skin, line, width);
+            for fc in composites {
+                for (link_idx, fmt_link) in fc.links.iter().enumerate() {
+                    let mut cols: Option<std::ops::Range<usize>> = None;
+                    for cc in &columns {
+                        if !std::ptr::eq(cc.fc, fc) || fc.link_idx(cc.idx) != Some(link_idx) {
+                            continue;
+                        }
+                        let end = cc.col + cc.compound().src.width();
+                        cols = Some(cols.map_or(cc.col..end, |cols| cols.start..end));
+                    }
+                    let cols = match cols {
+                        Some(cols) => cols,
+                        None => { continue; }
+                    };
+                    let wrapped = fc.continuation && line_idx > 0 && last == Some((line_idx - 1, link_idx));
+                    match links.last_mut() {
+                        Some(link) if wrapped => {
+                            link.spans.push((line_idx, cols));
+                        }
+                        _ => {
+                            links.push(Link {
+                                text: fmt_link.text().to_string(),
+                                url: fmt_link.url.to_string(),
+                                spans: vec![(line_idx, cols)],
+                            });
+                        }
+                    }
+                    last = Some((line_idx, link_idx));
+                }
+            }
+        }
+        links
+    }
+    /// Return the text without styles, as `to_plain_string`, but
+    /// hard wrapped at the given column.
+    ///
+    /// This is useful to write the text in a width constrained
+    /// destination (for example a commit message) whatever the
+    /// width the text was built for. As in display, the lines
+    /// wrapped in a list item are indented and the ones wrapped
+    /// in a quote start with the quote mark.
+    ///
+    /// Lines aren't unwrapped, so the text should normally have been
+    /// built without width, or a greater one. Tables are kept as they
+    /// are. Trailing spaces are removed.
+    ///
+    /// Panics if width is less than 3.
+    pub fn to_wrapped_plain_string(&self, width: usize) -> String {
+        assert!(width >= 3, "invalid width {} (must be at least 3)", width);
+        let text = WrappedPlainText {
+            text: self,
+            skin: self.skin.without_styles(),
+            width,
+        }.to_string();
+        let mut s = String::with_capacity(text.len());
+        for line in text.lines() {
+            s.push_str(line.trim_end());
+            s.push('\n');
+        }
+        s
+    }
+    fn write_with_skin(&self, f: &mut fmt::Formatter<'_>, skin: &MadSkin) -> fmt::Result {
+        for line in &self.lines {
+            skin.write_fmt_line(f, line, self.width, false)?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
 }
 
 impl fmt::Display for FmtText<'_, '_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for line in &self.lines {
-            self.skin.write_fmt_line(f, line, self.width, false)?;
-            writeln!(f)?;
+        self.write_with_skin(f, self.skin)
+    }
+}
+
+/// a text displayed with an unstyled version of its skin
+struct PlainText<'t, 'k, 's> {
+    text: &'t FmtText<'k, 's>,
+    skin: MadSkin,
+}
+
+impl fmt::Display for PlainText<'_, '_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.text.write_with_skin(f, &self.skin)
+    }
+}
+
+/// A text written without styles and wrapped at a given width
+struct WrappedPlainText<'t, 'k, 's> {
+    text: &'t FmtText<'k, 's>,
+    skin: MadSkin,
+    width: usize,
+}
+
+impl fmt::Display for WrappedPlainText<'_, '_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.text.lines {
+            match line {
+                FmtLine::Normal(fc) if fc.visible_length > self.width => {
+                    let fc = wrap::fit_decorations(fc, self.width);
+                    // the lines wrapped in a list item are aligned with its text
+                    let indent = if fc.composite.is_list_item() {
+                        (2 + fc.list_mark_extra_width()).min(self.width - 3)
+                    } else {
+                        0
+                    };
+                    let parts = wrap::hard_wrap_composite_indented(&fc, self.width, indent);
+                    for (idx, fc) in parts.into_iter().enumerate() {
+                        if idx > 0 {
+                            write!(f, "{:indent$}", "")?;
+                        }
+                        let width = self.width - if idx > 0 { indent } else { 0 };
+                        self.skin.write_fmt_line(f, &FmtLine::Normal(fc), Some(width), false)?;
+                        writeln!(f)?;
+                    }
+                }
+                _ => {
+                    self.skin.write_fmt_line(f, line, Some(self.width), false)?;
+                    writeln!(f)?;
+                }
+            }
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod text_tests {
+
+    use crate::*;
+
+    #[test]
+    fn check_plain_string() {
+        let mut skin = MadSkin::default();
+        skin.bullet.set_char('-');
+        let md = "# Title\n* **bold** item\n|a|b|\n|-|-|\n|c|*d*|";
+        let text = skin.text(md, Some(12));
+        assert!(text.to_string().contains('\u{1b}'));
+        assert_eq!(
+            text.to_plain_string(),
+            "   Title\n- bold item\n│a│b│\n├─┼─┤\n│c│d│\n",
+        );
+    }
+
+    #[test]
+    fn check_wrapped_plain_string() {
+        let mut skin = MadSkin::default();
+        skin.bullet.set_char('-');
+        skin.quote_mark.set_char('>');
+        skin.horizontal_rule.set_char('-');
+        let md = "# Title\n\
+            * an item long enough to be wrapped\n\
+            > some quoted text\n\
+            ---\n\
+            a paragraph";
+        let text = skin.text(md, None);
+        assert_eq!(
+            text.to_wrapped_plain_string(14),
+            "    Title\n\
+            - an item long\n  \
+              enough to be\n  \
+              wrapped\n\
+            > some quoted\n\
+            > text\n\
+            --------------\n\
+            a paragraph\n",
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid width 2 (must be at least 3)")]
+    fn check_wrapped_plain_string_min_width() {
+        let skin = MadSkin::default();
+        let _ = skin.text("* a list item", None).to_wrapped_plain_string(2);
+    }
+
+    #[test]
+    fn check_line_count_for_width() {
+        let skin = MadSkin::default();
+        let md = "# Title\n\
+            Some text long enough to be wrapped\n\
+            |a|b|\n\
+            |-|-|\n\
+            |some long cell content|x|\n\
+            ```\n\
+            code\n\
+            ```\n\
+            ---";
+        for width in [10, 20, 80] {
+            let rendered = skin.text(md, Some(width)).to_plain_string();
+            assert_eq!(
+                FmtText::line_count_for_width(&skin, md, width),
+                rendered.lines().count(),
+            );
+        }
+        assert_eq!(FmtText::line_count_for_width(&skin, md, 80), 7);
+    }
+
+    #[test]
+    fn check_stats() {
+        let skin = MadSkin::default();
+        let md = "# The **Title**\n\
+            Some *sty*led text, long enough to be wrapped\n\
+            ## Sub title\n\
+            * an `item`\n\
+            |a|b c|\n\
+            |-|-|\n\
+            ```\n\
+            let x = 3;\n\
+            ```";
+        assert_eq!(
+            skin.text(md, None).stats(),
+            TextStats {
+                words: 21,
+                chars: 68,
+                lines: 7,
+                headings: 2,
+            },
+        );
+        // wrapping changes the number of lines, not of words
+        let stats = skin.text(md, Some(14)).stats();
+        assert_eq!(stats.words, 21);
+        assert_eq!(stats.chars, 68);
+        assert!(stats.lines > 7);
+        assert_eq!(stats.headings, 2);
+    }
+
+    #[test]
+    fn check_block_alignments() {
+        let mut skin = MadSkin::no_style();
+        skin.quote_mark.set_char('>');
+        skin.code_block.align = Alignment::Center;
+        skin.quote_align = Alignment::Right;
+        let md = "```\nfn main() {\n}\n```\n> a quote\n> on two lines";
+        assert_eq!(
+            skin.text(md, Some(20)).to_plain_string(),
+            "    fn main() {\n    }          \n      > a quote     \n      > on two lines\n",
+        );
+    }
+
+    #[test]
+    fn check_rtl_alignment() {
+        let mut skin = MadSkin::no_style();
+        skin.set_rtl(true);
+        assert_eq!(
+            skin.text("abc\ndefgh", Some(8)).to_plain_string(),
+            "     abc\n   defgh\n",
+        );
+        // an explicit alignment is kept
+        skin.paragraph.align = Alignment::Left;
+        assert_eq!(
+            skin.text("abc", Some(8)).to_plain_string(),
+            "abc\n",
+        );
+    }
+
+    #[test]
+    fn check_header_marks() {
+        let mut skin = MadSkin::no_style();
+        skin.headers[0].align = Alignment::Center;
+        skin.set_header_mark(1, "▌ ");
+        skin.set_header_mark(3, "> ");
+        let md = "# Title\n## Sub\n### Subsub\ntext";
+        assert_eq!(
+            skin.text(md, Some(13)).to_plain_string(),
+            "   ▌ Title\nSub\n> Subsub\ntext\n",
+        );
+        skin.set_hash_header_marks();
+        assert_eq!(
+            skin.text(md, None).to_plain_string(),
+            "# Title\n## Sub\n### Subsub\ntext\n",
+        );
+        let stats = skin.text(md, None).stats();
+        assert_eq!((stats.words, stats.headings), (4, 3));
+        // a mark identical to the header text is counted only once
+        skin.set_header_mark(1, "Title");
+        let stats = skin.text("# Title", None).stats();
+        assert_eq!((stats.words, stats.chars), (1, 5));
+        // and a wrapped mark isn't counted either
+        skin.set_header_mark(1, "mark mark ");
+        let stats = skin.text("# Title", Some(6)).stats();
+        assert_eq!((stats.words, stats.chars, stats.headings), (1, 5, 1));
+    }
+
+    #[test]
+    fn check_compact_rendering() {
+        let md = "# A title too long for the width\n\nsome text\n\n```\nx\nlonger\n```\n\n* a\n\n* b\n\nend";
+        let mut skin = MadSkin::no_style();
+        skin.headers[0].align = Alignment::Left;
+        let normal = skin.text(md, Some(20));
+        assert_eq!(normal.lines.len(), 13);
+        skin.set_compact(true);
+        let compact = skin.text(md, Some(20));
+        assert_eq!(
+            compact.to_string(),
+            "A title too long fo…\nsome text\nx\nlonger\n• a\n• b\nend\n",
+        );
+        assert_eq!(FmtText::line_count_for_width(&skin, md, 20), 7);
+        skin.set_compact(false);
+        assert_eq!(skin.text(md, Some(20)).lines.len(), 13);
+    }
+
+    #[test]
+    fn check_code_block_blank_lines() {
+        let md = "text\n```\n\nlet a = 1;\n\n  \nlet b = 2;   \n\n```\nend\n\n    indented\n\n\n    code\n\nafter";
+        let mut skin = MadSkin::no_style();
+        skin.code_block.align = Alignment::Left;
+        assert_eq!(
+            skin.text(md, None).to_string(),
+            "text\n             \nlet a = 1;   \n             \n             \nlet b = 2;   \n             \nend\n\nindented\n        \n        \ncode    \n\nafter\n",
+        );
+        // wrapped code lines, blank ones included, don't overflow
+        assert_eq!(
+            skin.text(md, Some(8)).to_string(),
+            "text\n        \nlet a = \n1;      \n        \n        \nlet b = \n2;      \n        \nend\n\nindented\n        \n        \ncode    \n\nafter\n",
+        );
+        assert_eq!(FmtText::line_count_for_width(&skin, md, 8), 17);
+        let streamed: Vec<FmtLine<'_>> = skin.render_lines(md, 8).collect();
+        assert_eq!(streamed.len(), 17);
+        // the blank lines of code blocks aren't removed by the block spacing
+        skin.set_compact(true);
+        assert_eq!(
+            skin.text(md, None).to_plain_string(),
+            "text\n\nlet a = 1;\n\n  \nlet b = 2;   \n\nend\nindented\n\n\ncode\nafter\n",
+        );
+    }
+
+    #[test]
+    fn check_links() {
+        let mut skin = MadSkin::no_style();
+        assert!(skin.text("a [link](u)", None).links().is_empty()); // raw mode
+        skin.set_hyperlinks(HyperlinkMode::Text);
+        let md = "see [the *doc*](u) or [a link with words](v)\n|[x](w)|y|";
+        let text = skin.text(md, Some(16));
+        assert_eq!(text.to_plain_string(), "see the doc or a\nlink with words\n│x│y│\n");
+        let link = |text: &str, url: &str, spans: Vec<(usize, std::ops::Range<usize>)>| Link {
+            text: text.to_string(),
+            url: url.to_string(),
+            spans,
+        };
+        assert_eq!(
+            text.links(),
+            vec![
+                link("the doc", "u", vec![(0, 4..11)]),
+                link("a link with words", "v", vec![(0, 15..16), (1, 0..15)]),
+                link("x", "w", vec![(2, 1..2)]),
+            ],
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use {
+    crate::document::Heading,
+    std::collections::HashMap,
+};
+
+/// An entry of the table of contents of a markdown text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TocEntry {
+    /// the level of the header, starting at 1
+    pub level: u8,
+    /// the text of the header, without the markdown marks
+    pub text: String,
+    /// the anchor of the header, as GitHub makes it: for
+    /// example `some-title` for the `Some Title!` header
+    pub anchor: String,
+}
+
+/// Make the anchor of a header text the way GitHub does: lowercased,
+/// with spaces replaced with hyphens and punctuation removed
+pub(crate) fn slugify(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            _ if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Build the entries of the headings, the anchors being made
+/// unique with a numbered suffix (`title`, `title-1`, etc.)
+pub(crate) fn entries(headings: &[Heading<'_, '_>]) -> Vec<TocEntry> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    headings
+        .iter()
+        .map(|heading| {
+            let text = heading.text();
+            let slug = slugify(&text);
+            let count = counts.entry(slug.clone()).or_insert(0);
+            let anchor = if *count == 0 {
+                slug
+            } else {
+                format!("{}-{}", slug, count)
+            };
+            *count += 1;
+            TocEntry {
+                level: heading.level,
+                text,
+                anchor,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod toc_tests {
+
+    use {
+        super::*,
+        crate::*,
+    };
+
+    #[test]
+    fn check_slugs() {
+        assert_eq!(slugify("Some Title!"), "some-title");
+        assert_eq!(slugify(" What's new in v0.2? "), "whats-new-in-v02");
+        assert_eq!(slugify("snake_case & kebab-case"), "snake_case--kebab-case");
+        assert_eq!(slugify("Été"), "été");
+    }
+
+    #[test]
+    fn check_table_of_contents() {
+        let md = "# The *Title*\ntext\n## Usage\n### Options!\n## Usage\n```\n# not a title\n```";
+        let toc = MadSkin::default().table_of_contents(md);
+        let entry = |level, text: &str, anchor: &str| TocEntry {
+            level,
+            text: text.to_string(),
+            anchor: anchor.to_string(),
+        };
+        assert_eq!(
+            toc,
+            vec![
+                entry(1, "The Title", "the-title"),
+                entry(2, "Usage", "usage"),
+                entry(3, "Options!", "options"),
+                entry(2, "Usage", "usage-1"),
+            ],
+        );
+    }
+}
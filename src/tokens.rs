@@ -7,6 +7,7 @@ use {
 #[derive(Debug)]
 pub(crate) struct Token<'s> {
     pub compound: Compound<'s>,
+    pub compound_idx: usize,
     pub blank: bool,
     pub width: usize,
     pub start_in_compound: usize,
@@ -28,10 +29,13 @@ pub(crate) fn tokenize<'s, 'c>(
     max_token_width: usize,
 ) -> Vec<Token<'s>> {
     let mut tokens: Vec<Token<'s>> = Vec::new();
-    for compound in &composite.compounds {
+    // inline code is cut like normal text but the spaces of code
+    // blocks must be kept
+    let in_code_block = composite.is_code();
+    for (compound_idx, compound) in composite.compounds.iter().enumerate() {
         let mut token: Option<Token> = None;
         for (idx, char) in compound.src.char_indices() {
-            let blank = char.is_whitespace() && !compound.code;
+            let blank = char.is_whitespace() && !in_code_block;
             let char_width = char.width().unwrap_or(0);
             if let Some(token) = token.as_mut() {
                 if token.blank == blank && token.width + char_width <= max_token_width {
@@ -42,6 +46,7 @@ pub(crate) fn tokenize<'s, 'c>(
             }
             let new_token = Token {
                 compound: compound.clone(),
+                compound_idx,
                 blank,
                 width: char_width,
                 start_in_compound: idx,
@@ -1,5 +1,8 @@
 use {
-    super::*,
+    super::{
+        undo_stack::{EditKind, UndoStack},
+        *,
+    },
     crate::*,
     crossterm::{
         cursor,
@@ -15,9 +18,161 @@ use {
             SetBackgroundColor,
         },
     },
-    std::io::Write,
+    std::{
+        borrow::Cow,
+        io::Write,
+        sync::{Mutex, PoisonError},
+    },
+    unicode_width::UnicodeWidthStr,
 };
 
+type CharFilter = Box<dyn Fn(char) -> bool + Send + Sync>;
+type Highlighter = Box<dyn Fn(&str) -> Vec<(usize, usize, CompoundStyle)> + Send + Sync>;
+
+/// What the tab key does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TabBehavior {
+    /// the key isn't handled, and may be used by the application
+    Ignored,
+    /// some spaces are inserted
+    Spaces(u8),
+    /// a tab char is inserted
+    Char,
+}
+type ChangeCallback = Box<dyn FnMut(&InputFieldContent) + Send + Sync>;
+
+/// What an event applied to an input field did, from the
+/// most significant to the least one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFieldAction {
+    /// the content changed
+    ContentChanged,
+    /// the input got the focus (and its content didn't change)
+    Focused,
+    /// the event was used without changing the content: the
+    /// cursor, the selection or the scroll may have changed
+    Moved,
+    /// the event wasn't used
+    Ignored,
+}
+
+/// The shape of the cursor of an input field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorShape {
+    /// the char under the cursor is displayed in reverse video
+    Block,
+    /// the char under the cursor is underlined
+    Underline,
+    /// a thin bar at the left of the cell, drawn in place of the
+    /// cell when it's blank (for example at the end of the line)
+    /// and replaced with an underline when it's a visible char
+    Bar,
+}
+
+impl Default for CursorShape {
+    fn default() -> Self {
+        Self::Block
+    }
+}
+
+impl CursorShape {
+    /// the attribute added to the normal style to draw the cursor
+    const fn attribute(self) -> Attribute {
+        match self {
+            Self::Block => Attribute::Reverse,
+            Self::Underline | Self::Bar => Attribute::Underlined,
+        }
+    }
+    const fn terminal_shape(self) -> cursor::CursorShape {
+        match self {
+            Self::Block => cursor::CursorShape::Block,
+            Self::Underline => cursor::CursorShape::UnderScore,
+            Self::Bar => cursor::CursorShape::Line,
+        }
+    }
+}
+
+/// the char drawn for a bar cursor on a blank cell
+const BAR_CURSOR: char = '▏';
+
+/// the char displayed in place of every char in password mode,
+/// unless another one is set
+const DEFAULT_PASSWORD_CHAR: char = '*';
+
+/// A part of a line, as displayed on one row of the area
+#[derive(Debug, Clone, Copy)]
+struct VisualRow {
+    /// index of the line in the content
+    y: usize,
+    /// index of the first char of the row in the line
+    start: usize,
+    /// index of the char after the last one of the row
+    end: usize,
+    /// whether it's the last row of the line
+    last: bool,
+}
+
+/// The drawing of some columns of a row
+#[derive(Debug, PartialEq, Eq)]
+struct RenderedCell {
+    /// the first column, relative to the area
+    col: usize,
+    /// the number of columns
+    width: usize,
+    bytes: Vec<u8>,
+}
+
+/// The cells of a row being rendered
+#[derive(Default)]
+struct RowCells {
+    cells: Vec<RenderedCell>,
+    /// the column of the next cell
+    col: usize,
+}
+
+impl RowCells {
+    /// add a cell of the given width, returning the buffer
+    /// in which it must be written
+    fn cell(&mut self, width: usize) -> &mut Vec<u8> {
+        self.cells.push(RenderedCell {
+            col: self.col,
+            width,
+            bytes: Vec::new(),
+        });
+        self.col += width;
+        &mut self.cells.last_mut().unwrap().bytes
+    }
+}
+
+/// The rendering of the whole area of an input field
+struct RenderedFrame {
+    area: Area,
+    rows: Vec<Vec<RenderedCell>>,
+    /// the screen position of the terminal's cursor, if it must be shown
+    hardware_cursor_pos: Option<(u16, u16)>,
+}
+
+#[cfg(feature = "secure")]
+impl RenderedFrame {
+    /// overwrite the bytes of the cells with zeros
+    fn scrub(&mut self) {
+        for cell in self.rows.iter_mut().flatten() {
+            super::input_field_content::scrub_bytes(&mut cell.bytes);
+        }
+    }
+}
+
+impl VisualRow {
+    /// return the biggest x a cursor can have on this row
+    fn max_x(self) -> usize {
+        if self.last {
+            self.end
+        } else {
+            self.end - 1
+        }
+    }
+}
+
 /// A simple input field, managing its cursor position and
 /// either handling the events you give it or being managed
 /// through direct manipulation functions
@@ -27,18 +182,122 @@ use {
 /// textarea) you should set an area with a height of more
 /// than 1 and allow newline to be created on keyboard with
 /// `new_line_on`.
+///
+/// Edits are recorded so that they can be undone and redone
+/// (with Ctrl-Z and Ctrl-Y or by calling `undo` and `redo`).
+///
+/// As in Emacs, Ctrl-K kills the end of the line. The killed text
+/// is yanked (pasted) with Alt-Y, as Ctrl-Y is the redo key.
+///
+/// Text can be selected with the arrow, home and end keys
+/// while holding shift.
+///
+/// The selection can be copied or cut with Ctrl-C and Ctrl-X,
+/// then pasted with Ctrl-V. Those keys use a clipboard internal
+/// to the input field: if you want to use the system's clipboard,
+/// handle them yourself with `copy_selection`, `cut_selection`
+/// and `paste`.
+///
+/// In password mode, and when the content is secure, nothing can
+/// be copied, cut or kept in the kill buffer.
+///
+/// An input field can be made read only, to let the user
+/// scroll and select (for example in a long log) without
+/// editing the content.
+///
+/// Long lines are either scrolled horizontally or, when
+/// `set_wrap(true)` was called, wrapped on several rows.
+///
+/// In forms with many fields, you may call `set_partial_redraw(true)`
+/// so that only the cells which changed are written on `display_on`.
+///
+/// For a REPL-like prompt, submitted entries can be recorded with
+/// `push_history` then, after `set_history_navigation(true)`, recalled
+/// with the up and down keys when the cursor is on the first or
+/// last line.
 pub struct InputField {
     content: InputFieldContent,
     area: Area,
     focused_style: CompoundStyle,
     unfocused_style: CompoundStyle,
     cursor_style: CompoundStyle,
-    /// when true, the display will have stars instead of the normal chars
+    cursor_shape: CursorShape,
+    selection_style: CompoundStyle,
+    /// the fixed end of the selection, the other one being the cursor
+    selection_anchor: Option<Pos>,
+    /// text displayed when the input is empty
+    placeholder: String,
+    placeholder_style: CompoundStyle,
+    /// what is displayed in place of the hidden parts of long lines
+    ellipsis: Cow<'static, str>,
+    /// which part of the too long lines is hidden when not focused
+    fit_mode: Option<FitMode>,
+    /// when true, the display will have stars (or the password char)
+    /// instead of the normal chars
     pub password_mode: bool,
+    password_char: char,
+    /// when true, the chars are displayed even in password mode
+    reveal: bool,
     /// if not focused, the content will be displayed as text
     focused: bool,
+    /// when wrapping, scroll.y is expressed in rows, not lines
     scroll: Pos,
+    /// whether long lines are wrapped instead of scrolled
+    wrap: bool,
+    /// when wrapping, the visual column the cursor tries to reach
+    /// when moving between rows, with the position it was valid for
+    row_goal: Option<(usize, Pos)>,
+    /// number of lines scrolled on a wheel event
+    wheel_scroll_lines: usize,
+    /// whether the terminal's cursor is used instead of cursor_style
+    hardware_cursor: bool,
+    /// whether a gutter with line numbers is displayed at left
+    line_numbers: bool,
+    line_number_style: CompoundStyle,
+    scrollbar_style: ScrollBarStyle,
     new_line_keys: Vec<KeyEvent>,
+    /// when true, the user can't change the content
+    read_only: bool,
+    /// when true, typed chars replace the ones under the cursor
+    overwrite: bool,
+    /// style of the cursor in read only mode (hidden when None)
+    read_only_cursor_style: Option<CompoundStyle>,
+    /// maximal number of chars (line breaks included)
+    max_len: Option<usize>,
+    tab_behavior: TabBehavior,
+    /// chars not passing this filter can't be inserted
+    char_filter: Option<CharFilter>,
+    /// gives the styled byte ranges of a line
+    highlighter: Option<Highlighter>,
+    undo_stack: UndoStack,
+    /// called after every change of the content
+    on_change: Option<ChangeCallback>,
+    /// whether the content changed since the last call to take_changed
+    changed: bool,
+    /// whether a click focused the input since the last call
+    /// to take_focus_gained
+    focus_gained: bool,
+    /// what was last copied or cut with Ctrl-C or Ctrl-X
+    clipboard: String,
+    /// what was killed with Ctrl-K
+    kill_buffer: String,
+    /// whether the user is selecting with the mouse
+    dragging: bool,
+    /// whether the last action was a kill, in which case
+    /// a new kill appends to the kill buffer
+    killing: bool,
+    /// whether display_on only writes the cells which changed
+    partial_redraw: bool,
+    /// the frame last written by a partial redraw
+    last_frame: Mutex<Option<RenderedFrame>>,
+    /// the submitted entries, oldest first
+    history: Vec<String>,
+    /// whether up and down recall the entries of the history
+    history_navigation: bool,
+    /// the index of the recalled entry, if any
+    history_idx: Option<usize>,
+    /// what was typed before the navigation in the history started
+    history_draft: String,
 }
 
 impl Default for InputField {
@@ -47,19 +306,33 @@ impl Default for InputField {
     }
 }
 
+/// wrap a content function moving the cursor. The selection,
+/// if any, is removed.
 macro_rules! wrap_content_fun {
     ($fun:ident) => {
         pub fn $fun(&mut self) -> bool {
+            self.killing = false;
+            let had_selection = self.selection_anchor.take().is_some();
             if self.content.$fun() {
                 self.fix_scroll();
                 true
             } else {
-                false
+                had_selection
             }
         }
     };
 }
 
+/// wrap a content function modifying the content, so that
+/// the change can be undone
+macro_rules! wrap_content_edit_fun {
+    ($fun:ident) => {
+        pub fn $fun(&mut self) -> bool {
+            self.edit(EditKind::Other, |content| content.$fun())
+        }
+    };
+}
+
 impl InputField {
 
     pub const ENTER: KeyEvent = KeyEvent {
@@ -70,6 +343,34 @@ impl InputField {
         code: KeyCode::Enter,
         modifiers: KeyModifiers::ALT,
     };
+    pub const CTRL_Z: KeyEvent = KeyEvent {
+        code: KeyCode::Char('z'),
+        modifiers: KeyModifiers::CONTROL,
+    };
+    pub const CTRL_Y: KeyEvent = KeyEvent {
+        code: KeyCode::Char('y'),
+        modifiers: KeyModifiers::CONTROL,
+    };
+    pub const CTRL_K: KeyEvent = KeyEvent {
+        code: KeyCode::Char('k'),
+        modifiers: KeyModifiers::CONTROL,
+    };
+    pub const ALT_Y: KeyEvent = KeyEvent {
+        code: KeyCode::Char('y'),
+        modifiers: KeyModifiers::ALT,
+    };
+    pub const CTRL_C: KeyEvent = KeyEvent {
+        code: KeyCode::Char('c'),
+        modifiers: KeyModifiers::CONTROL,
+    };
+    pub const CTRL_X: KeyEvent = KeyEvent {
+        code: KeyCode::Char('x'),
+        modifiers: KeyModifiers::CONTROL,
+    };
+    pub const CTRL_V: KeyEvent = KeyEvent {
+        code: KeyCode::Char('v'),
+        modifiers: KeyModifiers::CONTROL,
+    };
 
     pub fn new(area: Area) -> Self {
         let focused_style = CompoundStyle::default();
@@ -82,10 +383,47 @@ impl InputField {
             focused_style,
             unfocused_style,
             cursor_style,
+            cursor_shape: CursorShape::Block,
+            selection_style: CompoundStyle::with_bg(gray(8)),
+            selection_anchor: None,
+            placeholder: String::new(),
+            placeholder_style: CompoundStyle::with_attr(Attribute::Dim),
+            ellipsis: crate::get_default_skin().ellipsis_str.clone(),
+            fit_mode: None,
             password_mode: false,
+            password_char: DEFAULT_PASSWORD_CHAR,
+            reveal: false,
             focused: true,
             scroll: Pos::default(),
+            wrap: false,
+            row_goal: None,
+            wheel_scroll_lines: 3,
+            hardware_cursor: false,
+            line_numbers: false,
+            line_number_style: CompoundStyle::with_fg(gray(12)),
+            scrollbar_style: crate::get_default_skin().scrollbar.clone(),
             new_line_keys: Vec::default(),
+            read_only: false,
+            overwrite: false,
+            read_only_cursor_style: None,
+            max_len: None,
+            char_filter: None,
+            highlighter: None,
+            tab_behavior: TabBehavior::Ignored,
+            undo_stack: UndoStack::default(),
+            on_change: None,
+            changed: false,
+            focus_gained: false,
+            clipboard: String::new(),
+            kill_buffer: String::new(),
+            killing: false,
+            dragging: false,
+            partial_redraw: false,
+            last_frame: Mutex::new(None),
+            history: Vec::new(),
+            history_navigation: false,
+            history_idx: None,
+            history_draft: String::new(),
         }
     }
     pub fn set_mono_line(&mut self) {
@@ -119,7 +457,7 @@ impl InputField {
         self.fix_scroll();
     }
     pub fn set_area(&mut self, area: Area) {
-        if &self.area != &area {
+        if self.area != area {
             self.area = area;
             self.fix_scroll();
         }
@@ -128,282 +466,1962 @@ impl InputField {
         &self.area
     }
     /// return the current scrolling state on both axis
+    /// (when wrapping, `y` is the index of the first displayed
+    /// row instead of the first displayed line)
     pub const fn scroll(&self) -> Pos {
         self.scroll
     }
-    /// Tell the input to be or not focused
-    pub fn set_focus(&mut self, b: bool) {
-        self.focused = b;
-        // there's no reason to change the scroll when unfocusing
-        if self.focused {
+    /// Set whether long lines should be wrapped at word boundaries
+    /// (instead of scrolled horizontally).
+    ///
+    /// Content positions stay the ones of the lines.
+    pub fn set_wrap(&mut self, b: bool) {
+        if self.wrap != b {
+            self.wrap = b;
+            self.scroll = Pos::default();
             self.fix_scroll();
         }
     }
-    pub const fn focused(&self) -> bool {
-        self.focused
-    }
-    pub fn set_normal_style(&mut self, style: CompoundStyle) {
-        self.focused_style = style;
-        self.cursor_style = self.focused_style.clone();
-        self.cursor_style.add_attr(Attribute::Reverse);
-    }
-    pub fn set_unfocused_style(&mut self, style: CompoundStyle) {
-        self.unfocused_style = style;
+    pub const fn is_wrap(&self) -> bool {
+        self.wrap
     }
-    pub const fn content(&self) -> &InputFieldContent {
-        &self.content
+    /// Set whether the terminal's cursor should be used instead of
+    /// drawing the cursor with the cursor style (the default).
+    ///
+    /// When true, `display_on` moves the terminal's cursor to the
+    /// insertion point and shows it. You're responsible for hiding it
+    /// when it's no longer needed (for example when this is set back
+    /// to false or when the input loses focus).
+    pub fn set_hardware_cursor(&mut self, b: bool) {
+        self.hardware_cursor = b;
     }
-    pub fn get_content(&self) -> String {
-        self.content.to_string()
+    /// Set whether line numbers should be displayed in a gutter at left
+    pub fn set_line_numbers(&mut self, b: bool) {
+        if self.line_numbers != b {
+            self.line_numbers = b;
+            self.fix_scroll();
+        }
     }
-    pub fn is_empty(&self) -> bool {
-        self.content.is_empty()
+    pub fn set_line_number_style(&mut self, style: CompoundStyle) {
+        self.line_number_style = style;
     }
-    /// tell whether the content of the input is equal
-    ///  to the argument
-    pub fn is_content(&self, s: &str) -> bool {
-        self.content.is_str(s)
+    /// Set whether `display_on` only writes the cells which changed
+    /// since its previous call, instead of the whole area.
+    ///
+    /// This supposes nothing else was drawn over the input in between:
+    /// call `invalidate` after a screen clear.
+    pub fn set_partial_redraw(&mut self, b: bool) {
+        self.partial_redraw = b;
+        self.invalidate();
     }
-    /// change the content to the new one and
-    ///  put the cursor at the end **if** the
-    ///  content is different from the previous one.
-    pub fn set_str<S: AsRef<str>>(&mut self, s: S) {
-        self.content.set_str(s);
-        self.fix_scroll();
+    /// forget what was drawn, so that the next partial redraw
+    /// writes the whole area
+    pub fn invalidate(&mut self) {
+        *self.last_frame.get_mut().unwrap_or_else(PoisonError::into_inner) = None;
     }
-    pub fn insert_new_line(&mut self) -> bool {
-        self.content.insert_new_line();
-        self.fix_scroll();
-        true
+    /// set the chars, styles and side of the scrollbar (by default
+    /// the one of the default skin)
+    pub fn set_scrollbar_style(&mut self, style: ScrollBarStyle) {
+        self.scrollbar_style = style;
     }
-    /// put a char at cursor position (and increment this
-    /// position).
-    pub fn put_char(&mut self, c: char) -> bool {
-        self.content.insert_char(c);
-        self.fix_scroll();
-        true
+    /// return the width of the line numbers gutter (including
+    /// the space separating it from the text), which is 0 when
+    /// there's no room for it
+    fn gutter_width(&self) -> usize {
+        if self.line_numbers {
+            let width = self.content.line_count().to_string().len() + 1;
+            if width < self.area.width as usize {
+                width
+            } else {
+                0
+            }
+        } else {
+            0
+        }
     }
-    pub fn clear(&mut self) {
-        self.content.clear();
-        self.fix_scroll();
+    /// return the width taken before the gutter and the text by
+    /// the scrollbar, when there's one on the left side
+    fn left_scrollbar_width(&self) -> usize {
+        if self.scrollbar_style.is_left() && self.has_scrollbar(self.row_count()) {
+            1
+        } else {
+            0
+        }
     }
-    /// remove the char at cursor position, if any
-    pub fn del_char_below(&mut self) -> bool {
-        self.content.del_char_below()
+    /// return the width available for the text and the scrollbar
+    fn text_width(&self) -> usize {
+        (self.area.width as usize).saturating_sub(self.gutter_width())
     }
-    /// Insert the string on cursor point, as if it was typed
-    pub fn insert_str<S: AsRef<str>>(&mut self, s: S) {
-        self.content.insert_str(s);
-        self.fix_scroll();
+    /// tell whether a scrollbar is displayed for the given number
+    /// of rows, that is when they don't all fit the height.
+    ///
+    /// This is the only place where this is decided, so that the
+    /// width of the text always agrees with the drawn scrollbar.
+    fn has_scrollbar(&self, row_count: usize) -> bool {
+        row_count > self.area.height as usize
     }
-
-    wrap_content_fun!(move_up);
-    wrap_content_fun!(move_down);
-    wrap_content_fun!(move_left);
-    wrap_content_fun!(move_right);
-    wrap_content_fun!(move_to_start);
-    wrap_content_fun!(move_to_end);
-    wrap_content_fun!(move_to_line_start);
-    wrap_content_fun!(move_to_line_end);
-    wrap_content_fun!(move_word_left);
-    wrap_content_fun!(move_word_right);
-    wrap_content_fun!(del_char_left);
-    wrap_content_fun!(del_word_left);
-    wrap_content_fun!(del_word_right);
-
-    pub fn page_up(&mut self) -> bool {
-        if self.content.move_lines_up(self.area.height as usize) {
-            self.fix_scroll();
-            true
+    /// return the width available for the text when there are
+    /// the given number of rows, the scrollbar being removed
+    fn visible_text_width(&self, row_count: usize) -> usize {
+        let width = self.text_width();
+        if self.has_scrollbar(row_count) {
+            width.saturating_sub(1)
         } else {
-            false
+            width
         }
     }
-
-    pub fn page_down(&mut self) -> bool {
-        if self.content.move_lines_down(self.area.height as usize) {
-            self.fix_scroll();
-            true
+    /// return the number of rows of the content, which are
+    /// the lines when not wrapping
+    fn row_count(&self) -> usize {
+        if self.wrap {
+            self.visual_rows().len()
         } else {
-            false
+            self.content.line_count()
         }
     }
-
-    /// apply an event being a key
-    ///
+    /// Scroll the content up (negative delta) or down, without
+    /// moving the cursor.
     ///
-    /// This function handles a few events like deleting a
-    /// char, or going to the start (home key) or end (end key)
-    /// of the input. If you want to totally handle events, you
-    /// may call function like `put_char` and `del_char_left`
-    /// directly.
-    pub fn apply_key_event(&mut self, key: KeyEvent) -> bool {
-        if !self.focused {
-            return false;
-        }
-        if self.new_line_keys.contains(&key) {
-            self.insert_new_line();
-            return true;
-        }
-        use crossterm::event::{
-            KeyModifiers as Mod,
-        };
-        match (key.code, key.modifiers) {
-            (code, Mod::NONE) | (code, Mod::SHIFT) => self.apply_keycode_event(code),
-            _ => false,
+    /// Return true if the scroll changed.
+    pub fn scroll_lines(&mut self, delta: i32) -> bool {
+        let max_scroll = self.row_count().saturating_sub(self.area.height as usize);
+        let scroll = (self.scroll.y as i64 + delta as i64).clamp(0, max_scroll as i64) as usize;
+        if scroll == self.scroll.y {
+            false
+        } else {
+            self.scroll.y = scroll;
+            true
         }
     }
-
-    /// apply an event being a key without modifier.
+    /// Scroll the content if the pointer, at the given position, is
+    /// over the input. `lines` is -1 for a wheel up, 1 for a wheel down.
     ///
-    /// You don't usually call this function but the more
-    /// general `apply_event`. This one is useful when you
-    /// manage events mostly yourselves.
-    pub fn apply_keycode_event(&mut self, code: KeyCode) -> bool {
-        if !self.focused {
-            return false;
-        }
-        match code {
-            KeyCode::Home => self.move_to_line_start(),
-            KeyCode::End => self.move_to_line_end(),
-            KeyCode::Char(c) => self.put_char(c),
-            KeyCode::Up => self.move_up(),
-            KeyCode::Down => self.move_down(),
-            KeyCode::Left => self.move_left(),
-            KeyCode::PageUp => self.page_up(),
-            KeyCode::PageDown => self.page_down(),
-            KeyCode::Right => self.move_right(),
-            KeyCode::Backspace => self.del_char_left(),
-            KeyCode::Delete => self.del_char_below(),
-            _ => false,
-        }
+    /// Return true if the scroll changed.
+    pub fn apply_wheel_event(&mut self, x: u16, y: u16, lines: i32) -> bool {
+        self.area.contains(x, y)
+            && self.scroll_lines(lines * self.wheel_scroll_lines as i32)
     }
-
-    /// Apply a click event
-    pub fn apply_click_event(&mut self, x: u16, y: u16) -> bool {
-        if self.area.contains(x, y) {
-            if self.focused {
-                self.content.set_cursor_pos(Pos {
-                    x: (x - self.area.left) as usize + self.scroll.x,
-                    y: (y - self.area.top) as usize + self.scroll.y,
+    /// set the number of lines scrolled by one step of the
+    /// mouse wheel (default is 3)
+    pub fn set_wheel_scroll_lines(&mut self, n: usize) {
+        self.wheel_scroll_lines = n;
+    }
+    /// compute the rows of the wrapped content for a given width
+    fn visual_rows_for_width(&self, width: usize) -> Vec<VisualRow> {
+        let mut rows = Vec::new();
+        for (y, line) in self.content.lines().iter().enumerate() {
+            let starts = fit::wrap::wrap_chars_with(&line.chars, width, |c| self.char_width(c));
+            for (i, &start) in starts.iter().enumerate() {
+                let last = i + 1 == starts.len();
+                rows.push(VisualRow {
+                    y,
+                    start,
+                    end: if last { line.chars.len() } else { starts[i + 1] },
+                    last,
                 });
-            } else {
-                self.focused = true;
             }
-            true
+        }
+        rows
+    }
+    /// compute the rows of the wrapped content, taking into
+    /// account the width of the scrollbar if there's one
+    fn visual_rows(&self) -> Vec<VisualRow> {
+        let width = self.text_width();
+        let rows = self.visual_rows_for_width(width);
+        if self.has_scrollbar(rows.len()) && width > 1 {
+            self.visual_rows_for_width(width - 1)
         } else {
-            false
+            rows
         }
     }
-
-    /// apply the passed event to change the state (content, cursor)
-    ///
-    /// Return true when the event was used.
-    pub fn apply_event(&mut self, event: &Event) -> bool {
-        match event {
-            Event::Click(x, y, ..) => {
-                self.apply_click_event(*x, *y)
-            }
-            Event::Key(KeyEvent{code, modifiers})
-                if (modifiers.is_empty()||*modifiers==KeyModifiers::SHIFT)
-            => {
-                self.apply_keycode_event(*code)
-            }
-            _ => false,
+    /// return the index of the row containing the cursor
+    fn cursor_row_idx(&self, rows: &[VisualRow]) -> usize {
+        let pos = self.content.cursor_pos();
+        rows.iter()
+            .rposition(|row| row.y == pos.y && row.start <= pos.x)
+            .unwrap_or(0)
+    }
+    /// move the cursor up (negative delta) or down of some
+    /// rows, when wrapping
+    fn move_rows(&mut self, delta: isize) -> bool {
+        let rows = self.visual_rows();
+        let pos = self.content.cursor_pos();
+        let idx = self.cursor_row_idx(&rows);
+        let target = (idx as isize + delta).clamp(0, rows.len() as isize - 1) as usize;
+        if target == idx {
+            return false;
         }
+        let lines = self.content.lines();
+        let col = match self.row_goal {
+            Some((col, goal_pos)) if goal_pos == pos => col,
+            _ => self.chars_width(&lines[pos.y].chars[rows[idx].start..pos.x]),
+        };
+        let row = rows[target];
+        let row_chars = &lines[row.y].chars[row.start..row.end];
+        let x = (row.start + self.char_idx_at_col(row_chars, col)).min(row.max_x());
+        let pos = Pos { x, y: row.y };
+        self.content.set_cursor_pos(pos);
+        self.row_goal = Some((col, pos));
+        true
     }
-
-    fn fix_scroll(&mut self) {
-        let mut width = self.area.width as usize;
-        let height = self.area.height as usize;
-        let lines = &self.content.lines();
-        let has_y_scroll = lines.len() > height;
-        if has_y_scroll {
-            width -= 1;
+    /// return the number of cells the char takes on screen
+    fn char_width(&self, c: char) -> usize {
+        if self.is_masked() {
+            1
         } else {
-            self.scroll.y = 0;
+            fit::wrap::char_width(c)
         }
-        let pos = self.content.cursor_pos();
-
-        if has_y_scroll {
-            if self.scroll.y + height > lines.len() {
-                self.scroll.y = lines.len() - height;
-            }
-            if self.focused {
-                // we must ensure the cursor is visible
-                if self.scroll.y > pos.y {
-                    self.scroll.y = pos.y;
-                    if self.scroll.y > 0 && height > 4 {
-                        self.scroll.y -= 1;
-                    }
-                } else if pos.y >= self.scroll.y + height {
-                    self.scroll.y = pos.y - height + 1;
-                    if pos.y + 1 < lines.len() {
-                        self.scroll.y -= 1;
-                    }
-                }
+    }
+    /// return the char displayed for the given one
+    fn displayed_char(&self, c: char) -> char {
+        if self.is_masked() {
+            self.password_char
+        } else if c == '\t' {
+            ' '
+        } else {
+            c
+        }
+    }
+    /// return the number of cells the chars take on screen
+    fn chars_width(&self, chars: &[char]) -> usize {
+        chars.iter().map(|&c| self.char_width(c)).sum()
+    }
+    /// return the index of the char covering the given column
+    /// (or the number of chars if the column is after them)
+    fn char_idx_at_col(&self, chars: &[char], col: usize) -> usize {
+        let mut end = 0;
+        for (idx, &c) in chars.iter().enumerate() {
+            end += self.char_width(c);
+            if end > col {
+                return idx;
             }
         }
-
-        let line_len = self.content.current_line().chars.len();
-        if line_len < width {
-            self.scroll.x = 0;
+        chars.len()
+    }
+    /// move the cursor up of some lines, or rows when wrapping
+    fn move_lines_up(&mut self, n: usize) -> bool {
+        self.killing = false;
+        let had_selection = self.selection_anchor.take().is_some();
+        let moved = if self.wrap {
+            self.move_rows(-(n as isize))
         } else {
-            if self.focused {
-                // we don't show ellipsis if the width is below 4
-                // so we need less margin
-                if width < 4 {
-                    if pos.x < 2 {
-                        self.scroll.x = 0;
-                    } else if pos.x < self.scroll.x + 1 {
-                        self.scroll.x = pos.x - 1;
-                    } else if pos.x > self.scroll.x + width {
-                        self.scroll.x = pos.x + 1 - width;
-                    }
-                } else {
-                    if pos.x < self.scroll.x + 2 {
-                        if pos.x < 2 {
-                            self.scroll.x = 0;
-                        } else {
-                            self.scroll.x = pos.x - 2;
-                        }
-                    } else if pos.x > self.scroll.x + width - 2 {
-                        self.scroll.x = pos.x + 2 - width;
-                    }
-                }
-            }
-            if self.scroll.x + width > line_len + 1 {
-                self.scroll.x = line_len + 1 - width;
-            }
+            self.content.move_lines_up(n)
+        };
+        if moved {
+            self.fix_scroll();
+            true
+        } else {
+            had_selection
         }
     }
-
-    /// Render the input field on screen.
-    ///
-    /// All rendering must be explicitely called, no rendering is
-    /// done on functions changing the state.
-    ///
-    /// w is typically either stderr or stdout. This function doesn't
-    /// flush by itself (useful to avoid flickering)
-    pub fn display_on<W: Write>(&self, w: &mut W) -> Result<(), Error> {
-        let normal_style = if self.focused {
-            &self.focused_style
+    /// move the cursor down of some lines, or rows when wrapping
+    fn move_lines_down(&mut self, n: usize) -> bool {
+        self.killing = false;
+        let had_selection = self.selection_anchor.take().is_some();
+        let moved = if self.wrap {
+            self.move_rows(n as isize)
         } else {
-            &self.unfocused_style
+            self.content.move_lines_down(n)
         };
+        if moved {
+            self.fix_scroll();
+            true
+        } else {
+            had_selection
+        }
+    }
+    /// Tell the input to be or not focused
+    pub fn set_focus(&mut self, b: bool) {
+        self.focused = b;
+        // there's no reason to change the scroll when unfocusing
+        if self.focused {
+            self.fix_scroll();
+        } else {
+            // a focus lost since the click isn't to be reported
+            self.focus_gained = false;
+        }
+    }
+    pub const fn focused(&self) -> bool {
+        self.focused
+    }
+    /// Tell whether a click focused the input since the previous
+    /// call to this function, and reset this flag.
+    ///
+    /// This lets the application unfocus the other inputs.
+    pub fn take_focus_gained(&mut self) -> bool {
+        std::mem::replace(&mut self.focus_gained, false)
+    }
+    /// focus the input, on a click
+    fn gain_focus(&mut self) {
+        if !self.focused {
+            self.focused = true;
+            self.focus_gained = true;
+        }
+    }
+    pub fn set_normal_style(&mut self, style: CompoundStyle) {
+        self.focused_style = style;
+        self.update_cursor_style();
+    }
+    /// Set the shape of the cursor (a block by default).
+    ///
+    /// With a hardware cursor, the terminal's cursor is given this
+    /// shape, when the terminal supports it.
+    pub fn set_cursor_shape(&mut self, shape: CursorShape) {
+        self.cursor_shape = shape;
+        self.update_cursor_style();
+    }
+    pub const fn cursor_shape(&self) -> CursorShape {
+        self.cursor_shape
+    }
+    /// the shape the cursor is drawn with, which is a block
+    /// in overwrite mode
+    fn displayed_cursor_shape(&self) -> CursorShape {
+        if self.overwrite {
+            CursorShape::Block
+        } else {
+            self.cursor_shape
+        }
+    }
+    fn update_cursor_style(&mut self) {
+        self.cursor_style = self.focused_style.clone();
+        self.cursor_style.add_attr(self.displayed_cursor_shape().attribute());
+    }
+    /// queue the cell of the cursor, for a char displayed as `c`
+    fn queue_cursor_cell(
+        &self,
+        cell: &mut Vec<u8>,
+        cursor_style: &CompoundStyle,
+        normal_style: &CompoundStyle,
+        c: char,
+    ) -> Result<(), Error> {
+        if self.displayed_cursor_shape() == CursorShape::Bar && c == ' ' {
+            // the bar hides nothing
+            normal_style.queue(cell, BAR_CURSOR)?;
+        } else {
+            cursor_style.queue(cell, c)?;
+        }
+        Ok(())
+    }
+    pub fn set_unfocused_style(&mut self, style: CompoundStyle) {
+        self.unfocused_style = style;
+    }
+    /// set the style of the selected chars
+    pub fn set_selection_style(&mut self, style: CompoundStyle) {
+        self.selection_style = style;
+    }
+    /// Set whether the content can be changed by the user.
+    ///
+    /// In read only mode, edition functions (put_char, del_char_left,
+    /// insert_new_line, etc.) do nothing and return false. The cursor
+    /// can still be moved and the selection copied.
+    ///
+    /// The content can still be changed with set_str and clear.
+    pub fn set_read_only(&mut self, b: bool) {
+        self.read_only = b;
+    }
+    pub const fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+    /// Set whether typed chars replace the ones under the cursor
+    /// instead of being inserted (at the end of a line, or when
+    /// there's a selection, chars are still inserted).
+    ///
+    /// The Insert key toggles this mode. While it's on, the cursor
+    /// is drawn as a block whatever its shape.
+    pub fn set_overwrite(&mut self, b: bool) {
+        self.overwrite = b;
+        self.update_cursor_style();
+    }
+    pub const fn is_overwrite(&self) -> bool {
+        self.overwrite
+    }
+    /// tell whether typing the char would replace the one
+    /// under the cursor
+    fn overwrites(&self, c: char) -> bool {
+        self.overwrite
+            && c != '\n'
+            && self.selected_range().is_none()
+            && self.content.cursor_pos().x < self.content.current_line().chars.len()
+    }
+    /// set the style of the cursor in read only mode. By default
+    /// (i.e. with None), the cursor isn't displayed when read only.
+    pub fn set_read_only_cursor_style(&mut self, style: Option<CompoundStyle>) {
+        self.read_only_cursor_style = style;
+    }
+    /// set a text to display, dimmed, when the input is empty
+    pub fn set_placeholder<S: Into<String>>(&mut self, s: S) {
+        self.placeholder = s.into();
+    }
+    /// set the style of the placeholder, applied over the
+    /// normal or unfocused style
+    pub fn set_placeholder_style(&mut self, style: CompoundStyle) {
+        self.placeholder_style = style;
+    }
+    /// Set the string displayed in place of the hidden parts of the lines
+    /// too long for the field, and of a too long placeholder.
+    ///
+    /// It's by default the `ellipsis_str` of the default skin.
+    pub fn set_ellipsis<S: Into<Cow<'static, str>>>(&mut self, ellipsis: S) {
+        self.ellipsis = ellipsis.into();
+        self.fix_scroll();
+    }
+    /// Set which part of the lines too long for the field is hidden
+    /// when it isn't focused, for example `FitMode::Start` to see the
+    /// end of a long path.
+    ///
+    /// When None (the default), the lines are displayed from the last
+    /// horizontal scroll position. This doesn't apply when wrapping.
+    pub fn set_fit_mode(&mut self, mode: Option<FitMode>) {
+        self.fit_mode = mode;
+    }
+    /// Set the char displayed in place of every char in password
+    /// mode (`*` by default), for example `•`, or a space to show
+    /// nothing but the cursor.
+    ///
+    /// The char must take exactly one cell: other ones, like wide
+    /// or control chars, are replaced with `*`.
+    pub fn set_password_char(&mut self, c: char) {
+        self.password_char = if fit::wrap::char_width(c) == 1 && !c.is_control() {
+            c
+        } else {
+            DEFAULT_PASSWORD_CHAR
+        };
+    }
+    pub const fn password_char(&self) -> char {
+        self.password_char
+    }
+    /// Show the real chars even in password mode (or hide them
+    /// again), for example when the user toggles an "eye" button.
+    ///
+    /// The content is still considered a secret: the highlighter
+    /// isn't applied to it.
+    pub fn set_reveal(&mut self, reveal: bool) {
+        self.reveal = reveal;
+        self.fix_scroll();
+    }
+    pub const fn is_revealed(&self) -> bool {
+        self.reveal
+    }
+    /// Make the content secure, for example for a password: the
+    /// chars of the content and of the states kept for undoing are
+    /// then overwritten with zeros in memory when they're removed
+    /// or freed (see `InputFieldContent::set_secure`).
+    ///
+    /// The text can't be copied, cut or killed anymore, and what was
+    /// before is overwritten too, like the last frame when partially
+    /// redrawing. Revealed frames aren't kept.
+    ///
+    /// This doesn't apply to the history nor to the strings
+    /// given to or returned by the field.
+    ///
+    /// This requires the `secure` feature.
+    #[cfg(feature = "secure")]
+    pub fn set_secure(&mut self, secure: bool) {
+        self.content.set_secure(secure);
+        self.undo_stack.set_secure(secure);
+        if secure {
+            super::input_field_content::scrub_string(&mut self.clipboard);
+            super::input_field_content::scrub_string(&mut self.kill_buffer);
+            let last_frame = self.last_frame.get_mut().unwrap_or_else(PoisonError::into_inner);
+            if let Some(mut frame) = last_frame.take() {
+                frame.scrub();
+            }
+        }
+    }
+    #[cfg(feature = "secure")]
+    pub const fn is_secure(&self) -> bool {
+        self.content.is_secure()
+    }
+    /// tell whether the text mustn't be copied out of the content,
+    /// to the clipboard or the kill buffer, because it's a password
+    /// or it's secure
+    fn is_secret(&self) -> bool {
+        #[cfg(feature = "secure")]
+        if self.content.is_secure() {
+            return true;
+        }
+        self.password_mode
+    }
+    /// tell whether the chars are displayed as password chars
+    const fn is_masked(&self) -> bool {
+        self.password_mode && !self.reveal
+    }
+    /// return the position of the cursor in the content
+    pub const fn cursor_pos(&self) -> Pos {
+        self.content.cursor_pos()
+    }
+    /// Move the cursor to the given position in the content (or
+    /// to the nearest valid one), scrolling if necessary
+    ///
+    /// ```
+    /// use termimad::*;
+    /// let mut input = InputField::default();
+    /// input.set_str("some text");
+    /// input.set_cursor_pos(Pos { x: 4, y: 0 });
+    /// assert_eq!(input.cursor_pos(), Pos { x: 4, y: 0 });
+    /// ```
+    pub fn set_cursor_pos(&mut self, pos: Pos) {
+        self.selection_anchor = None;
+        self.killing = false;
+        self.content.set_cursor_pos(pos);
+        self.fix_scroll();
+    }
+    /// return the position of the cursor on screen, or None if
+    /// it's not in the visible part of the content.
+    ///
+    /// This may be used to display a popup near the cursor.
+    pub fn screen_cursor_pos(&self) -> Option<Pos> {
+        let pos = self.content.cursor_pos();
+        let chars = &self.content.lines()[pos.y].chars;
+        let (row_idx, col, row_count) = if self.wrap {
+            let rows = self.visual_rows();
+            let row_idx = self.cursor_row_idx(&rows);
+            let col = self.chars_width(&chars[rows[row_idx].start..pos.x]);
+            (row_idx, col, rows.len())
+        } else {
+            if pos.x < self.scroll.x {
+                return None;
+            }
+            let col = self.chars_width(&chars[self.scroll.x..pos.x]);
+            (pos.y, col, self.content.line_count())
+        };
+        let height = self.area.height as usize;
+        let width = self.visible_text_width(row_count);
+        if row_idx < self.scroll.y || row_idx >= self.scroll.y + height || col >= width {
+            return None;
+        }
+        Some(Pos {
+            x: self.area.left as usize + self.left_scrollbar_width() + self.gutter_width() + col,
+            y: self.area.top as usize + row_idx - self.scroll.y,
+        })
+    }
+    pub const fn content(&self) -> &InputFieldContent {
+        &self.content
+    }
+    pub fn get_content(&self) -> String {
+        self.content.to_string()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty()
+    }
+    /// return the number of chars of the content, counting
+    /// line breaks
+    pub fn len(&self) -> usize {
+        self.content.len()
+    }
+    /// set the maximal number of chars (line breaks included)
+    /// the content can have.
+    ///
+    /// Insertions beyond this limit are refused but the
+    /// current content isn't truncated.
+    pub fn set_max_len(&mut self, max: Option<usize>) {
+        self.max_len = max;
+    }
+    /// Make the tab key insert the given number of spaces (or
+    /// do nothing if the width is 0, which is the default).
+    ///
+    /// Backspace, when the cursor is in the leading spaces of a
+    /// line, then removes spaces up to the previous tab stop.
+    pub fn set_tab_inserts_spaces(&mut self, width: u8) {
+        self.tab_behavior = if width == 0 {
+            TabBehavior::Ignored
+        } else {
+            TabBehavior::Spaces(width)
+        };
+    }
+    /// Make the tab key insert a tab char
+    pub fn set_tab_inserts_char(&mut self) {
+        self.tab_behavior = TabBehavior::Char;
+    }
+    /// set a filter which chars must pass to be inserted, either
+    /// typed or pasted (new lines aren't filtered).
+    ///
+    /// Example:
+    /// ```
+    /// use termimad::*;
+    /// let mut input = InputField::default();
+    /// input.set_char_filter(InputField::digits_only());
+    /// input.insert_str("12a3");
+    /// assert!(input.is_content("123"));
+    /// ```
+    pub fn set_char_filter(&mut self, f: impl Fn(char) -> bool + Send + Sync + 'static) {
+        self.char_filter = Some(Box::new(f));
+    }
+    /// remove the char filter, if any
+    pub fn remove_char_filter(&mut self) {
+        self.char_filter = None;
+    }
+    /// Set a function giving the styles of parts of a line, as
+    /// ranges of bytes (start included, end excluded) of the string
+    /// it receives. This may be used for syntax highlighting, or to
+    /// show URLs or matches of a search.
+    ///
+    /// The styles are applied over the normal style but beneath the
+    /// ones of the cursor and selection. They're not applied in
+    /// password mode.
+    ///
+    /// Example:
+    /// ```
+    /// use termimad::*;
+    /// let mut input = InputField::default();
+    /// input.set_highlighter(|line| {
+    ///     line.match_indices("TODO")
+    ///         .map(|(start, s)| (start, start + s.len(), CompoundStyle::with_fg(rgb(255, 0, 0))))
+    ///         .collect()
+    /// });
+    /// ```
+    pub fn set_highlighter(
+        &mut self,
+        f: impl Fn(&str) -> Vec<(usize, usize, CompoundStyle)> + Send + Sync + 'static,
+    ) {
+        self.highlighter = Some(Box::new(f));
+    }
+    /// remove the highlighter, if any
+    pub fn remove_highlighter(&mut self) {
+        self.highlighter = None;
+    }
+    /// return the style of every char of the line when a highlighter
+    /// gives one
+    fn highlighted_styles(
+        &self,
+        chars: &[char],
+        normal_style: &CompoundStyle,
+    ) -> Vec<Option<CompoundStyle>> {
+        let mut styles = vec![None; chars.len()];
+        let highlighter = match &self.highlighter {
+            Some(highlighter) if !self.password_mode => highlighter,
+            _ => { return styles; }
+        };
+        let line: String = chars.iter().collect();
+        let mut starts = Vec::with_capacity(chars.len()); // byte index of every char
+        let mut byte = 0;
+        for c in chars {
+            starts.push(byte);
+            byte += c.len_utf8();
+        }
+        for (start, end, style) in highlighter(&line) {
+            let first = starts.partition_point(|&b| b < start);
+            let after = starts.partition_point(|&b| b < end.min(line.len()));
+            for char_style in styles.iter_mut().take(after).skip(first) {
+                let char_style = char_style.get_or_insert_with(|| normal_style.clone());
+                char_style.overwrite_with(&style);
+            }
+        }
+        styles
+    }
+    /// a char filter accepting only ASCII digits
+    pub fn digits_only() -> impl Fn(char) -> bool + Send + Sync {
+        |c| c.is_ascii_digit()
+    }
+    /// a char filter accepting only ASCII chars
+    pub fn ascii_only() -> impl Fn(char) -> bool + Send + Sync {
+        |c| c.is_ascii()
+    }
+    /// tell whether the char may be inserted according
+    /// to the char filter
+    fn accepts(&self, c: char) -> bool {
+        c == '\n' || self.char_filter.as_ref().map_or(true, |f| f(c))
+    }
+    /// return the number of chars which can still be inserted,
+    /// taking into account that the selection would be replaced
+    fn room(&self) -> usize {
+        match self.max_len {
+            Some(max) => {
+                let selected_len = self.selected_text()
+                    .map_or(0, |s| s.chars().count());
+                max.saturating_sub(self.len() - selected_len)
+            }
+            None => usize::MAX,
+        }
+    }
+    /// Return the indexes of the chars of the content (line breaks
+    /// being counted as chars) matching the chars of the query, in
+    /// order, or None if the query isn't a subsequence of the content.
+    ///
+    /// The comparison is case insensitive and each char of the query
+    /// matches the first possible char, which makes it usable for
+    /// displaying the typed query in a fuzzy finder:
+    ///
+    /// ```
+    /// use termimad::*;
+    /// let mut input = InputField::default();
+    /// input.set_str("src/Main.rs");
+    /// assert_eq!(input.fuzzy_match("smr"), Some(vec![0, 4, 9]));
+    /// assert_eq!(input.fuzzy_match("rsm"), None);
+    /// ```
+    pub fn fuzzy_match(&self, query: &str) -> Option<Vec<usize>> {
+        let mut positions = Vec::new();
+        let mut content_chars = self.content.lines()
+            .iter()
+            .enumerate()
+            .flat_map(|(y, line)| {
+                let line_break = if y > 0 { Some('\n') } else { None };
+                line_break.into_iter().chain(line.chars.iter().copied())
+            })
+            .enumerate();
+        for q in query.chars() {
+            let (idx, _) = content_chars.find(|(_, c)| c.to_lowercase().eq(q.to_lowercase()))?;
+            positions.push(idx);
+        }
+        Some(positions)
+    }
+    /// tell whether the content of the input is equal
+    ///  to the argument
+    pub fn is_content(&self, s: &str) -> bool {
+        self.content.is_str(s)
+    }
+    /// Apply a change asked by the user to the content, unless
+    /// the input is read only
+    fn edit<F>(&mut self, kind: EditKind, f: F) -> bool
+    where
+        F: FnOnce(&mut InputFieldContent) -> bool,
+    {
+        if self.read_only {
+            return false;
+        }
+        self.force_edit(kind, f)
+    }
+    /// Apply a change to the content and, if it's effective,
+    /// record the previous state in the undo history.
+    ///
+    /// The selection is removed.
+    fn force_edit<F>(&mut self, kind: EditKind, f: F) -> bool
+    where
+        F: FnOnce(&mut InputFieldContent) -> bool,
+    {
+        self.selection_anchor = None;
+        self.killing = false;
+        let before = self.content.clone();
+        if f(&mut self.content) {
+            self.undo_stack.record(before, &self.content, kind);
+            self.fix_scroll();
+            self.notify_change();
+            true
+        } else {
+            false
+        }
+    }
+    /// Set a function to call after every change of the content
+    /// (but not on cursor moves), for example to update a search.
+    ///
+    /// Example:
+    /// ```
+    /// use {
+    ///     std::sync::{Arc, atomic::{AtomicUsize, Ordering}},
+    ///     termimad::*,
+    /// };
+    /// let changes = Arc::new(AtomicUsize::new(0));
+    /// let mut input = InputField::default();
+    /// let counter = Arc::clone(&changes);
+    /// input.on_change(move |_| { counter.fetch_add(1, Ordering::Relaxed); });
+    /// input.put_char('a');
+    /// input.move_left();
+    /// input.del_char_left(); // nothing to delete
+    /// assert_eq!(changes.load(Ordering::Relaxed), 1);
+    /// ```
+    pub fn on_change(&mut self, f: impl FnMut(&InputFieldContent) + Send + Sync + 'static) {
+        self.on_change = Some(Box::new(f));
+    }
+    /// Tell whether the content changed since the previous call
+    /// to this function (or since the creation of the input),
+    /// and reset this flag.
+    ///
+    /// This is an alternative to `on_change` for applications
+    /// checking the input after handling events.
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::replace(&mut self.changed, false)
+    }
+    fn notify_change(&mut self) {
+        self.changed = true;
+        self.row_goal = None;
+        if let Some(f) = self.on_change.as_mut() {
+            f(&self.content);
+        }
+    }
+    /// Apply an insertion, replacing the selection if there's one
+    fn insert<F>(&mut self, kind: EditKind, f: F) -> bool
+    where
+        F: FnOnce(&mut InputFieldContent) -> bool,
+    {
+        match self.selected_range() {
+            Some((start, end)) => self.edit(EditKind::Other, |content| {
+                content.delete_range(start, end);
+                f(content);
+                true
+            }),
+            None => self.edit(kind, f),
+        }
+    }
+    /// Return the start (included) and end (excluded) of the
+    /// selection, if there's a non empty one
+    pub fn selected_range(&self) -> Option<(Pos, Pos)> {
+        let anchor = self.selection_anchor?;
+        let pos = self.content.cursor_pos();
+        match anchor.cmp(&pos) {
+            std::cmp::Ordering::Less => Some((anchor, pos)),
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Greater => Some((pos, anchor)),
+        }
+    }
+    /// Return the selected text, if any
+    pub fn selected_text(&self) -> Option<String> {
+        self.selected_range()
+            .map(|(start, end)| self.content.string_between(start, end))
+    }
+    /// Remove the selection, without removing the selected text
+    pub fn unselect(&mut self) {
+        self.selection_anchor = None;
+    }
+    /// Select the whole content, putting the cursor at end
+    pub fn select_all(&mut self) {
+        self.killing = false;
+        self.content.move_to_end();
+        self.selection_anchor = Some(Pos::default());
+        self.fix_scroll();
+    }
+    /// Delete the selected text, if any.
+    ///
+    /// Return true when there was a selection.
+    pub fn del_selection(&mut self) -> bool {
+        match self.selected_range() {
+            Some((start, end)) => self.edit(EditKind::Other, |content| {
+                content.delete_range(start, end);
+                true
+            }),
+            None => false,
+        }
+    }
+    /// Insert a char at the given position, the cursor staying
+    /// before the same chars (see `InputFieldContent::insert_char_at`).
+    ///
+    /// The edit can be undone. The char filter and the maximal
+    /// length aren't applied.
+    ///
+    /// Return the new position of the cursor.
+    pub fn insert_char_at(&mut self, pos: Pos, c: char) -> Pos {
+        self.replace_range(pos, pos, c.encode_utf8(&mut [0; 4]))
+    }
+    /// Remove the chars between two positions (start included, end
+    /// excluded), the cursor staying before the same chars.
+    ///
+    /// The edit can be undone.
+    ///
+    /// Return the new position of the cursor.
+    pub fn delete_range(&mut self, from: Pos, to: Pos) -> Pos {
+        self.replace_range(from, to, "")
+    }
+    /// Replace the chars between two positions (start included, end
+    /// excluded) with a string, the cursor staying before the same
+    /// chars (see `InputFieldContent::replace_range`).
+    ///
+    /// The edit can be undone. The char filter and the maximal
+    /// length aren't applied.
+    ///
+    /// ```
+    /// use termimad::*;
+    /// let mut input = InputField::default();
+    /// input.set_str("a b c");
+    /// let cursor = input.replace_range(Pos { x: 2, y: 0 }, Pos { x: 3, y: 0 }, "bbb");
+    /// assert!(input.is_content("a bbb c"));
+    /// assert_eq!(cursor, Pos { x: 7, y: 0 });
+    /// ```
+    ///
+    /// Return the new position of the cursor.
+    pub fn replace_range(&mut self, from: Pos, to: Pos, s: &str) -> Pos {
+        self.edit(EditKind::Other, |content| {
+            content.replace_range(from, to, s);
+            from != to || !s.is_empty()
+        });
+        self.content.cursor_pos()
+    }
+    /// Return the selected text, if any, so that it
+    /// can be put in a clipboard
+    ///
+    /// Return None in password mode or when the content is secure.
+    pub fn copy_selection(&self) -> Option<String> {
+        if self.is_secret() {
+            return None;
+        }
+        self.selected_text()
+    }
+    /// Remove the selected text, if any, and return it
+    ///
+    /// Nothing is removed in password mode or when the content is secure.
+    pub fn cut_selection(&mut self) -> Option<String> {
+        if self.read_only || self.is_secret() {
+            return None;
+        }
+        let text = self.selected_text()?;
+        self.del_selection();
+        Some(text)
+    }
+    /// Insert a text coming from a clipboard at cursor point,
+    /// replacing the selection if any.
+    ///
+    /// If no new line key was defined (i.e. the input is mono
+    /// line), new lines are replaced with spaces.
+    ///
+    /// The insertion is a single step in the undo history.
+    ///
+    /// This is also the function to call with the text of a bracketed
+    /// paste: terminals send the whole paste at once when bracketed
+    /// paste is enabled (by writing `"\x1b[?2004h"`, and `"\x1b[?2004l"`
+    /// to disable it). The version of crossterm termimad uses doesn't
+    /// parse bracketed pastes (the text arrives as key events) so the
+    /// application must read it itself.
+    pub fn paste(&mut self, s: &str) {
+        self.paste_text(s);
+    }
+    fn paste_text(&mut self, s: &str) -> bool {
+        if self.new_line_keys.is_empty() {
+            let s = s.replace("\r\n", " ").replace(&['\r', '\n'][..], " ");
+            self.insert_text(&s)
+        } else {
+            self.insert_text(&InputFieldContent::normalize_newlines(s))
+        }
+    }
+    /// Delete the text from the cursor to the end of the line and
+    /// put it in the kill buffer. If the cursor is at the end of a
+    /// line, and new lines are allowed, the next line is joined.
+    ///
+    /// Consecutive kills are appended in the kill buffer.
+    ///
+    /// In password mode or when the content is secure, the text
+    /// is deleted without being put in the kill buffer.
+    pub fn kill_to_line_end(&mut self) -> bool {
+        let start = self.content.cursor_pos();
+        let line_len = self.content.current_line().chars.len();
+        let end = if start.x < line_len {
+            Pos { x: line_len, y: start.y }
+        } else if start.y + 1 < self.content.line_count() && !self.new_line_keys.is_empty() {
+            Pos { x: 0, y: start.y + 1 }
+        } else {
+            return false;
+        };
+        // a secret isn't copied, even temporarily
+        let killed = if self.is_secret() {
+            None
+        } else {
+            Some(self.content.string_between(start, end))
+        };
+        let appending = self.killing;
+        let done = self.edit(EditKind::Other, |content| {
+            content.del_between(start, end);
+            true
+        });
+        if let (true, Some(killed)) = (done, killed) {
+            if !appending {
+                self.kill_buffer.clear();
+            }
+            self.kill_buffer.push_str(&killed);
+            self.killing = true;
+        }
+        done
+    }
+    /// Insert the content of the kill buffer at cursor point
+    ///
+    /// Return false when there was nothing to yank.
+    pub fn yank(&mut self) -> bool {
+        if self.kill_buffer.is_empty() || self.read_only {
+            return false;
+        }
+        let text = self.kill_buffer.clone();
+        self.paste(&text);
+        true
+    }
+    /// Apply a cursor move extending the selection (or starting
+    /// a new one)
+    fn extend_selection<F>(&mut self, f: F) -> bool
+    where
+        F: FnOnce(&mut Self) -> bool,
+    {
+        let anchor = self.selection_anchor
+            .unwrap_or_else(|| self.content.cursor_pos());
+        let moved = f(self);
+        self.selection_anchor = Some(anchor);
+        moved
+    }
+    /// change the content to the new one and
+    ///  put the cursor at the end **if** the
+    ///  content is different from the previous one.
+    pub fn set_str<S: AsRef<str>>(&mut self, s: S) {
+        let s = InputFieldContent::normalize_newlines(s.as_ref());
+        self.force_edit(EditKind::Other, |content| {
+            if content.is_str(&s) {
+                false
+            } else {
+                content.set_str(s);
+                true
+            }
+        });
+    }
+    pub fn insert_new_line(&mut self) -> bool {
+        if self.room() == 0 {
+            return false;
+        }
+        self.insert(EditKind::Other, |content| {
+            content.insert_new_line();
+            true
+        })
+    }
+    /// put a char at cursor position (and increment this
+    /// position), replacing the char under the cursor in
+    /// overwrite mode.
+    pub fn put_char(&mut self, c: char) -> bool {
+        if !self.accepts(c) {
+            return false;
+        }
+        if self.overwrites(c) {
+            return self.edit(EditKind::CharInsertion, |content| {
+                content.del_char_below();
+                content.insert_char(c);
+                true
+            });
+        }
+        if self.room() == 0 {
+            return false;
+        }
+        let kind = if c == '\n' {
+            EditKind::Other
+        } else {
+            EditKind::CharInsertion
+        };
+        self.insert(kind, |content| {
+            content.insert_char(c);
+            true
+        })
+    }
+    pub fn clear(&mut self) {
+        self.force_edit(EditKind::Other, |content| {
+            if content.is_empty() {
+                false
+            } else {
+                content.clear();
+                true
+            }
+        });
+    }
+    /// remove the selection or, if there's none, the char at
+    /// cursor position, if any
+    pub fn del_char_below(&mut self) -> bool {
+        self.del_selection()
+            || self.edit(EditKind::Other, |content| content.del_char_below())
+    }
+    /// remove the selection or, if there's none, the char left
+    /// of the cursor, if any (or the spaces up to the previous
+    /// tab stop if tab inserts spaces and the cursor is in the
+    /// line's indentation)
+    pub fn del_char_left(&mut self) -> bool {
+        if self.del_selection() {
+            return true;
+        }
+        if let TabBehavior::Spaces(width) = self.tab_behavior {
+            let pos = self.content.cursor_pos();
+            let chars = &self.content.current_line().chars;
+            if pos.x > 0 && chars[..pos.x].iter().all(|&c| c == ' ') {
+                let n = (pos.x - 1) % width as usize + 1;
+                let start = Pos { x: pos.x - n, y: pos.y };
+                return self.edit(EditKind::Other, |content| {
+                    content.del_between(start, pos);
+                    true
+                });
+            }
+        }
+        self.edit(EditKind::Other, |content| content.del_char_left())
+    }
+    /// apply the tab key according to the behavior set with
+    /// `set_tab_inserts_spaces` or `set_tab_inserts_char`
+    pub fn put_tab(&mut self) -> bool {
+        match self.tab_behavior {
+            TabBehavior::Ignored => false,
+            TabBehavior::Spaces(width) => {
+                let mut inserted = false;
+                for _ in 0..width {
+                    inserted |= self.put_char(' ');
+                }
+                inserted
+            }
+            TabBehavior::Char => self.put_char('\t'),
+        }
+    }
+    /// Insert the string on cursor point, as if it was typed
+    /// (replacing the selection, if any).
+    ///
+    /// Chars not passing the char filter are dropped and, if a max
+    /// length is set, only the chars which fit are inserted.
+    pub fn insert_str<S: AsRef<str>>(&mut self, s: S) {
+        self.insert_text(s.as_ref());
+    }
+    fn insert_text(&mut self, s: &str) -> bool {
+        let mut chars: Vec<char> = InputFieldContent::normalize_newlines(s)
+            .chars()
+            .filter(|&c| self.accepts(c))
+            .collect();
+        let mut end = chars.len().min(self.room());
+        while !input_field_content::is_cluster_boundary(&chars, end) {
+            end -= 1; // we don't cut a grapheme cluster
+        }
+        chars.truncate(end);
+        let s: String = chars.into_iter().collect();
+        if s.is_empty() {
+            return false;
+        }
+        self.insert(EditKind::Other, |content| {
+            content.insert_str(s);
+            true
+        })
+    }
+    /// Restore the state preceding the last edit, if any.
+    ///
+    /// Return true when there was something to undo.
+    pub fn undo(&mut self) -> bool {
+        if self.read_only {
+            return false;
+        }
+        if let Some(previous) = self.undo_stack.undo(&self.content) {
+            self.selection_anchor = None;
+            self.killing = false;
+            self.content = previous;
+            self.fix_scroll();
+            self.notify_change();
+            true
+        } else {
+            false
+        }
+    }
+    /// Restore the state which was undone with the last call
+    /// to `undo`, if any (and if no edit occured since).
+    ///
+    /// Return true when there was something to redo.
+    pub fn redo(&mut self) -> bool {
+        if self.read_only {
+            return false;
+        }
+        if let Some(next) = self.undo_stack.redo(&self.content) {
+            self.selection_anchor = None;
+            self.killing = false;
+            self.content = next;
+            self.fix_scroll();
+            self.notify_change();
+            true
+        } else {
+            false
+        }
+    }
+    /// Set the maximal number of edits which can be undone.
+    ///
+    /// A depth of 0 disables the undo history.
+    /// The default depth is 100.
+    pub fn set_undo_depth(&mut self, n: usize) {
+        self.undo_stack.set_depth(n);
+    }
+
+    pub fn move_up(&mut self) -> bool {
+        self.move_lines_up(1)
+    }
+    pub fn move_down(&mut self) -> bool {
+        self.move_lines_down(1)
+    }
+    wrap_content_fun!(move_left);
+    wrap_content_fun!(move_right);
+    wrap_content_fun!(move_to_start);
+    wrap_content_fun!(move_to_end);
+    wrap_content_fun!(move_to_line_start);
+    wrap_content_fun!(move_to_line_end);
+    wrap_content_fun!(move_word_left);
+    wrap_content_fun!(move_word_right);
+    wrap_content_edit_fun!(del_word_left);
+    wrap_content_edit_fun!(del_word_right);
+    wrap_content_edit_fun!(trim);
+    wrap_content_edit_fun!(collapse_blank_lines);
+
+    /// Return the word (made of alphanumeric chars, as in word moves)
+    /// which ends at the cursor, possibly empty, with the position
+    /// of its start.
+    ///
+    /// With `screen_cursor_pos` and `replace_word_before_cursor`,
+    /// it lets an application propose completions.
+    pub fn word_before_cursor(&self) -> (String, Pos) {
+        let pos = self.content.cursor_pos();
+        let chars = &self.content.current_line().chars;
+        let mut start = pos.x;
+        while start > 0 && chars[start - 1].is_alphanumeric() {
+            start -= 1;
+        }
+        let word = chars[start..pos.x].iter().collect();
+        (word, Pos { x: start, y: pos.y })
+    }
+    /// Replace the word which ends at the cursor (see
+    /// `word_before_cursor`), for example with an accepted completion,
+    /// and put the cursor after the replacement.
+    ///
+    /// The replacement is a single step in the undo history.
+    /// Return true when the content changed.
+    pub fn replace_word_before_cursor(&mut self, replacement: &str) -> bool {
+        let (word, start) = self.word_before_cursor();
+        let room = self.room().saturating_add(word.chars().count());
+        let replacement: String = InputFieldContent::normalize_newlines(replacement)
+            .chars()
+            .filter(|&c| self.accepts(c))
+            .filter(|&c| c != '\n' || !self.new_line_keys.is_empty())
+            .take(room)
+            .collect();
+        if replacement == word {
+            return false;
+        }
+        let end = self.content.cursor_pos();
+        self.edit(EditKind::Other, |content| {
+            content.del_between(start, end);
+            content.insert_str(replacement);
+            true
+        })
+    }
+
+    /// Add an entry, usually the submitted content, at the end of the
+    /// history (an empty entry or one equal to the last one is ignored).
+    ///
+    /// The navigation in the history is reset, so that the next
+    /// recalled entry is this one.
+    pub fn push_history(&mut self, entry: String) {
+        self.history_idx = None;
+        self.history_draft.clear();
+        if !entry.is_empty() && self.history.last() != Some(&entry) {
+            self.history.push(entry);
+        }
+    }
+    /// return the entries of the history, oldest first
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+    /// Set whether the up key, when the cursor is on the first line,
+    /// and the down key, when it's on the last one, recall the entries
+    /// of the history (false by default)
+    pub fn set_history_navigation(&mut self, b: bool) {
+        self.history_navigation = b;
+    }
+    /// Replace the content with the previous entry of the history, the
+    /// content typed before the navigation being kept for `history_next`.
+    ///
+    /// Editing the recalled entry doesn't change the history.
+    /// Return false when there's no previous entry or the input
+    /// is read only.
+    pub fn history_prev(&mut self) -> bool {
+        if self.read_only {
+            return false;
+        }
+        let idx = match self.history_idx {
+            Some(0) => { return false; }
+            Some(idx) => idx - 1,
+            None if self.history.is_empty() => { return false; }
+            None => {
+                self.history_draft = self.get_content();
+                self.history.len() - 1
+            }
+        };
+        self.recall_history(Some(idx));
+        true
+    }
+    /// Replace the content with the next entry of the history or, after
+    /// the last one, with what was typed before the navigation started.
+    ///
+    /// Return false when no entry is recalled.
+    pub fn history_next(&mut self) -> bool {
+        if self.read_only {
+            return false;
+        }
+        let idx = match self.history_idx {
+            Some(idx) if idx + 1 < self.history.len() => Some(idx + 1),
+            Some(_) => None,
+            None => { return false; }
+        };
+        self.recall_history(idx);
+        true
+    }
+    fn recall_history(&mut self, idx: Option<usize>) {
+        self.history_idx = idx;
+        let entry = match idx {
+            Some(idx) => self.history[idx].clone(),
+            None => std::mem::take(&mut self.history_draft),
+        };
+        self.set_str(entry);
+    }
+
+    pub fn page_up(&mut self) -> bool {
+        self.move_lines_up(self.area.height as usize)
+    }
+
+    pub fn page_down(&mut self) -> bool {
+        self.move_lines_down(self.area.height as usize)
+    }
+
+    /// apply an event being a key
+    ///
+    ///
+    /// This function handles a few events like deleting a
+    /// char, or going to the start (home key) or end (end key)
+    /// of the input. If you want to totally handle events, you
+    /// may call function like `put_char` and `del_char_left`
+    /// directly.
+    pub fn apply_key_event(&mut self, key: KeyEvent) -> bool {
+        if !self.focused {
+            return false;
+        }
+        if self.new_line_keys.contains(&key) {
+            self.insert_new_line();
+            return true;
+        }
+        if key == Self::CTRL_Z {
+            return self.undo();
+        }
+        if key == Self::CTRL_Y {
+            return self.redo();
+        }
+        if key == Self::CTRL_K {
+            return self.kill_to_line_end();
+        }
+        self.killing = false;
+        if key == Self::ALT_Y {
+            return self.yank();
+        }
+        if key == Self::CTRL_C {
+            return match self.copy_selection() {
+                Some(text) => {
+                    self.clipboard = text;
+                    true
+                }
+                None => false,
+            };
+        }
+        if key == Self::CTRL_X {
+            return match self.cut_selection() {
+                Some(text) => {
+                    self.clipboard = text;
+                    true
+                }
+                None => false,
+            };
+        }
+        if key == Self::CTRL_V {
+            if self.clipboard.is_empty() {
+                return false;
+            }
+            let text = self.clipboard.clone();
+            self.paste(&text);
+            return true;
+        }
+        use crossterm::event::{
+            KeyModifiers as Mod,
+        };
+        match (key.code, key.modifiers) {
+            (code, Mod::SHIFT) => self.apply_selecting_keycode_event(code)
+                .unwrap_or_else(|| self.apply_keycode_event(code)),
+            (code, Mod::NONE) => self.apply_keycode_event(code),
+            _ => false,
+        }
+    }
+
+    /// apply a key, when shift is pressed, if it's a movement key.
+    ///
+    /// Return None if the key isn't a movement one
+    fn apply_selecting_keycode_event(&mut self, code: KeyCode) -> Option<bool> {
+        let moved = match code {
+            KeyCode::Home => self.extend_selection(Self::move_to_line_start),
+            KeyCode::End => self.extend_selection(Self::move_to_line_end),
+            KeyCode::Up => self.extend_selection(Self::move_up),
+            KeyCode::Down => self.extend_selection(Self::move_down),
+            KeyCode::Left => self.extend_selection(Self::move_left),
+            KeyCode::Right => self.extend_selection(Self::move_right),
+            KeyCode::PageUp => self.extend_selection(Self::page_up),
+            KeyCode::PageDown => self.extend_selection(Self::page_down),
+            _ => {
+                return None;
+            }
+        };
+        Some(moved)
+    }
+
+    /// apply an event being a key without modifier.
+    ///
+    /// You don't usually call this function but the more
+    /// general `apply_event`. This one is useful when you
+    /// manage events mostly yourselves.
+    pub fn apply_keycode_event(&mut self, code: KeyCode) -> bool {
+        if !self.focused {
+            return false;
+        }
+        match code {
+            KeyCode::Home => self.move_to_line_start(),
+            KeyCode::End => self.move_to_line_end(),
+            KeyCode::Char(c) => self.put_char(c),
+            KeyCode::Up => self.move_up() || (self.history_navigation && self.history_prev()),
+            KeyCode::Down => self.move_down() || (self.history_navigation && self.history_next()),
+            KeyCode::Left => self.move_left(),
+            KeyCode::PageUp => self.page_up(),
+            KeyCode::PageDown => self.page_down(),
+            KeyCode::Right => self.move_right(),
+            KeyCode::Backspace => self.del_char_left(),
+            KeyCode::Delete => self.del_char_below(),
+            KeyCode::Tab => self.put_tab(),
+            KeyCode::Insert if !self.read_only => {
+                self.set_overwrite(!self.overwrite);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// return the position in the content of the char at the
+    /// given screen coordinates (the returned position is valid
+    /// for the content but may be far from the coordinates when
+    /// they're outside the area)
+    fn content_pos_at(&self, x: u16, y: u16) -> Pos {
+        let x = (x.saturating_sub(self.area.left) as usize)
+            .saturating_sub(self.left_scrollbar_width() + self.gutter_width());
+        let y = if y < self.area.top {
+            self.scroll.y.saturating_sub((self.area.top - y) as usize)
+        } else {
+            (y - self.area.top) as usize + self.scroll.y
+        };
+        if self.wrap {
+            let rows = self.visual_rows();
+            match rows.get(y) {
+                Some(&row) => {
+                    let row_chars = &self.content.lines()[row.y].chars[row.start..row.end];
+                    Pos {
+                        x: (row.start + self.char_idx_at_col(row_chars, x)).min(row.max_x()),
+                        y: row.y,
+                    }
+                }
+                None => self.content.end(),
+            }
+        } else if y < self.content.line_count() {
+            let chars = &self.content.lines()[y].chars;
+            let scroll = self.scroll.x.min(chars.len());
+            Pos {
+                x: scroll + self.char_idx_at_col(&chars[scroll..], x),
+                y,
+            }
+        } else {
+            self.content.end()
+        }
+    }
+
+    /// Apply a click event
+    pub fn apply_click_event(&mut self, x: u16, y: u16) -> bool {
+        if self.dragging {
+            // end of a selection with the mouse
+            self.apply_drag_event(x, y);
+            self.dragging = false;
+            return true;
+        }
+        if self.area.contains(x, y) {
+            if self.focused {
+                self.selection_anchor = None;
+                self.killing = false;
+                let pos = self.content_pos_at(x, y);
+                self.content.set_cursor_pos(pos);
+            } else {
+                self.gain_focus();
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Apply a mouse button press, which may be the start
+    /// of a selection by dragging
+    pub fn apply_mouse_down_event(&mut self, x: u16, y: u16) -> bool {
+        if !self.focused || !self.area.contains(x, y) {
+            return false;
+        }
+        let pos = self.content_pos_at(x, y);
+        self.content.set_cursor_pos(pos);
+        self.selection_anchor = Some(self.content.cursor_pos());
+        self.dragging = true;
+        self.killing = false;
+        self.fix_scroll();
+        true
+    }
+
+    /// Apply a mouse move with the button pressed, extending the
+    /// selection started on the press.
+    ///
+    /// The content is scrolled if the mouse goes out of the area.
+    pub fn apply_drag_event(&mut self, x: u16, y: u16) -> bool {
+        if !self.dragging {
+            return false;
+        }
+        let pos = self.content_pos_at(x, y);
+        self.content.set_cursor_pos(pos);
+        self.fix_scroll();
+        true
+    }
+
+    /// Apply a double click event, selecting the word (or the run
+    /// of non alphanumeric chars) at the given screen coordinates
+    pub fn apply_double_click_event(&mut self, x: u16, y: u16) -> bool {
+        if !self.area.contains(x, y) {
+            return false;
+        }
+        self.gain_focus();
+        self.killing = false;
+        let pos = self.content_pos_at(x, y);
+        let chars = &self.content.lines()[pos.y].chars;
+        if pos.x < chars.len() {
+            let alnum = chars[pos.x].is_alphanumeric();
+            let mut start = pos.x;
+            while start > 0 && chars[start - 1].is_alphanumeric() == alnum {
+                start -= 1;
+            }
+            let mut end = pos.x + 1;
+            while end < chars.len() && chars[end].is_alphanumeric() == alnum {
+                end += 1;
+            }
+            self.content.set_cursor_pos(Pos { x: end, y: pos.y });
+            self.selection_anchor = Some(Pos { x: start, y: pos.y });
+        } else {
+            self.selection_anchor = None;
+            self.content.set_cursor_pos(pos);
+        }
+        self.fix_scroll();
+        true
+    }
+
+    /// Apply a triple click event, selecting the whole line at
+    /// the given screen coordinates
+    pub fn apply_triple_click_event(&mut self, x: u16, y: u16) -> bool {
+        if !self.area.contains(x, y) {
+            return false;
+        }
+        self.gain_focus();
+        self.killing = false;
+        let y = self.content_pos_at(x, y).y;
+        let line_len = self.content.lines()[y].chars.len();
+        self.content.set_cursor_pos(Pos { x: line_len, y });
+        self.selection_anchor = Some(Pos { x: 0, y });
+        self.fix_scroll();
+        true
+    }
+
+    /// apply the passed event to change the state (content, cursor)
+    ///
+    /// The events received from an `EventSource` can be given as is,
+    /// double and triple clicks being detected by the source:
+    ///
+    /// ```no_run
+    /// # use termimad::*;
+    /// # fn run(input: &mut InputField) -> Result<(), Error> {
+    /// let event_source = EventSource::new()?;
+    /// for event in event_source.receiver() {
+    ///     if input.apply_event(&event) {
+    ///         input.display()?;
+    ///     }
+    ///     event_source.unblock(false);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Return true when the event was used.
+    pub fn apply_event(&mut self, event: &Event) -> bool {
+        self.apply_event_detailed(event) != InputFieldAction::Ignored
+    }
+
+    /// apply the passed event, like `apply_event`, and tell what
+    /// it did, so that the application may for example redraw only
+    /// when needed and react to the changes of the content:
+    ///
+    /// ```
+    /// # use termimad::*;
+    /// # use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    /// let mut input = InputField::default();
+    /// let key = |code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+    /// assert_eq!(input.apply_event_detailed(&key(KeyCode::Char('a'))), InputFieldAction::ContentChanged);
+    /// assert_eq!(input.apply_event_detailed(&key(KeyCode::Left)), InputFieldAction::Moved);
+    /// assert_eq!(input.apply_event_detailed(&key(KeyCode::Left)), InputFieldAction::Ignored);
+    /// ```
+    pub fn apply_event_detailed(&mut self, event: &Event) -> InputFieldAction {
+        let was_focused = self.focused;
+        let changed_before = std::mem::replace(&mut self.changed, false);
+        let used = self.apply_event_to_state(event);
+        let content_changed = self.changed;
+        self.changed |= changed_before;
+        if content_changed {
+            InputFieldAction::ContentChanged
+        } else if self.focused && !was_focused {
+            InputFieldAction::Focused
+        } else if used {
+            InputFieldAction::Moved
+        } else {
+            InputFieldAction::Ignored
+        }
+    }
+
+    fn apply_event_to_state(&mut self, event: &Event) -> bool {
+        match event {
+            Event::Click(x, y, ..) => {
+                self.apply_click_event(*x, *y)
+            }
+            Event::DoubleClick(x, y) => {
+                self.apply_double_click_event(*x, *y)
+            }
+            Event::TripleClick(x, y) => {
+                self.apply_triple_click_event(*x, *y)
+            }
+            Event::MouseDown(x, y) => {
+                self.apply_mouse_down_event(*x, *y)
+            }
+            Event::Drag(x, y) => {
+                self.apply_drag_event(*x, *y)
+            }
+            Event::Wheel(lines) => {
+                // the position of the pointer isn't known, so only
+                // the focused input is scrolled
+                self.focused && self.scroll_lines(*lines * self.wheel_scroll_lines as i32)
+            }
+            Event::Key(key) => {
+                self.apply_key_event(*key)
+            }
+            _ => false,
+        }
+    }
+
+    /// apply an event read from crossterm (for example with
+    /// `crossterm::event::read`), converted with `Event::from_crossterm_event`.
+    ///
+    /// There's no double or triple click this way: use an `EventSource`
+    /// and `apply_event` to get them.
+    ///
+    /// Contrary to `Event::Wheel`, the wheel events read from crossterm
+    /// have a position, so they're applied with `apply_wheel_event`.
+    ///
+    /// Return true when the event was used.
+    pub fn apply_crossterm_event(&mut self, event: crossterm::event::Event) -> bool {
+        use crossterm::event::{MouseEvent, MouseEventKind};
+        match event {
+            crossterm::event::Event::Mouse(MouseEvent { kind: MouseEventKind::ScrollUp, column, row, .. }) => {
+                self.apply_wheel_event(column, row, -1)
+            }
+            crossterm::event::Event::Mouse(MouseEvent { kind: MouseEventKind::ScrollDown, column, row, .. }) => {
+                self.apply_wheel_event(column, row, 1)
+            }
+            _ => Event::from_crossterm_event(event).map_or(false, |event| self.apply_event(&event)),
+        }
+    }
+
+    /// fix the vertical scroll, given the number of rows
+    /// and the one containing the cursor
+    fn fix_vertical_scroll(&mut self, cursor_y: usize, row_count: usize) {
+        let height = self.area.height as usize;
+        if row_count <= height {
+            self.scroll.y = 0;
+            return;
+        }
+        if self.scroll.y + height > row_count {
+            self.scroll.y = row_count - height;
+        }
+        if self.focused {
+            // we must ensure the cursor is visible
+            if self.scroll.y > cursor_y {
+                self.scroll.y = cursor_y;
+                if self.scroll.y > 0 && height > 4 {
+                    self.scroll.y -= 1;
+                }
+            } else if cursor_y >= self.scroll.y + height {
+                self.scroll.y = cursor_y - height + 1;
+                if cursor_y + 1 < row_count {
+                    self.scroll.y -= 1;
+                }
+            }
+        }
+    }
+
+    fn fix_scroll(&mut self) {
+        if self.area.width == 0 || self.area.height == 0 {
+            // nothing is displayed (this may happen during a resize)
+            return;
+        }
+        if self.wrap {
+            let rows = self.visual_rows();
+            let cursor_y = self.cursor_row_idx(&rows);
+            self.fix_vertical_scroll(cursor_y, rows.len());
+            self.scroll.x = 0;
+            return;
+        }
+        let line_count = self.content.line_count();
+        let width = self.visible_text_width(line_count);
+        let pos = self.content.cursor_pos();
+        self.fix_vertical_scroll(pos.y, line_count);
+        if width == 0 {
+            // only the scrollbar is displayed
+            self.scroll.x = 0;
+            return;
+        }
+
+        let line = &self.content.current_line().chars;
+        let line_width = self.chars_width(line);
+        if line_width < width {
+            self.scroll.x = 0;
+        } else {
+            // the scroll is computed in columns, then converted
+            // into the index of the first visible char
+            let cursor_col = self.chars_width(&line[..pos.x]);
+            let cursor_end = cursor_col + line.get(pos.x).map_or(1, |&c| self.char_width(c));
+            let mut scroll = self.chars_width(&line[..self.scroll.x.min(line.len())]);
+            let ellipsis_width = self.ellipsis.width();
+            if self.focused {
+                // we don't show ellipsis if the width is too small
+                // so we need less margin
+                if width < ellipsis_width + 3 {
+                    if cursor_col < 2 {
+                        scroll = 0;
+                    } else if cursor_col < scroll + 1 {
+                        scroll = cursor_col - 1;
+                    } else if cursor_end > scroll + width + 1 {
+                        scroll = cursor_end - width;
+                    }
+                } else {
+                    if cursor_col < scroll + ellipsis_width + 1 {
+                        scroll = cursor_col.saturating_sub(ellipsis_width + 1);
+                    } else if cursor_end > scroll + width - ellipsis_width {
+                        scroll = cursor_end + ellipsis_width - width;
+                    }
+                }
+            }
+            if scroll + width > line_width + 1 {
+                scroll = line_width + 1 - width;
+            }
+            // a wide char can't be cut, so we start after it
+            let mut idx = 0;
+            let mut col = 0;
+            while idx < line.len() && col < scroll {
+                col += self.char_width(line[idx]);
+                idx += 1;
+            }
+            self.scroll.x = idx;
+        }
+    }
+
+    /// return the fit mode to apply to the line, if it must be
+    /// displayed fitted instead of scrolled
+    fn fit_mode_for_line(&self, y: usize, width: usize) -> Option<FitMode> {
+        self.fit_mode.filter(|_| {
+            !self.focused
+                && !self.wrap
+                && width > self.ellipsis.width() + 3
+                && self.chars_width(&self.content.lines()[y].chars) > width
+        })
+    }
+
+    /// queue the chars of a line too long for the width, replacing the
+    /// part given by the fit mode with the ellipsis
+    fn queue_fitted_line(
+        &self,
+        cells: &mut RowCells,
+        chars: &[char],
+        mode: FitMode,
+        normal_style: &CompoundStyle,
+        width: usize,
+    ) -> Result<(), Error> {
+        let ellipsis_width = self.ellipsis.width();
+        let (head_width, tail_width) = mode.kept_widths(width - ellipsis_width);
+        let mut head_end = 0;
+        let mut head_col = 0;
+        while head_end < chars.len() && head_col + self.char_width(chars[head_end]) <= head_width {
+            head_col += self.char_width(chars[head_end]);
+            head_end += 1;
+        }
+        let mut tail_start = chars.len();
+        let mut tail_col = 0;
+        while tail_start > head_end && tail_col + self.char_width(chars[tail_start - 1]) <= tail_width {
+            tail_start -= 1;
+            tail_col += self.char_width(chars[tail_start]);
+        }
+        let highlighted = self.highlighted_styles(chars, normal_style);
+        let queue_chars = |cells: &mut RowCells, start: usize, end: usize| -> Result<(), Error> {
+            for idx in start..end {
+                let style = highlighted[idx].as_ref().unwrap_or(normal_style);
+                let c = self.displayed_char(chars[idx]);
+                style.queue(cells.cell(self.char_width(chars[idx])), c)?;
+            }
+            Ok(())
+        };
+        queue_chars(cells, 0, head_end)?;
+        normal_style.queue(cells.cell(ellipsis_width), &*self.ellipsis)?;
+        // when wide chars don't fit, the tail is kept at the end
+        let padding = width - head_col - ellipsis_width - tail_col;
+        if padding > 0 {
+            SPACE_FILLING.queue_styled(cells.cell(padding), normal_style, padding)?;
+        }
+        queue_chars(cells, tail_start, chars.len())
+    }
+
+    /// Render the input field on screen.
+    ///
+    /// All rendering must be explicitely called, no rendering is
+    /// done on functions changing the state.
+    ///
+    /// w is typically either stderr or stdout. This function doesn't
+    /// flush by itself (useful to avoid flickering). The whole frame
+    /// is written at once, with a single call to `write_all`.
+    ///
+    /// In partial redraw mode, only the changed cells are written.
+    pub fn display_on<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+        let frame = if self.partial_redraw {
+            let mut frame = Vec::new();
+            self.write_changed_cells(&mut frame)?;
+            frame
+        } else {
+            self.render_to_vec()?
+        };
+        w.write_all(&frame)?;
+        Ok(())
+    }
 
-        let mut width = self.area.width as usize;
-        let pos = self.content.cursor_pos();
-        let scrollbar = self.area.scrollbar(
-            self.scroll.y as u16,
-            self.content.line_count() as u16,
-        );
-        if scrollbar.is_some() {
-            width -= 1;
+    /// Render the input field in a buffer, with the cursor moves
+    /// and styling escape sequences, exactly as `display_on` would
+    /// write it.
+    ///
+    /// This lets you batch the writing of several widgets, or
+    /// compare frames.
+    pub fn render_to_vec(&self) -> Result<Vec<u8>, Error> {
+        let mut w = Vec::new();
+        self.write_rows(&mut w, true)?;
+        Ok(w)
+    }
+
+    /// Render the input field as a string, with the same styling
+    /// escape sequences as `display_on` but without the cursor
+    /// moves (rows are separated with new lines).
+    ///
+    /// This is mostly useful for tests and logs.
+    pub fn to_styled_string(&self) -> String {
+        let mut w = Vec::new();
+        // writing in a vec can't fail
+        self.write_rows(&mut w, false).unwrap();
+        String::from_utf8_lossy(&w).to_string()
+    }
+
+    /// write all the rows of the input, either on their screen
+    /// position or one after the other
+    fn write_rows<W: Write>(&self, w: &mut W, positioned: bool) -> Result<(), Error> {
+        let frame = self.render_frame()?;
+        queue!(w, SetBackgroundColor(Color::Reset))?;
+        for (j, row) in frame.rows.iter().enumerate() {
+            if positioned {
+                queue!(w, cursor::MoveTo(self.area.left, j as u16 + self.area.top))?;
+            } else if j > 0 {
+                writeln!(w)?;
+            }
+            for cell in row {
+                w.write_all(&cell.bytes)?;
+            }
+        }
+        if let Some((x, y)) = frame.hardware_cursor_pos.filter(|_| positioned) {
+            queue!(
+                w,
+                cursor::MoveTo(x, y),
+                cursor::SetCursorShape(self.displayed_cursor_shape().terminal_shape()),
+                cursor::Show,
+            )?;
         }
+        Ok(())
+    }
 
+    /// write only the cells which changed since the last frame
+    /// written by this function (all of them if there's none or
+    /// if the area changed), and remember the new frame
+    fn write_changed_cells<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+        let frame = self.render_frame()?;
+        let mut last_frame = self.last_frame.lock().unwrap_or_else(PoisonError::into_inner);
+        let last_rows = last_frame
+            .as_ref()
+            .filter(|frame| frame.area == self.area)
+            .map(|frame| &frame.rows);
         queue!(w, SetBackgroundColor(Color::Reset))?;
-        let mut scrollbar_style = &crate::get_default_skin().scrollbar;
+        for (j, row) in frame.rows.iter().enumerate() {
+            let last_row = last_rows.and_then(|last_rows| last_rows.get(j));
+            // the column at which the terminal's cursor is, if known
+            let mut cursor_col = None;
+            for cell in row {
+                let unchanged = last_row.map_or(false, |last_row| {
+                    last_row
+                        .binary_search_by_key(&cell.col, |last_cell| last_cell.col)
+                        .map_or(false, |idx| last_row[idx] == *cell)
+                });
+                if unchanged {
+                    continue;
+                }
+                if cursor_col != Some(cell.col) {
+                    queue!(w, cursor::MoveTo(
+                        self.area.left + cell.col as u16,
+                        self.area.top + j as u16,
+                    ))?;
+                }
+                w.write_all(&cell.bytes)?;
+                cursor_col = Some(cell.col + cell.width);
+            }
+        }
+        if let Some((x, y)) = frame.hardware_cursor_pos {
+            queue!(
+                w,
+                cursor::MoveTo(x, y),
+                cursor::SetCursorShape(self.displayed_cursor_shape().terminal_shape()),
+                cursor::Show,
+            )?;
+        }
+        #[cfg(feature = "secure")]
+        if self.content.is_secure() && !self.is_masked() {
+            // a revealed secret isn't kept
+            let mut frame = frame;
+            frame.scrub();
+            *last_frame = None;
+            return Ok(());
+        }
+        *last_frame = Some(frame);
+        Ok(())
+    }
+
+    /// render the rows of the input as cells
+    fn render_frame(&self) -> Result<RenderedFrame, Error> {
+        if self.area.width == 0 || self.area.height == 0 {
+            // nothing to draw (this may happen during a resize)
+            return Ok(RenderedFrame {
+                area: self.area.clone(),
+                rows: (0..self.area.height).map(|_| Vec::new()).collect(),
+                hardware_cursor_pos: None,
+            });
+        }
+        let normal_style = if self.focused {
+            &self.focused_style
+        } else {
+            &self.unfocused_style
+        };
+
+        let gutter_width = self.gutter_width();
+        let pos = self.content.cursor_pos();
+        let rows: Vec<VisualRow> = if self.wrap {
+            self.visual_rows()
+        } else {
+            self.content.lines().iter()
+                .enumerate()
+                .map(|(y, line)| VisualRow {
+                    y,
+                    start: self.scroll.x,
+                    end: line.chars.len(),
+                    last: true,
+                })
+                .collect()
+        };
+        let width = self.visible_text_width(rows.len());
+        let scrollbar = if self.has_scrollbar(rows.len()) {
+            compute_scrollbar(self.scroll.y, rows.len(), self.area.height, self.area.top)
+        } else {
+            None
+        };
+
+        let mut scrollbar_style = &self.scrollbar_style;
         let mut focused_scrollbar_style;
         if self.focused {
             if let Some(bg) = self.focused_style.get_bg() {
@@ -413,62 +2431,216 @@ impl InputField {
             }
         }
 
-        let mut numbered_lines = self.content.lines().iter()
-            .map(|line| &line.chars)
-            .enumerate()
-            .skip(self.scroll.y);
+        let cursor_style = if !self.focused {
+            None
+        } else if self.read_only {
+            self.read_only_cursor_style.as_ref()
+        } else {
+            Some(&self.cursor_style)
+        };
+
+        // the style used to draw the cursor, if it's not the terminal's one
+        let drawn_cursor_style = if self.hardware_cursor {
+            None
+        } else {
+            cursor_style
+        };
+        // the screen position of the cursor, when it's the terminal's one
+        let mut hardware_cursor_pos: Option<(u16, u16)> = None;
+        let left_scrollbar = scrollbar.filter(|_| scrollbar_style.is_left());
+        let text_left = self.area.left + u16::from(left_scrollbar.is_some()) + gutter_width as u16;
+
+        let selection = if self.focused {
+            self.selected_range()
+        } else {
+            None
+        };
+        let is_selected = |x: usize, y: usize| {
+            selection.map_or(false, |(start, end)| {
+                let pos = Pos { x, y };
+                start <= pos && pos < end
+            })
+        };
+
+        let placeholder: Vec<char> = if self.content.is_empty() && width > 0 {
+            let chars: Vec<char> = self.placeholder.chars().collect();
+            if self.chars_width(&chars) > width {
+                let mut fitting = Vec::new();
+                let mut fitting_width = 0;
+                for c in chars {
+                    fitting_width += self.char_width(c);
+                    if fitting_width > width.saturating_sub(self.ellipsis.width()) {
+                        break;
+                    }
+                    fitting.push(c);
+                }
+                fitting.extend(self.ellipsis.chars());
+                fitting
+            } else {
+                chars
+            }
+        } else {
+            Vec::new()
+        };
+        let mut placeholder_style = normal_style.clone();
+        placeholder_style.overwrite_with(&self.placeholder_style);
+
+        let mut rows = rows.iter().skip(self.scroll.y);
+        let mut rendered_rows = Vec::with_capacity(self.area.height as usize);
+        // the styles given by the highlighter to the chars of the last line
+        let mut highlighted_line: Option<(usize, Vec<Option<CompoundStyle>>)> = None;
 
         for j in 0..self.area.height {
-            queue!(w, cursor::MoveTo(self.area.left, j + self.area.top))?;
-            if let Some((y, chars)) = numbered_lines.next() {
-                // we don't show ellipsis if the width is below 4
-                let ellipsis_at_start = self.scroll.x > 0 && width > 4;
-                let cursor_at_end = self.focused && y == pos.y && pos.x == chars.len();
-                let ellipsis_at_end = !cursor_at_end
-                    && chars.len() > self.scroll.x + width
-                    && width > 4;
-                for i in 0..width {
-                    if i == 0 && ellipsis_at_start && chars.len() > 0 {
-                        normal_style.queue(w, fit::ELLIPSIS)?;
-                        continue;
+            let mut cells = RowCells::default();
+            let row = rows.next();
+            let fit_mode = row.and_then(|row| self.fit_mode_for_line(row.y, width));
+            if let Some(thumb) = left_scrollbar {
+                scrollbar_style.queue_row(cells.cell(1), j + self.area.top, thumb)?;
+            }
+            if gutter_width > 0 {
+                match row {
+                    Some(row) if row.start == 0 || !self.wrap => {
+                        self.line_number_style.queue(
+                            cells.cell(gutter_width),
+                            format!("{:>1$} ", row.y + 1, gutter_width - 1),
+                        )?;
+                    }
+                    _ => {
+                        SPACE_FILLING.queue_styled(
+                            cells.cell(gutter_width),
+                            &self.line_number_style,
+                            gutter_width,
+                        )?;
                     }
-                    if i == width-1 && ellipsis_at_end {
-                        normal_style.queue(w, fit::ELLIPSIS)?;
+                }
+            }
+            if !placeholder.is_empty() && j == 0 {
+                if self.hardware_cursor && cursor_style.is_some() {
+                    hardware_cursor_pos = Some((text_left, self.area.top));
+                }
+                let mut col = 0;
+                for (i, &c) in placeholder.iter().enumerate() {
+                    let char_width = self.char_width(c);
+                    match drawn_cursor_style {
+                        Some(cursor_style) if i == 0 => {
+                            self.queue_cursor_cell(cells.cell(char_width), cursor_style, &placeholder_style, c)?;
+                        }
+                        _ => placeholder_style.queue(cells.cell(char_width), c)?,
+                    }
+                    col += char_width;
+                }
+                if col < width {
+                    SPACE_FILLING.queue_styled(cells.cell(width - col), normal_style, width - col)?;
+                }
+            } else if let (Some(row), Some(mode)) = (row, fit_mode) {
+                let chars = &self.content.lines()[row.y].chars;
+                self.queue_fitted_line(&mut cells, chars, mode, normal_style, width)?;
+            } else if let Some(row) = row {
+                let y = row.y;
+                let chars = &self.content.lines()[y].chars;
+                if highlighted_line.as_ref().map_or(true, |(hy, _)| *hy != y) {
+                    highlighted_line = Some((y, self.highlighted_styles(chars, normal_style)));
+                }
+                let highlighted = highlighted_line.as_ref().map_or(&[][..], |(_, styles)| &styles[..]);
+                // we don't show ellipsis if the width is too small
+                let ellipsis_width = self.ellipsis.width();
+                let ellipsis_at_start = !self.wrap && self.scroll.x > 0 && width > ellipsis_width + 3;
+                let cursor_at_end = cursor_style.is_some()
+                    && row.last
+                    && y == pos.y
+                    && pos.x == chars.len();
+                let ellipsis_at_end = !self.wrap
+                    && !cursor_at_end
+                    && self.chars_width(&chars[self.scroll.x.min(chars.len())..]) > width
+                    && width > ellipsis_width + 3;
+                // the number of columns available for the chars
+                let text_end = if ellipsis_at_end { width - ellipsis_width } else { width };
+                let mut col = 0;
+                let mut idx = row.start;
+                while col < width {
+                    if col == 0 && ellipsis_at_start && !chars.is_empty() {
+                        normal_style.queue(cells.cell(ellipsis_width), &*self.ellipsis)?;
+                        // the ellipsis hides the first chars
+                        let mut hidden_width = 0;
+                        while hidden_width < ellipsis_width {
+                            hidden_width += if idx < row.end { self.char_width(chars[idx]) } else { 1 };
+                            idx += 1;
+                        }
+                        col += ellipsis_width;
+                        while col < hidden_width {
+                            // the last hidden char was a wide one
+                            normal_style.queue(cells.cell(1), ' ')?;
+                            col += 1;
+                        }
                         continue;
                     }
-                    let idx = i + self.scroll.x;
-                    if idx >= chars.len() {
-                        if cursor_at_end && idx == chars.len() {
-                            self.cursor_style.queue(w, ' ')?;
-                        } else {
-                            normal_style.queue(w, ' ')?;
+                    if col == text_end {
+                        normal_style.queue(cells.cell(ellipsis_width), &*self.ellipsis)?;
+                        break;
+                    }
+                    let at_cursor = if idx >= row.end {
+                        cursor_at_end && idx == chars.len()
+                    } else {
+                        cursor_style.is_some() && pos.x == idx && pos.y == y
+                    };
+                    if self.hardware_cursor && at_cursor {
+                        hardware_cursor_pos = Some((text_left + col as u16, self.area.top + j));
+                    }
+                    if idx >= row.end {
+                        match drawn_cursor_style {
+                            Some(cursor_style) if at_cursor => {
+                                self.queue_cursor_cell(cells.cell(1), cursor_style, normal_style, ' ')?;
+                            }
+                            _ if row.last && idx == chars.len() && is_selected(idx, y) => {
+                                // the selection goes over the line end
+                                self.selection_style.queue(cells.cell(1), ' ')?;
+                            }
+                            _ => {
+                                normal_style.queue(cells.cell(1), ' ')?;
+                            }
                         }
+                        col += 1;
                     } else {
-                        let c = if self.password_mode {
-                            '*'
-                        } else {
-                            chars[idx]
-                        };
-                        if self.focused && pos.x == idx && pos.y == y {
-                            self.cursor_style.queue(w, c)?;
-                        } else {
-                            normal_style.queue(w, c)?;
+                        let char_width = self.char_width(chars[idx]);
+                        if col + char_width > text_end {
+                            // a wide char isn't split, there's a space instead
+                            normal_style.queue(cells.cell(1), ' ')?;
+                            col += 1;
+                            continue;
+                        }
+                        let c = self.displayed_char(chars[idx]);
+                        match drawn_cursor_style {
+                            Some(cursor_style) if at_cursor => {
+                                self.queue_cursor_cell(cells.cell(char_width), cursor_style, normal_style, c)?;
+                            }
+                            _ if is_selected(idx, y) => {
+                                self.selection_style.queue(cells.cell(char_width), c)?;
+                            }
+                            _ => {
+                                let style = highlighted
+                                    .get(idx)
+                                    .and_then(Option::as_ref)
+                                    .unwrap_or(normal_style);
+                                style.queue(cells.cell(char_width), c)?;
+                            }
                         }
+                        col += char_width;
                     }
+                    idx += 1;
                 }
             } else {
-                SPACE_FILLING.queue_styled(w, &normal_style, width)?;
+                SPACE_FILLING.queue_styled(cells.cell(width), normal_style, width)?;
             }
-            if let Some((sctop, scbottom)) = scrollbar {
-                let y = j + self.area.top;
-                if sctop <= y && y <= scbottom {
-                    scrollbar_style.thumb.queue(w)?;
-                } else {
-                    scrollbar_style.track.queue(w)?;
-                }
+            if let Some(thumb) = scrollbar.filter(|_| left_scrollbar.is_none()) {
+                scrollbar_style.queue_row(cells.cell(1), j + self.area.top, thumb)?;
             }
+            rendered_rows.push(cells.cells);
         }
-        Ok(())
+        Ok(RenderedFrame {
+            area: self.area.clone(),
+            rows: rendered_rows,
+            hardware_cursor_pos,
+        })
     }
 
     /// render the input field on stdout
@@ -480,3 +2652,1064 @@ impl InputField {
     }
 }
 
+
+#[cfg(test)]
+mod input_field_undo_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_undo_redo() {
+        let mut input = InputField::default();
+        for c in "hello".chars() {
+            input.put_char(c);
+        }
+        input.put_char(' ');
+        for c in "world".chars() {
+            input.put_char(c);
+        }
+        input.del_char_left();
+        assert!(input.is_content("hello worl"));
+        // deletion breaks the typing run
+        assert!(input.undo());
+        assert!(input.is_content("hello world"));
+        // a run of typed chars is undone in one step
+        assert!(input.undo());
+        assert!(input.is_content(""));
+        assert!(!input.undo());
+        assert!(input.apply_key_event(InputField::CTRL_Y));
+        assert!(input.is_content("hello world"));
+        assert!(input.redo());
+        assert!(input.is_content("hello worl"));
+        assert!(!input.redo());
+        // clear and set_str are undoable
+        input.clear();
+        input.set_str("other");
+        assert!(input.undo());
+        assert!(input.is_content(""));
+        assert!(input.undo());
+        assert!(input.is_content("hello worl"));
+        // moving the cursor breaks the typing run
+        input.move_left();
+        input.put_char('X');
+        input.move_to_line_end();
+        input.put_char('Y');
+        assert!(input.undo());
+        assert!(input.is_content("hello worXl"));
+    }
+
+    #[test]
+    fn test_undo_depth() {
+        let mut input = InputField::default();
+        input.set_undo_depth(2);
+        for s in ["a", "b", "c", "d"] {
+            input.set_str(s);
+        }
+        assert!(input.undo());
+        assert!(input.undo());
+        assert!(!input.undo());
+        assert!(input.is_content("b"));
+    }
+
+    #[test]
+    fn test_paste() {
+        let mut input = InputField::default();
+        input.new_line_on(InputField::ALT_ENTER);
+        input.put_char('>');
+        input.move_left();
+        let paste = "one\r\ntwo\nthree";
+        input.paste(paste);
+        assert!(input.is_content("one\ntwo\nthree>"));
+        // the whole paste is undone at once
+        assert!(input.undo());
+        assert!(input.is_content(">"));
+        let mut input = InputField::default();
+        input.paste(paste);
+        assert!(input.is_content("one two three"));
+        input.set_read_only(true);
+        input.paste(paste);
+        assert!(input.is_content("one two three"));
+    }
+}
+
+#[cfg(test)]
+mod input_field_selection_tests {
+
+    use {
+        super::*,
+        crossterm::event::{KeyEvent, KeyModifiers},
+    };
+
+    fn shift(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::SHIFT)
+    }
+
+    #[test]
+    fn test_range_edits() {
+        let mut input = InputField::default();
+        input.new_line_on(InputField::ALT_ENTER);
+        input.set_str("ab\ncd");
+        // a second cursor, at the start of the first line, kept
+        // consistent by the application
+        let other = Pos { x: 0, y: 0 };
+        let cursor = input.insert_char_at(other, '>');
+        assert_eq!(cursor, Pos { x: 2, y: 1 });
+        assert!(input.is_content(">ab\ncd"));
+        assert_eq!(input.delete_range(Pos { x: 2, y: 0 }, Pos { x: 1, y: 1 }), Pos { x: 3, y: 0 });
+        assert!(input.is_content(">ad"));
+        assert!(input.undo());
+        assert!(input.is_content(">ab\ncd"));
+        input.set_read_only(true);
+        assert_eq!(input.replace_range(other, other, "x"), Pos { x: 2, y: 1 });
+        assert!(input.is_content(">ab\ncd"));
+    }
+
+    #[test]
+    fn test_selection() {
+        let mut input = InputField::default();
+        input.set_str("hello\nworld");
+        assert_eq!(input.selected_range(), None);
+        input.apply_key_event(shift(KeyCode::Left));
+        input.apply_key_event(shift(KeyCode::Left));
+        input.apply_key_event(shift(KeyCode::Up));
+        assert_eq!(
+            input.selected_range(),
+            Some((Pos { x: 3, y: 0 }, Pos { x: 5, y: 1 })),
+        );
+        assert_eq!(input.selected_text().as_deref(), Some("lo\nworld"));
+        // typing replaces the selection
+        input.put_char('X');
+        assert!(input.is_content("helX"));
+        assert_eq!(input.selected_range(), None);
+        assert!(input.undo());
+        assert!(input.is_content("hello\nworld"));
+        // a plain move removes the selection
+        input.apply_key_event(shift(KeyCode::Home));
+        assert!(input.selected_text().is_some());
+        input.move_right();
+        assert_eq!(input.selected_range(), None);
+        // deleting left only removes the selection
+        input.move_to_end();
+        input.apply_key_event(shift(KeyCode::Left));
+        input.apply_key_event(shift(KeyCode::Left));
+        input.del_char_left();
+        assert!(input.is_content("hello\nwor"));
+        // shifted chars are still typed
+        input.apply_key_event(shift(KeyCode::Char('D')));
+        assert!(input.is_content("hello\nworD"));
+    }
+}
+
+#[cfg(test)]
+mod input_field_clipboard_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_cut_paste() {
+        let mut input = InputField::default();
+        input.set_str("hello world");
+        input.select_all();
+        input.move_to_line_start(); // removes the selection
+        assert_eq!(input.copy_selection(), None);
+        assert!(!input.apply_key_event(InputField::CTRL_X));
+        input.select_all();
+        assert!(input.apply_key_event(InputField::CTRL_X));
+        assert!(input.is_empty());
+        assert!(input.apply_key_event(InputField::CTRL_V));
+        assert!(input.apply_key_event(InputField::CTRL_V));
+        assert!(input.is_content("hello worldhello world"));
+    }
+
+    #[test]
+    fn test_paste_new_lines() {
+        let mut input = InputField::default();
+        input.paste("a\nb\r\nc");
+        assert!(input.is_content("a b c"));
+        let mut textarea = InputField::default();
+        textarea.new_line_on(InputField::ALT_ENTER);
+        textarea.paste("a\nb");
+        assert!(textarea.is_content("a\nb"));
+        // CRLF and lone CR are line breaks
+        textarea.clear();
+        textarea.paste("a\r\nb\rc");
+        assert_eq!(textarea.content().line_count(), 3);
+        assert!(textarea.is_content("a\nb\nc"));
+        input.clear();
+        input.paste("a\rb");
+        assert!(input.is_content("a b"));
+    }
+
+    #[test]
+    fn test_no_copy_of_passwords() {
+        let mut input = InputField::new(Area::new(0, 0, 10, 1));
+        input.password_mode = true;
+        input.set_str("secret");
+        input.select_all();
+        assert_eq!(input.copy_selection(), None);
+        assert!(!input.apply_key_event(InputField::CTRL_C));
+        assert!(!input.apply_key_event(InputField::CTRL_X));
+        assert!(input.is_content("secret"));
+        assert!(input.clipboard.is_empty());
+        // a kill deletes without filling the kill buffer
+        input.move_to_start();
+        assert!(input.apply_key_event(InputField::CTRL_K));
+        assert!(input.is_empty());
+        assert!(input.kill_buffer.is_empty());
+        assert!(!input.apply_key_event(InputField::ALT_Y));
+    }
+}
+
+#[cfg(all(test, feature = "secure"))]
+mod input_field_secure_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_set_secure_scrubs_copies() {
+        let mut input = InputField::new(Area::new(0, 0, 10, 1));
+        input.set_str("secret");
+        input.select_all();
+        assert!(input.apply_key_event(InputField::CTRL_C));
+        input.move_to_start();
+        assert!(input.apply_key_event(InputField::CTRL_K));
+        assert!(!input.clipboard.is_empty());
+        assert!(!input.kill_buffer.is_empty());
+        input.set_secure(true);
+        assert!(input.clipboard.is_empty());
+        assert!(input.kill_buffer.is_empty());
+        // revealed frames aren't kept
+        input.set_str("secret");
+        input.set_partial_redraw(true);
+        input.display_on(&mut Vec::new()).unwrap();
+        assert!(input.last_frame.lock().unwrap().is_none());
+        input.password_mode = true;
+        input.display_on(&mut Vec::new()).unwrap();
+        assert!(input.last_frame.lock().unwrap().is_some());
+    }
+}
+
+#[cfg(test)]
+mod input_field_max_len_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_max_len() {
+        let mut input = InputField::default();
+        input.new_line_on(InputField::ALT_ENTER);
+        input.set_max_len(Some(6));
+        input.insert_str("12\n3");
+        assert_eq!(input.len(), 4);
+        assert!(input.put_char('4'));
+        input.insert_str("5678");
+        assert!(input.is_content("12\n345"));
+        assert!(!input.put_char('9'));
+        assert!(!input.insert_new_line());
+        // replacing a selection frees some room
+        input.apply_key_event(KeyEvent::new(KeyCode::Left, KeyModifiers::SHIFT));
+        assert!(input.put_char('X'));
+        assert!(input.is_content("12\n34X"));
+        // a grapheme cluster isn't cut
+        input.clear();
+        input.insert_str("abcdef\u{301}");
+        assert!(input.is_content("abcde"));
+    }
+}
+
+#[cfg(test)]
+mod input_field_read_only_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_read_only() {
+        let mut input = InputField::default();
+        input.set_read_only(true);
+        input.set_str("some log");
+        assert!(!input.put_char('a'));
+        assert!(!input.del_char_left());
+        assert!(!input.insert_new_line());
+        assert!(input.move_left());
+        input.select_all();
+        assert_eq!(input.cut_selection(), None);
+        assert_eq!(input.copy_selection().as_deref(), Some("some log"));
+        assert!(input.is_content("some log"));
+    }
+}
+
+#[cfg(test)]
+mod input_field_wrap_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_wrapped_moves() {
+        let mut input = InputField::new(Area::new(0, 0, 10, 5));
+        input.set_wrap(true);
+        input.set_str("a short text, and more\nend");
+        // rows: "a short ", "text, and ", "more", "end"
+        assert_eq!(input.content().cursor_pos(), Pos { x: 3, y: 1 });
+        assert!(input.move_up());
+        assert_eq!(input.content().cursor_pos(), Pos { x: 21, y: 0 });
+        assert!(input.move_up());
+        assert_eq!(input.content().cursor_pos(), Pos { x: 11, y: 0 });
+        assert!(input.move_up());
+        assert_eq!(input.content().cursor_pos(), Pos { x: 3, y: 0 });
+        assert!(!input.move_up());
+        // clicks are mapped to rows
+        assert!(input.apply_click_event(9, 1));
+        assert_eq!(input.content().cursor_pos(), Pos { x: 17, y: 0 });
+        assert!(input.apply_click_event(9, 0));
+        assert_eq!(input.content().cursor_pos(), Pos { x: 7, y: 0 });
+        assert!(input.apply_click_event(2, 4));
+        assert_eq!(input.content().cursor_pos(), Pos { x: 3, y: 1 });
+        let mut w = Vec::new();
+        input.display_on(&mut w).unwrap();
+    }
+
+    #[test]
+    fn test_wrapped_moves_goal_column() {
+        let mut input = InputField::new(Area::new(0, 0, 20, 5));
+        input.set_wrap(true);
+        input.set_str("abcdefgh\nab\nabcdefgh");
+        input.set_cursor_pos(Pos { x: 6, y: 0 });
+        assert!(input.move_down());
+        assert_eq!(input.content().cursor_pos(), Pos { x: 2, y: 1 });
+        assert!(input.move_down());
+        assert_eq!(input.content().cursor_pos(), Pos { x: 6, y: 2 });
+        assert!(input.move_up());
+        assert!(input.move_up());
+        assert_eq!(input.content().cursor_pos(), Pos { x: 6, y: 0 });
+        // an horizontal move resets the goal
+        assert!(input.move_down());
+        assert!(input.move_left());
+        assert!(input.move_down());
+        assert_eq!(input.content().cursor_pos(), Pos { x: 1, y: 2 });
+    }
+}
+
+#[cfg(test)]
+mod input_field_line_numbers_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_gutter() {
+        let mut input = InputField::new(Area::new(0, 0, 20, 20));
+        input.set_line_numbers(true);
+        input.set_str("a\nb\nc\nd\ne\nf\ng\nh\ni\nsome text");
+        // 10 lines: the gutter is 3 chars wide
+        assert_eq!(input.text_width(), 17);
+        assert!(input.apply_click_event(5, 9));
+        assert_eq!(input.content().cursor_pos(), Pos { x: 2, y: 9 });
+        assert!(input.apply_click_event(1, 9));
+        assert_eq!(input.content().cursor_pos(), Pos { x: 0, y: 9 });
+        let mut w = Vec::new();
+        input.display_on(&mut w).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod input_field_change_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_take_changed() {
+        let mut input = InputField::default();
+        assert!(!input.take_changed());
+        input.set_str("abc");
+        assert!(input.take_changed());
+        assert!(!input.take_changed());
+        input.move_left();
+        input.set_str("abc"); // same content
+        assert!(!input.take_changed());
+        input.clear();
+        assert!(input.take_changed());
+        input.clear();
+        assert!(!input.take_changed());
+        input.undo();
+        assert!(input.take_changed());
+    }
+}
+
+#[cfg(test)]
+mod input_field_kill_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_kill_yank() {
+        let mut input = InputField::default();
+        input.new_line_on(InputField::ALT_ENTER);
+        input.set_str("first line\nsecond line\nthird");
+        input.move_to_start();
+        for _ in 0..5 {
+            input.move_right();
+        }
+        assert!(input.apply_key_event(InputField::CTRL_K));
+        assert!(input.apply_key_event(InputField::CTRL_K));
+        assert!(input.apply_key_event(InputField::CTRL_K));
+        assert!(input.is_content("first\nthird"));
+        input.move_to_end();
+        assert!(input.apply_key_event(InputField::ALT_Y));
+        assert!(input.is_content("first\nthird line\nsecond line"));
+        // a new kill replaces the kill buffer
+        input.move_to_start();
+        assert!(input.apply_key_event(InputField::CTRL_K));
+        assert!(input.apply_key_event(InputField::ALT_Y));
+        assert!(input.apply_key_event(InputField::ALT_Y));
+        assert!(input.is_content("firstfirst\nthird line\nsecond line"));
+    }
+
+    #[test]
+    fn test_kill_after_move_or_undo() {
+        let mut input = InputField::new(Area::new(0, 0, 20, 1));
+        input.set_str("abc def");
+        // a click between two kills
+        input.set_cursor_pos(Pos { x: 4, y: 0 });
+        assert!(input.apply_key_event(InputField::CTRL_K));
+        assert!(input.apply_click_event(1, 0));
+        assert!(input.apply_key_event(InputField::CTRL_K));
+        assert!(input.apply_key_event(InputField::ALT_Y));
+        assert!(input.is_content("abc "));
+        // an undo between two kills
+        input.set_str("abc def");
+        input.set_cursor_pos(Pos { x: 4, y: 0 });
+        assert!(input.apply_key_event(InputField::CTRL_K));
+        assert!(input.apply_key_event(InputField::CTRL_Z));
+        assert!(input.apply_key_event(InputField::CTRL_K));
+        assert!(input.apply_key_event(InputField::ALT_Y));
+        assert!(input.is_content("abc def"));
+    }
+}
+
+#[cfg(test)]
+mod input_field_history_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_history() {
+        let mut input = InputField::default();
+        input.push_history("first".to_string());
+        input.push_history("second".to_string());
+        input.push_history("second".to_string());
+        input.push_history(String::new());
+        assert_eq!(input.history(), &["first", "second"]);
+        input.set_str("draft");
+        assert!(!input.apply_keycode_event(KeyCode::Up)); // navigation not enabled
+        input.set_history_navigation(true);
+        assert!(input.apply_keycode_event(KeyCode::Up));
+        assert!(input.is_content("second"));
+        assert!(input.apply_keycode_event(KeyCode::Up));
+        assert!(input.is_content("first"));
+        assert!(!input.apply_keycode_event(KeyCode::Up));
+        // editing a recalled entry doesn't change the history
+        input.put_char('!');
+        assert!(input.apply_keycode_event(KeyCode::Down));
+        assert!(input.is_content("second"));
+        assert!(input.apply_keycode_event(KeyCode::Down));
+        assert!(input.is_content("draft"));
+        assert!(!input.apply_keycode_event(KeyCode::Down));
+        assert_eq!(input.history(), &["first", "second"]);
+        // in a multi-line field, up and down first move between lines
+        input.new_line_on(InputField::ALT_ENTER);
+        input.set_str("a\nb");
+        assert!(input.apply_keycode_event(KeyCode::Up));
+        assert!(input.is_content("a\nb"));
+        assert!(input.apply_keycode_event(KeyCode::Up));
+        assert!(input.is_content("second"));
+    }
+}
+
+#[cfg(test)]
+mod input_field_fuzzy_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match() {
+        let mut input = InputField::default();
+        assert_eq!(input.fuzzy_match(""), Some(vec![]));
+        assert_eq!(input.fuzzy_match("a"), None);
+        input.new_line_on(InputField::ALT_ENTER);
+        input.set_str("Été à\nla mer");
+        assert_eq!(input.fuzzy_match("éam"), Some(vec![0, 7, 9])); // "à" isn't "a"
+        assert_eq!(input.fuzzy_match("tà\nl"), Some(vec![1, 4, 5, 6]));
+        assert_eq!(input.fuzzy_match("ÉTÉ"), Some(vec![0, 1, 2]));
+        assert_eq!(input.fuzzy_match("mm"), None);
+    }
+}
+
+#[cfg(test)]
+mod input_field_overwrite_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_overwrite() {
+        let mut input = InputField::new(Area::new(0, 0, 6, 1));
+        input.set_max_len(Some(4));
+        input.set_cursor_shape(CursorShape::Bar);
+        input.set_str("abcd");
+        input.move_to_line_start();
+        assert!(input.apply_keycode_event(KeyCode::Insert));
+        assert!(input.is_overwrite());
+        input.put_char('x'); // allowed even at max length
+        input.put_char('y');
+        assert!(input.is_content("xycd"));
+        assert_eq!(input.cursor_pos().x, 2);
+        // the cursor is a block
+        assert_eq!(input.to_styled_string(), "\u{1b}[49mxy\u{1b}[7mc\u{1b}[0md  ");
+        input.undo();
+        assert!(input.is_content("abcd"));
+        // at the end of the line, chars are inserted
+        input.set_max_len(None);
+        input.move_to_line_end();
+        input.put_char('e');
+        assert!(input.is_content("abcde"));
+        assert!(input.apply_keycode_event(KeyCode::Insert));
+        assert!(!input.is_overwrite());
+        input.move_to_line_start();
+        input.put_char('z');
+        assert!(input.is_content("zabcde"));
+    }
+}
+
+#[cfg(test)]
+mod input_field_tab_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_tab_spaces() {
+        let mut input = InputField::default();
+        assert!(!input.apply_keycode_event(KeyCode::Tab));
+        input.set_tab_inserts_spaces(4);
+        input.apply_keycode_event(KeyCode::Tab);
+        input.apply_keycode_event(KeyCode::Tab);
+        input.put_char(' ');
+        input.put_char('a');
+        assert!(input.is_content("         a"));
+        input.move_left();
+        // smart backspace
+        input.apply_keycode_event(KeyCode::Backspace);
+        assert!(input.is_content("        a"));
+        input.apply_keycode_event(KeyCode::Backspace);
+        assert!(input.is_content("    a"));
+        input.set_tab_inserts_char();
+        input.apply_keycode_event(KeyCode::Tab);
+        assert!(input.is_content("    \ta"));
+        input.apply_keycode_event(KeyCode::Backspace);
+        assert!(input.is_content("    a"));
+    }
+}
+
+#[cfg(test)]
+mod input_field_click_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_multiple_clicks() {
+        let mut input = InputField::new(Area::new(10, 10, 30, 3));
+        input.set_str("first line\nsome words, and more");
+        assert!(input.apply_event(&Event::DoubleClick(16, 11)));
+        assert_eq!(input.selected_text().as_deref(), Some("words"));
+        assert!(input.apply_event(&Event::DoubleClick(21, 11)));
+        assert_eq!(input.selected_text().as_deref(), Some(", "));
+        assert!(input.apply_event(&Event::TripleClick(12, 10)));
+        assert_eq!(input.selected_text().as_deref(), Some("first line"));
+        assert!(!input.apply_event(&Event::TripleClick(2, 10)));
+    }
+
+    #[test]
+    fn test_drag() {
+        let mut input = InputField::new(Area::new(0, 0, 10, 2));
+        input.set_str("a\nb\nc\nd\ne");
+        input.move_to_start();
+        assert!(!input.apply_event(&Event::Drag(1, 1)));
+        assert!(input.apply_event(&Event::MouseDown(0, 1)));
+        assert!(input.apply_event(&Event::Drag(1, 1)));
+        assert_eq!(input.selected_text().as_deref(), Some("b"));
+        // dragging below the area scrolls
+        assert!(input.apply_event(&Event::Drag(1, 3)));
+        assert!(input.apply_event(&Event::Drag(1, 3)));
+        assert!(input.scroll().y > 0);
+        assert!(input.apply_event(&Event::Click(0, 1, KeyModifiers::NONE)));
+        assert!(input.selected_text().is_some());
+        // a simple click removes the selection
+        assert!(input.apply_event(&Event::MouseDown(0, 0)));
+        assert!(input.apply_event(&Event::Click(0, 0, KeyModifiers::NONE)));
+        assert_eq!(input.selected_text(), None);
+    }
+
+    #[test]
+    fn test_crossterm_events() {
+        use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+        let mut input = InputField::new(Area::new(0, 0, 10, 1));
+        let key = crossterm::event::Event::Key(KeyEvent::from(KeyCode::Char('a')));
+        assert!(input.apply_crossterm_event(key));
+        assert!(input.apply_crossterm_event(key));
+        assert_eq!(input.get_content(), "aa");
+        let click = crossterm::event::Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Up(MouseButton::Left),
+            column: 1,
+            row: 0,
+            modifiers: KeyModifiers::NONE,
+        });
+        assert!(input.apply_crossterm_event(click));
+        assert_eq!(input.cursor_pos(), Pos { x: 1, y: 0 });
+        assert!(!input.apply_crossterm_event(crossterm::event::Event::Resize(20, 3)));
+    }
+}
+
+#[cfg(test)]
+mod input_field_scroll_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_wheel() {
+        let mut input = InputField::new(Area::new(0, 0, 10, 4));
+        input.set_str("1\n2\n3\n4\n5\n6\n7\n8\n9\n10");
+        input.move_to_start();
+        assert_eq!(input.scroll().y, 0);
+        assert!(input.apply_wheel_event(2, 1, 1));
+        assert_eq!(input.scroll().y, 3);
+        // the wheel is ignored when the pointer isn't over the field
+        assert!(!input.apply_wheel_event(2, 4, 1));
+        assert!(!input.apply_wheel_event(10, 1, -1));
+        assert_eq!(input.scroll().y, 3);
+        assert!(input.apply_wheel_event(9, 3, 1));
+        assert_eq!(input.scroll().y, 6);
+        assert!(!input.apply_wheel_event(2, 1, 1));
+        assert!(input.scroll_lines(-1));
+        assert_eq!(input.scroll().y, 5);
+        // without position, the wheel only scrolls the focused input
+        assert!(input.apply_event(&Event::Wheel(-1)));
+        assert_eq!(input.scroll().y, 2);
+        input.set_focus(false);
+        assert!(!input.apply_event(&Event::Wheel(1)));
+        assert_eq!(input.scroll().y, 2);
+        assert_eq!(input.content().cursor_pos(), Pos::default());
+        assert_eq!(input.screen_cursor_pos(), None);
+    }
+
+    #[test]
+    fn test_screen_cursor_pos() {
+        let mut input = InputField::new(Area::new(5, 3, 10, 4));
+        input.set_str("1\n2\n3\n4\n5\n6\n7\n8\n9\n10");
+        assert_eq!(input.cursor_pos(), Pos { x: 2, y: 9 });
+        assert_eq!(input.screen_cursor_pos(), Some(Pos { x: 7, y: 6 }));
+        input.set_cursor_pos(Pos { x: 20, y: 1 });
+        assert_eq!(input.cursor_pos(), Pos { x: 1, y: 1 });
+        assert_eq!(input.screen_cursor_pos(), Some(Pos { x: 6, y: 3 }));
+    }
+    #[test]
+    fn test_left_scrollbar() {
+        let mut input = InputField::new(Area::new(5, 3, 6, 3));
+        input.set_focus(false);
+        let mut scrollbar_style = ScrollBarStyle::new();
+        scrollbar_style.track = StyledChar::nude('.');
+        scrollbar_style.thumb = StyledChar::nude('#');
+        scrollbar_style.set_side(ScrollBarSide::Left);
+        input.set_scrollbar_style(scrollbar_style);
+        input.set_str("abc\ndef\nghi\njkl\nmno\npqr");
+        input.set_cursor_pos(Pos { x: 1, y: 0 });
+        assert_eq!(input.screen_cursor_pos(), Some(Pos { x: 7, y: 3 }));
+        input.set_focus(true);
+        input.apply_click_event(8, 4);
+        assert_eq!(input.cursor_pos(), Pos { x: 2, y: 1 });
+        input.set_focus(false);
+        assert_eq!(input.to_styled_string(), "\u{1b}[49m#abc  \n#def  \n.ghi  ");
+        // no scrollbar when the content fits
+        input.set_str("abc");
+        input.set_cursor_pos(Pos { x: 1, y: 0 });
+        assert_eq!(input.screen_cursor_pos(), Some(Pos { x: 6, y: 3 }));
+    }
+}
+
+#[cfg(test)]
+mod input_field_degenerate_area_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_degenerate_areas() {
+        let mut input = InputField::new(Area::new(0, 0, 0, 0));
+        input.set_str("abc");
+        let mut w = Vec::new();
+        input.display_on(&mut w).unwrap();
+        for (width, height) in [(0, 0), (0, 3), (3, 0), (1, 1), (1, 3), (2, 3)] {
+            let mut input = InputField::new(Area::new(0, 0, width, height));
+            input.new_line_on(InputField::ALT_ENTER);
+            input.set_line_numbers(true);
+            input.set_str("a long enough line\nb\nc\nd");
+            input.move_to_start();
+            for wrap in [false, true] {
+                input.set_wrap(wrap);
+                input.display_on(&mut w).unwrap();
+                let frame = input.render_frame().unwrap();
+                assert_eq!(frame.rows.len(), height as usize);
+                for row in &frame.rows {
+                    let row_width: usize = row.iter().map(|cell| cell.width).sum();
+                    assert!(row_width <= width as usize);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod input_field_scrollbar_boundary_tests {
+
+    use super::*;
+
+    fn row_widths(input: &InputField) -> Vec<usize> {
+        input.render_frame().unwrap()
+            .rows
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.width).sum())
+            .collect()
+    }
+
+    #[test]
+    fn test_content_height_equal_to_area_height() {
+        let mut input = InputField::new(Area::new(0, 0, 6, 3));
+        input.new_line_on(InputField::ALT_ENTER);
+        input.set_focus(true);
+        // as many lines as rows: no scrollbar, the whole width is for the text
+        input.set_str("a\nb\nabcdef");
+        assert_eq!(input.cursor_pos(), Pos { x: 6, y: 2 });
+        assert!(!input.has_scrollbar(input.row_count()));
+        assert_eq!(input.visible_text_width(input.row_count()), 6);
+        assert_eq!(row_widths(&input), vec![6, 6, 6]);
+        // the line is scrolled by one column to show the cursor at its end
+        assert_eq!(input.screen_cursor_pos(), Some(Pos { x: 5, y: 2 }));
+        // one more line: the scrollbar takes the last column
+        input.move_to_end();
+        input.put_char('\n');
+        assert!(input.has_scrollbar(input.row_count()));
+        assert_eq!(input.visible_text_width(input.row_count()), 5);
+        assert_eq!(row_widths(&input), vec![6, 6, 6]);
+        input.set_cursor_pos(Pos { x: 6, y: 2 });
+        assert_eq!(input.screen_cursor_pos(), Some(Pos { x: 4, y: 1 })); // scrolled
+        // when wrapping, the rows are computed with the width they're drawn on
+        input.set_wrap(true);
+        // (a row keeps a cell for the cursor at the end of the line)
+        input.set_str("abcde\nabcde\nabcde");
+        assert_eq!(input.row_count(), 3);
+        assert_eq!(row_widths(&input), vec![6, 6, 6]);
+        input.put_char('g');
+        assert_eq!(input.row_count(), 6);
+        assert_eq!(input.visible_text_width(input.row_count()), 5);
+        assert_eq!(row_widths(&input), vec![6, 6, 6]);
+    }
+}
+
+#[cfg(test)]
+mod input_field_event_action_tests {
+
+    use {
+        super::*,
+        crossterm::event::KeyModifiers,
+    };
+
+    fn key(code: KeyCode) -> Event {
+        Event::Key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn click(x: u16) -> Event {
+        Event::Click(x, 0, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_event_actions() {
+        let mut input = InputField::new(Area::new(0, 0, 10, 1));
+        input.set_focus(false);
+        input.set_str("ab");
+        assert!(input.take_changed());
+        assert_eq!(input.apply_event_detailed(&click(20)), InputFieldAction::Ignored);
+        assert!(!input.take_focus_gained());
+        assert_eq!(input.apply_event_detailed(&click(1)), InputFieldAction::Focused);
+        assert!(input.take_focus_gained());
+        assert!(!input.take_focus_gained());
+        assert_eq!(input.apply_event_detailed(&click(1)), InputFieldAction::Moved);
+        assert!(!input.take_focus_gained());
+        assert_eq!(input.apply_event_detailed(&key(KeyCode::Backspace)), InputFieldAction::ContentChanged);
+        assert_eq!(input.apply_event_detailed(&key(KeyCode::Backspace)), InputFieldAction::Ignored);
+        assert_eq!(input.apply_event_detailed(&key(KeyCode::Char('c'))), InputFieldAction::ContentChanged);
+        // the changes are still reported by take_changed
+        assert!(input.take_changed());
+        assert_eq!(input.apply_event_detailed(&key(KeyCode::Home)), InputFieldAction::Moved);
+        assert!(!input.take_changed());
+        input.set_read_only(true);
+        assert_eq!(input.apply_event_detailed(&key(KeyCode::Delete)), InputFieldAction::Ignored);
+        // a double click also focuses the input
+        input.set_focus(false);
+        assert!(input.apply_event(&Event::DoubleClick(1, 0)));
+        assert!(input.focused());
+        assert!(input.take_focus_gained());
+        // a focus gained then lost isn't reported
+        input.set_focus(false);
+        assert_eq!(input.apply_event_detailed(&click(1)), InputFieldAction::Focused);
+        input.set_focus(false);
+        assert!(!input.take_focus_gained());
+    }
+}
+
+#[cfg(test)]
+mod input_field_completion_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_word_before_cursor() {
+        let mut input = InputField::default();
+        input.new_line_on(InputField::ALT_ENTER);
+        input.set_str("let x = some_va\nfoo.ba");
+        assert_eq!(input.word_before_cursor(), ("ba".to_string(), Pos { x: 4, y: 1 }));
+        input.put_char('.');
+        assert_eq!(input.word_before_cursor(), (String::new(), Pos { x: 7, y: 1 }));
+        input.set_cursor_pos(Pos { x: 15, y: 0 });
+        assert_eq!(input.word_before_cursor(), ("va".to_string(), Pos { x: 13, y: 0 }));
+        assert!(input.replace_word_before_cursor("value"));
+        assert!(input.is_content("let x = some_value\nfoo.ba."));
+        assert_eq!(input.cursor_pos(), Pos { x: 18, y: 0 });
+        assert!(!input.replace_word_before_cursor("value"));
+        assert!(input.undo());
+        assert!(input.is_content("let x = some_va\nfoo.ba."));
+    }
+}
+
+#[cfg(test)]
+mod input_field_render_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_styled_string() {
+        let mut input = InputField::new(Area::new(3, 3, 4, 2));
+        input.set_focus(false);
+        input.set_str("ab\ncd");
+        assert_eq!(
+            input.to_styled_string(),
+            "\u{1b}[49mab  \ncd  ",
+        );
+    }
+
+    #[test]
+    fn test_password_char() {
+        let mut input = InputField::new(Area::new(0, 0, 6, 1));
+        input.set_focus(false);
+        input.set_str("a日c");
+        input.password_mode = true;
+        assert_eq!(input.to_styled_string(), "\u{1b}[49m***   ");
+        input.set_password_char('•');
+        assert_eq!(input.password_char(), '•');
+        assert_eq!(input.to_styled_string(), "\u{1b}[49m•••   ");
+        input.set_password_char(' ');
+        assert_eq!(input.to_styled_string(), "\u{1b}[49m      ");
+        // chars not taking exactly one cell are refused
+        input.set_password_char('日');
+        assert_eq!(input.password_char(), '*');
+        input.set_password_char('\t');
+        assert_eq!(input.password_char(), '*');
+    }
+
+    #[test]
+    fn test_reveal() {
+        let mut input = InputField::new(Area::new(0, 0, 6, 1));
+        input.set_focus(false);
+        input.set_str("a日c");
+        input.password_mode = true;
+        assert_eq!(input.to_styled_string(), "\u{1b}[49m***   ");
+        input.set_reveal(true);
+        assert!(input.is_revealed());
+        assert_eq!(input.to_styled_string(), "\u{1b}[49ma日c  ");
+        input.set_reveal(false);
+        assert_eq!(input.to_styled_string(), "\u{1b}[49m***   ");
+        // revealing doesn't matter out of password mode
+        input.password_mode = false;
+        assert_eq!(input.to_styled_string(), "\u{1b}[49ma日c  ");
+    }
+
+    #[test]
+    fn test_cursor_shapes() {
+        let mut input = InputField::new(Area::new(0, 0, 4, 1));
+        input.set_str("ab");
+        assert_eq!(input.to_styled_string(), "\u{1b}[49mab\u{1b}[7m \u{1b}[0m ");
+        input.set_cursor_shape(CursorShape::Underline);
+        assert_eq!(input.to_styled_string(), "\u{1b}[49mab\u{1b}[4m \u{1b}[0m ");
+        // the bar is drawn on a blank cell, and an underline on a char
+        input.set_cursor_shape(CursorShape::Bar);
+        assert_eq!(input.to_styled_string(), "\u{1b}[49mab▏ ");
+        input.move_left();
+        assert_eq!(input.to_styled_string(), "\u{1b}[49ma\u{1b}[4mb\u{1b}[0m  ");
+        // the terminal's cursor is given the shape
+        input.set_hardware_cursor(true);
+        let frame = String::from_utf8(input.render_to_vec().unwrap()).unwrap();
+        assert!(frame.ends_with("\u{1b}[1;2H\u{1b}[5 q\u{1b}[?25h"));
+    }
+
+    #[test]
+    fn test_render_to_vec() {
+        let mut input = InputField::new(Area::new(3, 3, 4, 2));
+        input.set_str("ab\ncd");
+        let frame = input.render_to_vec().unwrap();
+        assert!(frame.starts_with(b"\x1b[49m\x1b[4;4H"));
+        let mut w = Vec::new();
+        input.display_on(&mut w).unwrap();
+        assert_eq!(w, frame);
+    }
+
+    #[test]
+    fn test_highlighter() {
+        let mut input = InputField::new(Area::new(0, 0, 7, 1));
+        input.set_focus(false);
+        input.set_str("a日b bc");
+        input.set_highlighter(|line| {
+            line.match_indices('b')
+                .map(|(start, _)| (start, start + 1, CompoundStyle::with_fg(Color::Red)))
+                .chain(std::iter::once((1, 100, CompoundStyle::with_attr(Attribute::Bold))))
+                .collect()
+        });
+        // the bold range, going beyond the line, is clamped
+        assert_eq!(
+            input.to_styled_string(),
+            "\u{1b}[49ma\
+            \u{1b}[1m日\u{1b}[0m\
+            \u{1b}[38;5;9m\u{1b}[1mb\u{1b}[0m\
+            \u{1b}[1m \u{1b}[0m\
+            \u{1b}[38;5;9m\u{1b}[1mb\u{1b}[0m\
+            \u{1b}[1mc\u{1b}[0m",
+        );
+        input.password_mode = true;
+        assert!(!input.to_styled_string().contains("\u{1b}[1m"));
+    }
+
+    #[test]
+    fn test_partial_redraw() {
+        let mut input = InputField::new(Area::new(3, 3, 10, 2));
+        input.set_str("abc");
+        input.set_partial_redraw(true);
+        let display = |input: &InputField| {
+            let mut w = Vec::new();
+            input.display_on(&mut w).unwrap();
+            String::from_utf8(w).unwrap()
+        };
+        // first display: everything is written
+        let full = String::from_utf8(input.render_to_vec().unwrap()).unwrap();
+        assert_eq!(display(&input), full);
+        // nothing changed
+        assert_eq!(display(&input), "\u{1b}[49m");
+        // only the typed char and the cursor are written
+        input.put_char('d');
+        let partial = display(&input);
+        assert!(partial.starts_with("\u{1b}[49m\u{1b}[4;7Hd"));
+        assert!(!partial.contains("abc"));
+        assert!(partial.len() < full.len() / 2);
+        // after an invalidation, the whole area is written again
+        input.invalidate();
+        assert_eq!(display(&input), String::from_utf8(input.render_to_vec().unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_input_field_is_send_and_sync() {
+        fn check<T: Send + Sync>() {}
+        check::<InputField>();
+    }
+}
+
+#[cfg(test)]
+mod input_field_wide_chars_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_wide_chars_rendering() {
+        let mut input = InputField::new(Area::new(0, 0, 5, 1));
+        input.set_focus(false);
+        input.set_str("日本語");
+        assert_eq!(input.to_styled_string(), "\u{1b}[49m日本…");
+        // a wide char straddling the right edge is replaced with a space
+        input.set_area(Area::new(0, 0, 3, 1));
+        assert_eq!(input.to_styled_string(), "\u{1b}[49m日 ");
+    }
+
+    #[test]
+    fn test_fit_mode() {
+        let mut input = InputField::new(Area::new(0, 0, 10, 1));
+        input.set_str("/home/dev/project/file.rs");
+        input.set_focus(false);
+        input.set_fit_mode(Some(FitMode::Start));
+        assert_eq!(input.to_styled_string(), "\u{1b}[49m…t/file.rs");
+        input.set_fit_mode(Some(FitMode::Middle));
+        assert_eq!(input.to_styled_string(), "\u{1b}[49m/home…e.rs");
+        // wide chars aren't split
+        input.set_str("日本語のテキスト");
+        input.set_fit_mode(Some(FitMode::Start));
+        assert_eq!(input.to_styled_string(), "\u{1b}[49m… テキスト");
+        // when focused, the cursor decides what's visible
+        input.set_focus(true);
+        assert_eq!(input.to_styled_string(), "\u{1b}[49m… キスト\u{1b}[7m \u{1b}[0m ");
+    }
+
+    #[test]
+    fn test_custom_ellipsis() {
+        let mut input = InputField::new(Area::new(0, 0, 8, 1));
+        input.set_ellipsis("...");
+        input.set_focus(false);
+        input.set_str("abcdefghijkl");
+        assert_eq!(input.to_styled_string(), "\u{1b}[49mabcde...");
+        // the cursor stays visible out of the ellipsis
+        input.set_focus(true);
+        input.set_cursor_pos(Pos { x: 6, y: 0 });
+        assert_eq!(input.screen_cursor_pos(), Some(Pos { x: 4, y: 0 }));
+        assert_eq!(input.to_styled_string(), "\u{1b}[49m...f\u{1b}[7mg\u{1b}[0m...");
+        input.move_to_end();
+        assert_eq!(input.to_styled_string(), "\u{1b}[49m...ijkl\u{1b}[7m \u{1b}[0m");
+        // the placeholder is fitted too
+        input.clear();
+        input.set_placeholder("a long placeholder");
+        let dim = |s: &str| s.chars().map(|c| format!("\u{1b}[2m{}\u{1b}[0m", c)).collect::<String>();
+        assert_eq!(
+            input.to_styled_string(),
+            format!("\u{1b}[49m\u{1b}[7ma\u{1b}[0m{}", dim(" lon...")),
+        );
+    }
+
+    #[test]
+    fn test_wide_chars_cursor() {
+        let mut input = InputField::new(Area::new(0, 0, 10, 1));
+        input.set_str("日本語");
+        assert_eq!(input.screen_cursor_pos(), Some(Pos { x: 6, y: 0 }));
+        assert!(input.apply_click_event(3, 0));
+        assert_eq!(input.cursor_pos(), Pos { x: 1, y: 0 });
+        assert!(input.apply_click_event(1, 0));
+        assert_eq!(input.cursor_pos(), Pos { x: 0, y: 0 });
+        // the scroll keeps the cursor visible
+        input.set_area(Area::new(0, 0, 6, 1));
+        input.set_str("日本語の文字");
+        assert_eq!(input.scroll().x, 4);
+        assert_eq!(input.screen_cursor_pos(), Some(Pos { x: 4, y: 0 }));
+        assert!(input.apply_click_event(2, 0));
+        assert_eq!(input.cursor_pos(), Pos { x: 5, y: 0 });
+    }
+
+    #[test]
+    fn test_wide_chars_wrapping() {
+        let mut input = InputField::new(Area::new(0, 0, 5, 3));
+        input.set_wrap(true);
+        input.set_str("日本語の文");
+        // rows: "日本", "語の", "文"
+        assert_eq!(input.screen_cursor_pos(), Some(Pos { x: 2, y: 2 }));
+        assert!(input.move_up());
+        assert_eq!(input.cursor_pos(), Pos { x: 3, y: 0 });
+        assert!(input.apply_click_event(3, 0));
+        assert_eq!(input.cursor_pos(), Pos { x: 1, y: 0 });
+    }
+}
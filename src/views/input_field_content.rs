@@ -1,26 +1,173 @@
 use {
     std::{
+        borrow::Cow,
+        cmp::Ordering,
         fmt,
+        ops::Range,
     },
+    unicode_width::UnicodeWidthChar,
 };
 
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
+#[cfg(all(test, feature = "secure"))]
+thread_local! {
+    /// number of chars overwritten by scrubbing in the thread
+    static SCRUBBED: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Overwrite with zeros the spare capacity of the chars, where
+/// removed chars may remain
+#[cfg(feature = "secure")]
+fn scrub_spare_capacity(chars: &mut Vec<char>) {
+    #[cfg(test)]
+    SCRUBBED.with(|n| n.set(n.get() + chars.spare_capacity_mut().len()));
+    for slot in chars.spare_capacity_mut() {
+        // a volatile write can't be optimized away, even when the
+        // buffer is about to be freed
+        unsafe { std::ptr::write_volatile(slot.as_mut_ptr(), '\0') };
+    }
+    std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+}
+
+/// Without the `secure` feature, contents can't be made secure
+/// and there's never anything to scrub
+#[cfg(not(feature = "secure"))]
+fn scrub_spare_capacity(_chars: &mut Vec<char>) {}
+
+/// Remove the chars, overwriting the whole buffer with zeros
+fn scrub(chars: &mut Vec<char>) {
+    chars.clear();
+    scrub_spare_capacity(chars);
+}
+
+/// Remove the bytes, overwriting the whole buffer with zeros
+#[cfg(feature = "secure")]
+pub(crate) fn scrub_bytes(bytes: &mut Vec<u8>) {
+    bytes.clear();
+    for slot in bytes.spare_capacity_mut() {
+        unsafe { std::ptr::write_volatile(slot.as_mut_ptr(), 0) };
+    }
+    std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+}
+
+/// Remove the text, overwriting the whole buffer with zeros
+#[cfg(feature = "secure")]
+pub(crate) fn scrub_string(s: &mut String) {
+    // the string is empty before its buffer is overwritten, so
+    // it's never invalid UTF-8
+    scrub_bytes(unsafe { s.as_mut_vec() });
+}
+
+/// Tell whether the char is part of the grapheme cluster of the
+/// preceding one: combining marks, variation selectors, joiners
+/// and emoji skin tone modifiers
+fn is_cluster_extension(c: char) -> bool {
+    matches!(c, '\u{1F3FB}'..='\u{1F3FF}')
+        || (c != '\u{200B}' && c.width() == Some(0))
+}
+
+const fn is_regional_indicator(c: char) -> bool {
+    matches!(c, '\u{1F1E6}'..='\u{1F1FF}')
+}
+
+/// Tell whether a grapheme cluster starts at the given index.
+///
+/// This is only an approximation of the Unicode segmentation rules
+/// (termimad doesn't depend on unicode-segmentation), good enough
+/// for combining sequences, emoji modifiers, common ZWJ sequences
+/// and flags. Other cases aren't handled: Hangul syllables made of
+/// conjoining jamos are split, any char following a ZWJ is joined
+/// (not only pictographs), prepend chars are separated, etc.
+pub(crate) fn is_cluster_boundary(chars: &[char], idx: usize) -> bool {
+    if idx == 0 || idx >= chars.len() {
+        return true;
+    }
+    let c = chars[idx];
+    let prev = chars[idx - 1];
+    if prev == ZERO_WIDTH_JOINER || is_cluster_extension(c) {
+        return false;
+    }
+    if is_regional_indicator(c) && is_regional_indicator(prev) {
+        // flags are pairs of regional indicators
+        let count = chars[..idx].iter()
+            .rev()
+            .take_while(|&&c| is_regional_indicator(c))
+            .count();
+        return count % 2 == 0;
+    }
+    true
+}
+
+/// return the start of the grapheme cluster ending at idx
+fn prev_cluster_boundary(chars: &[char], idx: usize) -> usize {
+    let mut idx = idx.saturating_sub(1);
+    while !is_cluster_boundary(chars, idx) {
+        idx -= 1;
+    }
+    idx
+}
+
+/// return the end of the grapheme cluster starting at idx
+fn next_cluster_boundary(chars: &[char], idx: usize) -> usize {
+    let mut idx = idx + 1;
+    while !is_cluster_boundary(chars, idx) {
+        idx += 1;
+    }
+    idx.min(chars.len())
+}
+
+/// A position in the content, `y` being the line index
+/// and `x` the index of the char in the line.
+///
+/// Moves and deletions keep the cursor on grapheme cluster
+/// boundaries, so that for example an accent made of a combining
+/// char is deleted with its base letter. Those boundaries are
+/// approximated: some rare clusters (for example Hangul syllables
+/// made of conjoining jamos) are still handled char by char.
+///
+/// Positions are ordered as in the text: by line, then by char.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct Pos {
     pub x: usize,
     pub y: usize,
 }
+impl Ord for Pos {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.y.cmp(&other.y).then(self.x.cmp(&other.x))
+    }
+}
+impl PartialOrd for Pos {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Line {
     pub chars: Vec<char>,
 }
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub struct InputFieldContent {
     /// the cursor's position
     pos: Pos,
     /// never empty
     lines: Vec<Line>,
+    /// the column the cursor tries to reach when moving
+    /// vertically, with the position it was valid for
+    /// (any other move or edit invalidates it)
+    goal: Option<(usize, Pos)>,
+    /// whether the buffers are overwritten with zeros when
+    /// they're freed or when chars are removed
+    secure: bool,
 }
 
+impl PartialEq for InputFieldContent {
+    fn eq(&self, other: &Self) -> bool {
+        self.pos == other.pos && self.lines == other.lines
+    }
+}
+impl Eq for InputFieldContent {}
+
 pub struct Chars<'c> {
     content: &'c InputFieldContent,
     pos: Pos,
@@ -69,6 +216,18 @@ impl Default for InputFieldContent {
             // there's always a line
             lines: vec![Line::default()],
             pos: Pos::default(),
+            goal: None,
+            secure: false,
+        }
+    }
+}
+
+impl Drop for InputFieldContent {
+    fn drop(&mut self) {
+        if self.secure {
+            for line in &mut self.lines {
+                scrub(&mut line.chars);
+            }
         }
     }
 }
@@ -77,16 +236,12 @@ impl fmt::Display for InputFieldContent {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use fmt::Write;
         let mut lines = self.lines.iter().peekable();
-        loop {
-            if let Some(line) = lines.next() {
-                for &c in &line.chars {
-                    f.write_char(c)?;
-                }
-                if lines.peek().is_some() {
-                    f.write_char('\n')?;
-                }
-            } else {
-                break;
+        while let Some(line) = lines.next() {
+            for &c in &line.chars {
+                f.write_char(c)?;
+            }
+            if lines.peek().is_some() {
+                f.write_char('\n')?;
             }
         }
         Ok(())
@@ -114,13 +269,25 @@ impl InputFieldContent {
     /// The position set may be different to ensure consistency
     /// (for example if it's after the end, it will be set back).
     pub fn set_cursor_pos(&mut self, new_pos: Pos) {
-        if new_pos.y >= self.lines.len() {
-            self.pos = self.end();
+        self.pos = self.valid_pos(new_pos);
+    }
+    /// return the nearest position which is in the content
+    fn valid_pos(&self, pos: Pos) -> Pos {
+        if pos.y >= self.lines.len() {
+            self.end()
         } else {
-            self.pos.y = new_pos.y;
-            self.pos.x = new_pos.x.min(self.lines[self.pos.y].chars.len());
+            Pos {
+                x: pos.x.min(self.lines[pos.y].chars.len()),
+                y: pos.y,
+            }
         }
     }
+    /// return the number of chars, counting line breaks
+    pub fn len(&self) -> usize {
+        self.lines.iter()
+            .map(|line| line.chars.len())
+            .sum::<usize>() + self.lines.len() - 1
+    }
     pub fn is_empty(&self) -> bool {
         match self.lines.len() {
             1 => self.lines[0].chars.is_empty(),
@@ -146,6 +313,7 @@ impl InputFieldContent {
         if c == '\n' {
             self.lines.push(Line::default());
         } else {
+            self.reserve(self.lines.len() - 1, 1);
             self.last_line().chars.push(c);
         }
     }
@@ -156,14 +324,80 @@ impl InputFieldContent {
         content
     }
     pub fn clear(&mut self) {
+        if self.secure {
+            for line in &mut self.lines {
+                scrub(&mut line.chars);
+            }
+        }
         self.lines.clear();
         self.lines.push(Line::default());
         self.pos = Pos::default();
     }
+    /// Make the content secure, for example for a password: the
+    /// chars are then overwritten with zeros in memory when they're
+    /// removed, when the content is cleared or dropped, and when a
+    /// line is moved to a bigger buffer.
+    ///
+    /// This is a best effort: it doesn't apply to the strings
+    /// given to or returned by the content.
+    ///
+    /// This requires the `secure` feature.
+    #[cfg(feature = "secure")]
+    pub fn set_secure(&mut self, secure: bool) {
+        self.secure = secure;
+        if secure {
+            for y in 0..self.lines.len() {
+                self.scrub_spare(y);
+            }
+        }
+    }
+    #[cfg(feature = "secure")]
+    pub const fn is_secure(&self) -> bool {
+        self.secure
+    }
+    /// ensure there's room for more chars in the line, the chars
+    /// being moved to a bigger buffer and the old one scrubbed
+    /// when secure (instead of letting the vec reallocate)
+    fn reserve(&mut self, y: usize, additional: usize) {
+        let chars = &mut self.lines[y].chars;
+        if !self.secure || chars.capacity() - chars.len() >= additional {
+            return;
+        }
+        let mut bigger = Vec::with_capacity((chars.len() + additional).max(2 * chars.capacity()));
+        bigger.extend_from_slice(chars);
+        scrub_spare_capacity(&mut bigger); // so that it's never uninitialized
+        let mut old = std::mem::replace(chars, bigger);
+        scrub(&mut old);
+    }
+    /// scrub the chars removed from the line, when secure
+    fn scrub_spare(&mut self, y: usize) {
+        if self.secure {
+            scrub_spare_capacity(&mut self.lines[y].chars);
+        }
+    }
+    /// free lines removed from the content, scrubbing them when secure
+    fn discard_lines<I: IntoIterator<Item = Line>>(&self, lines: I) {
+        for mut line in lines {
+            self.discard_chars(&mut line.chars);
+        }
+    }
+    /// free chars removed from the content, scrubbing them when secure
+    fn discard_chars(&self, chars: &mut Vec<char>) {
+        if self.secure {
+            scrub(chars);
+        }
+    }
+    /// append chars to the line, discarding their buffer
+    fn append_chars(&mut self, y: usize, mut chars: Vec<char>) {
+        self.reserve(y, chars.len());
+        self.lines[y].chars.extend_from_slice(&chars);
+        self.discard_chars(&mut chars);
+    }
     pub fn insert_new_line(&mut self) {
         let new_line = Line {
             chars: self.lines[self.pos.y].chars.split_off(self.pos.x),
         };
+        self.scrub_spare(self.pos.y);
         self.pos.x = 0;
         self.pos.y += 1;
         self.lines.insert(self.pos.y, new_line);
@@ -176,13 +410,16 @@ impl InputFieldContent {
         } else if c == '\r' {
             // skipping
         } else {
+            self.reserve(self.pos.y, 1);
             self.lines[self.pos.y].chars.insert(self.pos.x, c);
             self.pos.x += 1;
         }
     }
-    /// Insert the string on cursor point, as if it was typed
+    /// Insert the string on cursor point, as if it was typed.
+    ///
+    /// CRLF and lone CR are inserted as line breaks.
     pub fn insert_str<S: AsRef<str>>(&mut self, s: S) {
-        for c in s.as_ref().chars() {
+        for c in Self::normalize_newlines(s.as_ref()).chars() {
             self.insert_char(c);
         }
     }
@@ -204,42 +441,208 @@ impl InputFieldContent {
     ///
     ///  Don't move the cursor if the string content didn't change.
     pub fn set_str<S: AsRef<str>>(&mut self, s: S) {
-        if self.is_str(s.as_ref()) {
+        let s = Self::normalize_newlines(s.as_ref());
+        if self.is_str(&s) {
             return;
         }
         self.clear();
         self.insert_str(s);
     }
-    /// remove the char left of the cursor, if any.
+    /// Return the string between two positions (start included, end excluded)
+    pub fn string_between(&self, start: Pos, end: Pos) -> String {
+        let mut s = String::new();
+        for y in start.y..=end.y.min(self.lines.len() - 1) {
+            let chars = &self.lines[y].chars;
+            let x_start = if y == start.y { start.x.min(chars.len()) } else { 0 };
+            let x_end = if y == end.y { end.x.min(chars.len()) } else { chars.len() };
+            if x_start < x_end {
+                s.extend(&chars[x_start..x_end]);
+            }
+            if y < end.y {
+                s.push('\n');
+            }
+        }
+        s
+    }
+    /// Iterate over the lines having chars between two positions (start
+    /// included, end excluded), giving for each one its index and the
+    /// range of those chars
+    pub fn lines_between(
+        &self,
+        start: Pos,
+        end: Pos,
+    ) -> impl Iterator<Item = (usize, Range<usize>)> + '_ {
+        let (start, end) = (self.valid_pos(start), self.valid_pos(end));
+        (start.y..=end.y)
+            .filter(move |_| start <= end)
+            .map(move |y| {
+                let x_start = if y == start.y { start.x } else { 0 };
+                let x_end = if y == end.y { end.x } else { self.lines[y].chars.len() };
+                (y, x_start..x_end)
+            })
+    }
+    /// Return the char at the given position, which is a line break
+    /// at the end of all lines but the last one
+    pub fn char_at(&self, pos: Pos) -> Option<char> {
+        let line = self.lines.get(pos.y)?;
+        match line.chars.get(pos.x) {
+            Some(&c) => Some(c),
+            None if pos.x == line.chars.len() && pos.y + 1 < self.lines.len() => Some('\n'),
+            None => None,
+        }
+    }
+    /// Return the position of the char starting at the given byte
+    /// index in the string of the content (as given by `to_string`),
+    /// or None if it's not a char boundary of this string.
+    ///
+    /// This lets you use the results of an analysis of the string,
+    /// for example to highlight its tokens.
+    pub fn byte_to_pos(&self, byte: usize) -> Option<Pos> {
+        let mut line_start = 0;
+        for (y, line) in self.lines.iter().enumerate() {
+            let mut offset = line_start;
+            for (x, c) in line.chars.iter().enumerate() {
+                if offset == byte {
+                    return Some(Pos { x, y });
+                }
+                if offset > byte {
+                    return None;
+                }
+                offset += c.len_utf8();
+            }
+            if offset == byte {
+                return Some(Pos { x: line.chars.len(), y });
+            }
+            line_start = offset + 1; // the line break
+        }
+        None
+    }
+    /// Return the byte index, in the string of the content (as given
+    /// by `to_string`), of the char at the given position
+    pub fn pos_to_byte(&self, pos: Pos) -> usize {
+        let pos = self.valid_pos(pos);
+        self.lines[..pos.y]
+            .iter()
+            .map(|line| line.chars.iter().map(|c| c.len_utf8()).sum::<usize>() + 1)
+            .sum::<usize>()
+            + self.lines[pos.y].chars[..pos.x].iter().map(|c| c.len_utf8()).sum::<usize>()
+    }
+    /// Remove the chars between two positions (start included, end excluded)
+    /// and put the cursor at start.
+    pub fn del_between(&mut self, start: Pos, end: Pos) {
+        let end = end.min(self.end());
+        if start >= end {
+            return;
+        }
+        let tail = self.lines[end.y].chars.split_off(end.x);
+        let removed: Vec<Line> = self.lines.drain(start.y + 1..=end.y).collect();
+        self.discard_lines(removed);
+        self.lines[start.y].chars.truncate(start.x);
+        self.scrub_spare(start.y);
+        self.append_chars(start.y, tail);
+        self.pos = start;
+    }
+    /// Insert a char at the given position, the cursor staying
+    /// before the same chars.
+    ///
+    /// Return the new position of the cursor.
+    pub fn insert_char_at(&mut self, pos: Pos, c: char) -> Pos {
+        self.replace_range(pos, pos, c.encode_utf8(&mut [0; 4]))
+    }
+    /// Remove the chars between two positions (start included, end
+    /// excluded), the cursor staying before the same chars.
+    ///
+    /// Return the new position of the cursor.
+    pub fn delete_range(&mut self, from: Pos, to: Pos) -> Pos {
+        self.replace_range(from, to, "")
+    }
+    /// Replace the chars between two positions (start included, end
+    /// excluded) with a string, the cursor staying before the same
+    /// chars (or at the start of the range if it was inside).
+    ///
+    /// This doesn't move the cursor as an insertion would, so that
+    /// applications may manage several cursors, adjusting the other
+    /// ones the same way.
+    ///
+    /// Return the new position of the cursor.
+    pub fn replace_range(&mut self, from: Pos, to: Pos, s: &str) -> Pos {
+        let (from, to) = (self.valid_pos(from.min(to)), self.valid_pos(from.max(to)));
+        let s = Self::normalize_newlines(s);
+        let tail = self.lines[to.y].chars.split_off(to.x);
+        let removed: Vec<Line> = self.lines.drain(from.y + 1..=to.y).collect();
+        self.discard_lines(removed);
+        self.lines[from.y].chars.truncate(from.x);
+        self.scrub_spare(from.y);
+        let mut inserted_end = from;
+        for c in s.chars() {
+            if c == '\n' {
+                inserted_end.y += 1;
+                inserted_end.x = 0;
+                self.lines.insert(inserted_end.y, Line::default());
+            } else if c != '\r' {
+                self.reserve(inserted_end.y, 1);
+                self.lines[inserted_end.y].chars.push(c);
+                inserted_end.x += 1;
+            }
+        }
+        self.append_chars(inserted_end.y, tail);
+        let pos = self.pos;
+        self.pos = if pos <= from {
+            pos
+        } else if pos < to {
+            from
+        } else if pos.y == to.y {
+            Pos {
+                x: inserted_end.x + pos.x - to.x,
+                y: inserted_end.y,
+            }
+        } else {
+            Pos {
+                x: pos.x,
+                y: pos.y + inserted_end.y - to.y,
+            }
+        };
+        self.goal = None;
+        self.pos
+    }
+    /// remove the grapheme cluster (usually a char) left of the cursor, if any.
     pub fn del_char_left(&mut self) -> bool {
         if self.pos.x > 0 {
-            self.pos.x -= 1;
-            self.lines[self.pos.y].chars.remove(self.pos.x);
+            let chars = &mut self.lines[self.pos.y].chars;
+            let start = prev_cluster_boundary(chars, self.pos.x);
+            chars.drain(start..self.pos.x);
+            self.pos.x = start;
+            self.scrub_spare(self.pos.y);
             true
         } else if self.pos.y > 0 {
-            let mut removed_line = self.lines.remove(self.pos.y);
+            let removed_line = self.lines.remove(self.pos.y);
             self.pos.y -= 1;
             self.pos.x = self.lines[self.pos.y].chars.len();
-            self.lines[self.pos.y].chars.append(&mut removed_line.chars);
+            self.append_chars(self.pos.y, removed_line.chars);
             true
         } else {
             false
         }
     }
-    /// Remove the char at cursor position, if any.
+    /// Remove the grapheme cluster (usually a char) at cursor position, if any.
     ///
     /// Cursor position is unchanged
     pub fn del_char_below(&mut self) -> bool {
+        self.goal = None;
         let line_len = self.current_line().chars.len();
         if line_len == 0 {
             if self.lines.len() > 1 {
-                self.lines.remove(self.pos.y);
+                let removed_line = self.lines.remove(self.pos.y);
+                self.discard_lines(Some(removed_line));
                 true
             } else {
                 false
             }
         } else if self.pos.x < line_len {
-            self.lines[self.pos.y].chars.remove(self.pos.x);
+            let chars = &mut self.lines[self.pos.y].chars;
+            let end = next_cluster_boundary(chars, self.pos.x);
+            chars.drain(self.pos.x..end);
+            self.scrub_spare(self.pos.y);
             true
         } else {
             false
@@ -248,20 +651,39 @@ impl InputFieldContent {
     /// Move the cursor to the right (or to the line below
     /// if it's a the end of a non-last line)
     pub fn move_right(&mut self) -> bool {
-        if self.pos.x < self.lines[self.pos.y].chars.len() {
-            self.pos.x += 1;
+        let chars = &self.lines[self.pos.y].chars;
+        if self.pos.x < chars.len() {
+            self.pos.x = next_cluster_boundary(chars, self.pos.x);
             true
         } else {
             false
         }
     }
+    /// return the column the cursor should try to reach
+    /// when moving vertically
+    fn goal_x(&self) -> usize {
+        match self.goal {
+            Some((x, pos)) if pos == self.pos => x,
+            _ => self.pos.x,
+        }
+    }
+    /// move the cursor to the line at y, as near as possible
+    /// from the goal column
+    fn move_vertically_to(&mut self, y: usize) {
+        let goal_x = self.goal_x();
+        let chars = &self.lines[y].chars;
+        let mut x = goal_x.min(chars.len());
+        if !is_cluster_boundary(chars, x) {
+            x = prev_cluster_boundary(chars, x);
+        }
+        self.pos = Pos { x, y };
+        self.goal = Some((goal_x, self.pos));
+    }
+    /// Move the cursor up, keeping the column it had before
+    /// the first of consecutive vertical moves, when possible
     pub fn move_lines_up(&mut self, lines: usize) -> bool {
         if self.pos.y > 0 {
-            self.pos.y -= lines.min(self.pos.y);
-            let line_len = self.lines[self.pos.y].chars.len();
-            if self.pos.x > line_len {
-                self.pos.x = line_len;
-            }
+            self.move_vertically_to(self.pos.y - lines.min(self.pos.y));
             true
         } else {
             false
@@ -270,13 +692,11 @@ impl InputFieldContent {
     pub fn move_up(&mut self) -> bool {
         self.move_lines_up(1)
     }
+    /// Move the cursor down, keeping the column it had before
+    /// the first of consecutive vertical moves, when possible
     pub fn move_lines_down(&mut self, lines: usize) -> bool {
         if self.pos.y + 1 < self.lines.len() {
-            self.pos.y += lines.min(self.lines.len() - self.pos.y - 1);
-            let line_len = self.lines[self.pos.y].chars.len();
-            if self.pos.x > line_len {
-                self.pos.x = line_len;
-            }
+            self.move_vertically_to(self.pos.y + lines.min(self.lines.len() - self.pos.y - 1));
             true
         } else {
             false
@@ -287,7 +707,7 @@ impl InputFieldContent {
     }
     pub fn move_left(&mut self) -> bool {
         if self.pos.x > 0 {
-            self.pos.x -= 1;
+            self.pos.x = prev_cluster_boundary(&self.lines[self.pos.y].chars, self.pos.x);
             true
         } else {
             false
@@ -366,6 +786,7 @@ impl InputFieldContent {
                     break;
                 }
             }
+            self.scrub_spare(self.pos.y);
             true
         } else {
             false
@@ -391,6 +812,7 @@ impl InputFieldContent {
                     break;
                 }
             }
+            self.scrub_spare(self.pos.y);
             true
         } else if self.pos.x == self.current_line().chars.len() && self.pos.x > 0 {
             self.pos.x -= 1;
@@ -399,7 +821,78 @@ impl InputFieldContent {
             false
         }
     }
-
+    /// Return the content without its leading and trailing
+    /// whitespaces (line breaks included)
+    pub fn trimmed(&self) -> String {
+        self.to_string().trim().to_string()
+    }
+    /// Remove the leading and trailing whitespaces (line breaks
+    /// included) of the content, the cursor staying before the
+    /// same chars.
+    ///
+    /// Return true when the content changed.
+    pub fn trim(&mut self) -> bool {
+        let mut start = None;
+        let mut stop = Pos::default();
+        for (y, line) in self.lines.iter().enumerate() {
+            for (x, c) in line.chars.iter().enumerate() {
+                if !c.is_whitespace() {
+                    start.get_or_insert(Pos { x, y });
+                    stop = Pos { x: x + 1, y };
+                }
+            }
+        }
+        let start = match start {
+            Some(start) => start,
+            None if self.is_empty() => { return false; }
+            None => {
+                self.clear();
+                return true;
+            }
+        };
+        let end = self.end();
+        if start == Pos::default() && stop == end {
+            return false;
+        }
+        self.replace_range(stop, end, "");
+        self.replace_range(Pos::default(), start, "");
+        true
+    }
+    /// Replace every sequence of several blank lines (empty or made
+    /// of whitespaces) with one empty line, the cursor staying before
+    /// the same chars.
+    ///
+    /// Return true when the content changed.
+    pub fn collapse_blank_lines(&mut self) -> bool {
+        let is_blank = |line: &Line| line.chars.iter().all(|c| c.is_whitespace());
+        let mut changed = false;
+        let mut y = self.lines.len();
+        while y > 0 {
+            y -= 1;
+            if !is_blank(&self.lines[y]) {
+                continue;
+            }
+            let last = y;
+            while y > 0 && is_blank(&self.lines[y - 1]) {
+                y -= 1;
+            }
+            if last > y {
+                let end = Pos { x: self.lines[last].chars.len(), y: last };
+                self.replace_range(Pos { x: 0, y }, end, "");
+                changed = true;
+            }
+        }
+        changed
+    }
+    /// Return the string with its CRLF and lone CR line breaks replaced
+    /// with LF ones, for example for a text pasted from a Windows clipboard
+    pub fn normalize_newlines(s: &str) -> Cow<'_, str> {
+        if s.contains('\r') {
+            Cow::Owned(s.replace("\r\n", "\n").replace('\r', "\n"))
+        } else {
+            Cow::Borrowed(s)
+        }
+    }
 }
 
 #[test]
@@ -525,3 +1018,270 @@ mod input_content_edit_monoline_tests {
     }
 }
 
+
+#[cfg(test)]
+mod input_content_range_edit_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_range_edits() {
+        let mut con = InputFieldContent::from("abc\ndef\nghi");
+        con.set_cursor_pos(Pos { x: 2, y: 1 });
+        // an insertion before the cursor, on its line
+        assert_eq!(con.insert_char_at(Pos { x: 0, y: 1 }, 'X'), Pos { x: 3, y: 1 });
+        assert!(con.is_str("abc\nXdef\nghi"));
+        // an insertion after the cursor
+        assert_eq!(con.insert_char_at(Pos { x: 1, y: 2 }, 'Y'), Pos { x: 3, y: 1 });
+        assert!(con.is_str("abc\nXdef\ngYhi"));
+        // an insertion at the cursor doesn't move it
+        assert_eq!(con.insert_char_at(Pos { x: 3, y: 1 }, 'Z'), Pos { x: 3, y: 1 });
+        assert!(con.is_str("abc\nXdeZf\ngYhi"));
+        // a deletion removing the line break before the cursor's line
+        assert_eq!(con.delete_range(Pos { x: 2, y: 0 }, Pos { x: 1, y: 1 }), Pos { x: 4, y: 0 });
+        assert!(con.is_str("abdeZf\ngYhi"));
+        // a replacement with new lines
+        assert_eq!(
+            con.replace_range(Pos { x: 0, y: 0 }, Pos { x: 1, y: 0 }, "1\n2\n"),
+            Pos { x: 3, y: 2 },
+        );
+        assert!(con.is_str("1\n2\nbdeZf\ngYhi"));
+        // the cursor being in the replaced range
+        assert_eq!(
+            con.replace_range(Pos { x: 5, y: 2 }, Pos { x: 1, y: 2 }, "-"),
+            Pos { x: 1, y: 2 },
+        );
+        assert!(con.is_str("1\n2\nb-\ngYhi"));
+        // positions out of the content are clamped
+        assert_eq!(con.insert_char_at(Pos { x: 10, y: 10 }, '!'), Pos { x: 1, y: 2 });
+        assert!(con.is_str("1\n2\nb-\ngYhi!"));
+    }
+}
+
+#[cfg(test)]
+mod input_content_trim_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_trim() {
+        let mut con = InputFieldContent::from("\n  ab c \n d\t\n\n");
+        assert_eq!(con.trimmed(), "ab c \n d");
+        con.set_cursor_pos(Pos { x: 3, y: 1 });
+        assert!(con.trim());
+        assert!(con.is_str("ab c \n d"));
+        assert_eq!(con.cursor_pos(), Pos { x: 1, y: 0 });
+        assert!(!con.trim());
+        let mut con = InputFieldContent::from(" \n\t");
+        assert!(con.trim());
+        assert!(con.is_empty());
+        assert!(!con.trim());
+    }
+
+    #[test]
+    fn test_collapse_blank_lines() {
+        let mut con = InputFieldContent::from("a\n\n  \n\nb\n\nc\n\n");
+        assert!(con.collapse_blank_lines());
+        assert!(con.is_str("a\n\nb\n\nc\n"));
+        assert_eq!(con.cursor_pos(), Pos { x: 0, y: 5 });
+        assert!(!con.collapse_blank_lines());
+    }
+
+    #[test]
+    fn test_normalize_newlines() {
+        assert_eq!(InputFieldContent::normalize_newlines("a\r\nb\nc"), "a\nb\nc");
+        assert_eq!(InputFieldContent::normalize_newlines("a\r\rb"), "a\n\nb");
+        assert!(matches!(InputFieldContent::normalize_newlines("a\nb"), Cow::Borrowed(_)));
+    }
+}
+
+#[cfg(test)]
+mod input_content_line_ending_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_line_endings() {
+        let mut con = InputFieldContent::default();
+        con.insert_str("a\r\nb\rc");
+        assert_eq!(con.line_count(), 3);
+        assert!(con.is_str("a\nb\nc"));
+        assert_eq!(con.cursor_pos(), Pos { x: 1, y: 2 });
+        con.set_str("x\r\ry");
+        assert!(con.is_str("x\n\ny"));
+        con.replace_range(Pos { x: 0, y: 1 }, Pos { x: 0, y: 2 }, "\r\n-\r");
+        assert!(con.is_str("x\n\n-\ny"));
+    }
+}
+
+#[cfg(all(test, feature = "secure"))]
+mod input_content_secure_tests {
+
+    use super::*;
+
+    /// number of chars scrubbed in the thread while running `f`
+    fn scrubbed_by<F: FnOnce()>(f: F) -> usize {
+        let before = SCRUBBED.with(|n| n.get());
+        f();
+        SCRUBBED.with(|n| n.get()) - before
+    }
+
+    #[test]
+    fn test_secure_edits() {
+        let mut secure = InputFieldContent::default();
+        secure.set_secure(true);
+        assert!(secure.is_secure());
+        let mut normal = InputFieldContent::default();
+        type Edit = fn(&mut InputFieldContent);
+        let edits: Vec<(Edit, bool)> = vec![
+            (|con| { con.insert_str("my secret\npass phrase"); }, false),
+            (|con| { con.del_word_left(); }, true),
+            (|con| { con.del_char_left(); }, true),
+            (|con| { con.insert_char('P'); }, false),
+            (|con| { con.move_to_start(); }, false),
+            (|con| { con.del_char_below(); }, true),
+            (|con| { con.del_between(Pos { x: 1, y: 0 }, Pos { x: 3, y: 0 }); }, true),
+            (|con| { con.set_cursor_pos(Pos { x: 0, y: 1 }); }, false),
+            (|con| { con.del_char_left(); }, true), // joins the lines
+            (|con| { con.replace_range(Pos { x: 2, y: 0 }, Pos { x: 4, y: 0 }, "+\n-"); }, true),
+            (|con| { con.insert_new_line(); }, false),
+            (|con| { con.push_char('!'); }, false),
+        ];
+        for (edit, removes) in edits {
+            // the buffers where chars were removed are scrubbed
+            let scrubbed = scrubbed_by(|| edit(&mut secure));
+            assert!(scrubbed > 0 || !removes);
+            assert_eq!(scrubbed_by(|| edit(&mut normal)), 0);
+        }
+        assert_eq!(secure, normal);
+        assert!(secure.is_str("ye+\n-et\npassP!"));
+        assert!(scrubbed_by(|| secure.clear()) > 0);
+        secure.insert_str("other secret");
+        assert!(scrubbed_by(|| drop(secure)) >= "other secret".len());
+        assert_eq!(scrubbed_by(|| drop(normal)), 0);
+    }
+}
+
+#[cfg(test)]
+mod input_content_access_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_lines_between() {
+        let con = InputFieldContent::from("abc\ndéf\n\nghi");
+        let lines: Vec<(usize, Range<usize>)> = con
+            .lines_between(Pos { x: 1, y: 0 }, Pos { x: 2, y: 3 })
+            .collect();
+        assert_eq!(lines, vec![(0, 1..3), (1, 0..3), (2, 0..0), (3, 0..2)]);
+        let lines: Vec<(usize, Range<usize>)> = con
+            .lines_between(Pos { x: 1, y: 1 }, Pos { x: 2, y: 1 })
+            .collect();
+        assert_eq!(lines, vec![(1, 1..2)]);
+        assert_eq!(con.lines_between(Pos { x: 2, y: 1 }, Pos { x: 1, y: 1 }).count(), 0);
+    }
+
+    #[test]
+    fn test_char_at() {
+        let con = InputFieldContent::from("ab\ncd");
+        assert_eq!(con.char_at(Pos { x: 1, y: 0 }), Some('b'));
+        assert_eq!(con.char_at(Pos { x: 2, y: 0 }), Some('\n'));
+        assert_eq!(con.char_at(Pos { x: 0, y: 1 }), Some('c'));
+        assert_eq!(con.char_at(Pos { x: 2, y: 1 }), None);
+        assert_eq!(con.char_at(Pos { x: 0, y: 2 }), None);
+    }
+
+    #[test]
+    fn test_byte_pos_conversions() {
+        let con = InputFieldContent::from("aé\n\n😀b");
+        let s = con.to_string();
+        for (byte, _) in s.char_indices().chain(std::iter::once((s.len(), ' '))) {
+            let pos = con.byte_to_pos(byte).unwrap();
+            assert_eq!(con.pos_to_byte(pos), byte);
+        }
+        assert_eq!(con.byte_to_pos(3), Some(Pos { x: 2, y: 0 }));
+        assert_eq!(con.byte_to_pos(4), Some(Pos { x: 0, y: 1 }));
+        assert_eq!(con.byte_to_pos(5), Some(Pos { x: 0, y: 2 }));
+        assert_eq!(con.byte_to_pos(9), Some(Pos { x: 1, y: 2 }));
+        assert_eq!(con.byte_to_pos(2), None); // inside 'é'
+        assert_eq!(con.byte_to_pos(11), None);
+    }
+}
+
+#[cfg(test)]
+mod input_content_move_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_goal_column() {
+        let mut con = InputFieldContent::from("a long line\nab\n\nanother line");
+        con.set_cursor_pos(Pos { x: 7, y: 0 });
+        con.move_down();
+        assert_eq!(con.cursor_pos(), Pos { x: 2, y: 1 });
+        con.move_down();
+        assert_eq!(con.cursor_pos(), Pos { x: 0, y: 2 });
+        con.move_down();
+        assert_eq!(con.cursor_pos(), Pos { x: 7, y: 3 });
+        con.move_lines_up(3);
+        assert_eq!(con.cursor_pos(), Pos { x: 7, y: 0 });
+        // an horizontal move resets the goal
+        con.move_down();
+        con.move_left();
+        con.move_down();
+        con.move_down();
+        assert_eq!(con.cursor_pos(), Pos { x: 1, y: 3 });
+        // as does an edit
+        con.move_to_start();
+        con.move_to_line_end();
+        con.move_down();
+        con.insert_char('c');
+        con.move_up();
+        assert_eq!(con.cursor_pos(), Pos { x: 3, y: 0 });
+    }
+}
+
+#[cfg(test)]
+mod input_content_cluster_tests {
+
+    use super::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    #[test]
+    fn test_cluster_boundaries() {
+        // e + combining acute accent, then a thumb with a skin tone
+        let line = chars("e\u{301}👍\u{1F3FD}!");
+        let boundaries: Vec<usize> = (0..=line.len())
+            .filter(|&idx| is_cluster_boundary(&line, idx))
+            .collect();
+        assert_eq!(boundaries, vec![0, 2, 4, 5]);
+        // a ZWJ sequence (family) and two flags
+        let line = chars("👨\u{200D}👩\u{200D}👧🇫🇷🇯🇵");
+        let boundaries: Vec<usize> = (0..=line.len())
+            .filter(|&idx| is_cluster_boundary(&line, idx))
+            .collect();
+        assert_eq!(boundaries, vec![0, 5, 7, 9]);
+    }
+
+    #[test]
+    fn test_cluster_editing() {
+        let mut con = InputFieldContent::from("ae\u{301}👍\u{1F3FD}");
+        assert!(con.del_char_left());
+        assert!(con.is_str("ae\u{301}"));
+        assert!(con.move_left());
+        assert_eq!(con.cursor_pos(), Pos { x: 1, y: 0 });
+        assert!(con.move_right());
+        assert_eq!(con.cursor_pos(), Pos { x: 3, y: 0 });
+        con.move_to_start();
+        assert!(con.move_right());
+        assert!(con.del_char_below());
+        assert!(con.is_str("a"));
+        // vertical moves don't land inside a cluster
+        let mut con = InputFieldContent::from("e\u{301}e\u{301}\nabc");
+        assert!(con.move_up());
+        assert_eq!(con.cursor_pos(), Pos { x: 2, y: 0 });
+    }
+}
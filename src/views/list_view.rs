@@ -37,13 +37,16 @@ struct Row<T> {
     displayed: bool,
 }
 
+type RowFilter<T> = Box<dyn Fn(&T) -> bool>;
+type RowOrder<T> = Box<dyn Fn(&T, &T) -> Ordering>;
+
 /// A filterable list whose columns can be automatically resized.
 ///
 ///
 /// Notes:
 /// * another version will allow more than one style per cell
-/// (i.e. make the cells composites rather than compounds). Shout
-/// out if you need that now.
+///   (i.e. make the cells composites rather than compounds). Shout
+///   out if you need that now.
 /// * this version doesn't allow cell wrapping
 pub struct ListView<'t, T> {
     titles: Vec<Title>,
@@ -52,9 +55,9 @@ pub struct ListView<'t, T> {
     pub area: Area,
     scroll: usize,
     pub skin: &'t MadSkin,
-    filter: Option<Box<dyn Fn(&T) -> bool>>, // a function determining if the row must be displayed
+    filter: Option<RowFilter<T>>, // a function determining if the row must be displayed
     displayed_rows_count: usize,
-    row_order: Option<Box<dyn Fn(&T, &T) -> Ordering>>,
+    row_order: Option<RowOrder<T>>,
     selection: Option<usize>, // index of the selected line
     selection_background: Color,
 }
@@ -126,7 +129,7 @@ impl<'t, T> ListView<'t, T> {
         }
     }
     /// set a comparator for row sorting
-    pub fn sort(&mut self, sort: Box<dyn Fn(&T, &T) -> Ordering>) {
+    pub fn sort(&mut self, sort: RowOrder<T>) {
         self.row_order = Some(sort);
     }
     /// return the height which is available for rows
@@ -273,7 +276,7 @@ impl<'t, T> ListView<'t, T> {
             }
         }
         // rows, maybe scrolled
-        let mut row_idx = self.scroll as usize;
+        let mut row_idx = self.scroll;
         let scrollbar = self.scrollbar();
         for y in 2..self.area.height {
             queue!(w, MoveTo(self.area.left, self.area.top + y))?;
@@ -333,22 +336,14 @@ impl<'t, T> ListView<'t, T> {
     /// ensure the last line is visible
     pub fn scroll_to_bottom(&mut self) {
         let body_height = self.tbody_height() as usize;
-        self.scroll = if self.displayed_rows_count > body_height {
-            self.displayed_rows_count - body_height
-        } else {
-            0
-        }
+        self.scroll = self.displayed_rows_count.saturating_sub(body_height);
     }
     /// set the scroll amount.
     /// lines_count can be negative
     pub fn try_scroll_lines(&mut self, lines_count: i32) {
         if lines_count < 0 {
             let lines_count = -lines_count as usize;
-                self.scroll = if lines_count >= self.scroll {
-                0
-            } else {
-                self.scroll - lines_count
-            };
+            self.scroll = self.scroll.saturating_sub(lines_count);
         } else {
             self.scroll = (self.scroll + lines_count as usize)
                 .min(self.displayed_rows_count - self.tbody_height() as usize + 1);
@@ -367,7 +362,7 @@ impl<'t, T> ListView<'t, T> {
         }
         if self.displayed_rows_count == 1 || self.selection.is_none() {
             for i in 0..self.rows.len() {
-                let i = (i + self.scroll as usize) % self.rows.len();
+                let i = (i + self.scroll) % self.rows.len();
                 if self.rows[i].displayed {
                     self.selection = Some(i);
                     self.make_selection_visible();
@@ -418,7 +413,7 @@ impl<'t, T> ListView<'t, T> {
         }
         if let Some(sel) = self.selection {
             if sel <= self.scroll {
-                self.scroll = if sel > 2 { sel - 2 } else { 0 };
+                self.scroll = sel.saturating_sub(2);
             } else if sel + 1 >= self.scroll + tbody_height {
                 self.scroll = sel - tbody_height + 2;
             }
@@ -1,23 +1,141 @@
 use {
     crate::{
-        area::Area,
+        area::{compute_scrollbar, Area},
+        compound_style::CompoundStyle,
+        displayable_line::{DisplayableLine, OverlaidLine},
         errors::Result,
-        skin::MadSkin,
-        views::TextView,
+        line::FmtLine,
+        link::Link,
+        render_options,
+        skin::{MadSkin, Overlay},
+        source_map,
+        text::FmtText,
+        SPACE_FILLING,
     },
-    crossterm::event::KeyEvent,
+    crossterm::{
+        cursor::MoveTo,
+        style::Attribute,
+        event::{
+            KeyCode,
+            KeyEvent,
+            KeyModifiers,
+        },
+        style::Print,
+        QueueableCommand,
+    },
+    minimad::once_cell::sync::OnceCell,
     std::io::Write,
+    unicode_width::UnicodeWidthChar,
 };
 
+/// The lines of the text, wrapped and rendered for a width
+struct RenderedLines {
+    area_width: u16,
+    with_scrollbar: bool,
+    /// the width of the widest line, when tables are wide
+    content_width: usize,
+    lines: Vec<String>,
+    links: Vec<Link>,
+    /// the index of the first line of every header
+    headings: Vec<usize>,
+    /// the link whose lines are in `highlighted`
+    highlighted_link: Option<usize>,
+    /// the lines of the selected link, rendered with the
+    /// selection style, with their index
+    highlighted: Vec<(usize, String)>,
+}
+
+/// Return the part of a rendered line covering `width` columns
+/// from the `start` one, with the number of columns it covers.
+///
+/// Escape sequences are all kept so that the styles are right. A
+/// wide char cut by an edge is replaced with spaces.
+fn slice_columns(line: &str, start: usize, width: usize) -> (String, usize) {
+    let end = start + width;
+    let mut slice = String::new();
+    let mut col = 0;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            slice.push(c);
+            match chars.next() {
+                Some('[') => {
+                    // CSI: everything until a final byte
+                    slice.push('[');
+                    for c in chars.by_ref() {
+                        slice.push(c);
+                        if ('@'..='~').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                Some(']') => {
+                    // OSC (for example a hyperlink): until BEL or ST
+                    slice.push(']');
+                    while let Some(c) = chars.next() {
+                        slice.push(c);
+                        if c == '\u{7}' {
+                            break;
+                        }
+                        if c == '\u{1b}' && chars.peek() == Some(&'\\') {
+                            slice.push('\\');
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                Some(c) => slice.push(c),
+                None => {}
+            }
+            continue;
+        }
+        let char_width = c.width().unwrap_or(0);
+        if col >= start && col + char_width <= end {
+            slice.push(c);
+        } else {
+            // the cells of the char which are in the slice
+            for _ in col.max(start)..(col + char_width).min(end) {
+                slice.push(' ');
+            }
+        }
+        col += char_width;
+    }
+    (slice, col.saturating_sub(start).min(width))
+}
+
 /// A MadView is like a textview but it owns everything, from the
 ///  source markdown to the area and the skin, which often makes it more convenient
 ///  for dynamic texts.
 /// It's also resizeable.
+///
+/// The wrapped lines are kept between renderings and computed again
+/// only when the width changes. If you modify the skin, call
+/// `invalidate` so that it's taken into account.
+///
+/// With `set_wide_tables`, tables keep their natural width and the
+/// view scrolls horizontally when they're wider than the area.
+///
+/// Links can be selected in turn with `select_next_link` and
+/// `select_prev_link`, for example on Tab, the selected one being
+/// highlighted (in reverse video unless `set_selected_link_style`
+/// is called).
+///
+/// The view can be scrolled to a header, for example chosen in the
+/// table of contents, with `scroll_to_heading` or `scroll_to_anchor`.
 pub struct MadView {
     markdown: String,
     area: Area,
     pub skin: MadSkin,
     pub scroll: usize,
+    /// number of columns hidden at left
+    pub h_scroll: usize,
+    /// whether tables aren't fitted to the width
+    wide_tables: bool,
+    selected_link: Option<usize>,
+    selected_url: Option<String>,
+    /// the style applied over the selected link (reversed if none)
+    selected_link_style: Option<CompoundStyle>,
+    rendered: OnceCell<RenderedLines>,
 }
 
 impl MadView {
@@ -28,18 +146,265 @@ impl MadView {
             area,
             skin,
             scroll: 0,
+            h_scroll: 0,
+            wide_tables: false,
+            selected_link: None,
+            selected_url: None,
+            selected_link_style: None,
+            rendered: OnceCell::new(),
+        }
+    }
+    /// Set whether tables keep their natural width instead of being
+    /// fitted to the area (by wrapping or truncating their cells and
+    /// removing columns when there's really not enough room).
+    ///
+    /// When a table is wider than the area, a horizontal scrollbar
+    /// is displayed on the last row and the whole text can be
+    /// scrolled horizontally.
+    pub fn set_wide_tables(&mut self, b: bool) {
+        if b != self.wide_tables {
+            self.wide_tables = b;
+            self.h_scroll = 0;
+            self.invalidate();
+        }
+    }
+    /// set the style applied over the normal style of the selected link
+    pub fn set_selected_link_style(&mut self, style: CompoundStyle) {
+        self.selected_link_style = Some(style);
+        self.invalidate();
+    }
+    /// forget the wrapped lines, so that they're computed again
+    /// at next rendering (needed after a change of the skin)
+    pub fn invalidate(&mut self) {
+        self.rendered.take();
+    }
+    /// tell whether there are a vertical and a horizontal scrollbar
+    /// for a content of the given width and height
+    fn scrollbars(&self, content_width: usize, content_height: usize) -> (bool, bool) {
+        let width = self.area.width as usize;
+        let height = self.area.height as usize;
+        let vertical = |horizontal: bool| content_height > height.saturating_sub(usize::from(horizontal));
+        let mut horizontal = content_width > width;
+        let mut with_scrollbar = vertical(horizontal);
+        if !horizontal && content_width > width - usize::from(with_scrollbar) {
+            horizontal = true;
+            with_scrollbar = vertical(horizontal);
+        }
+        (with_scrollbar, horizontal)
+    }
+    /// build the text for the area
+    fn fmt_text(&self) -> FmtText<'_, '_> {
+        if self.wide_tables {
+            FmtText::from_text_with_table_width(
+                &self.skin,
+                render_options::parse_text(&self.markdown, &self.skin.render_options),
+                Some(self.area.width as usize - 1),
+                usize::MAX,
+            )
+        } else {
+            self.skin.area_text(&self.markdown, &self.area)
+        }
+    }
+    /// the lines rendered for the current area, computed if needed
+    fn rendered_lines(&self) -> &RenderedLines {
+        self.rendered.get_or_init(|| self.render_lines())
+    }
+    /// make sure the rendered lines, if any, are still up to date
+    /// after a change of the area or of the selected link
+    fn update_rendered_lines(&mut self) {
+        let rendered = match self.rendered.get() {
+            Some(rendered) => rendered,
+            None => { return; }
+        };
+        let (with_scrollbar, _) = self.scrollbars(rendered.content_width, rendered.lines.len());
+        if rendered.area_width != self.area.width || rendered.with_scrollbar != with_scrollbar {
+            self.invalidate();
+            return;
+        }
+        if rendered.highlighted_link != self.selected_link {
+            let text = self.fmt_text();
+            let width = self.area.width as usize - usize::from(with_scrollbar);
+            let highlighted = self.highlighted_lines(&text, &rendered.links, width);
+            if let Some(rendered) = self.rendered.get_mut() {
+                rendered.highlighted = highlighted;
+                rendered.highlighted_link = self.selected_link;
+            }
         }
     }
+    /// wrap and render the lines for the area
+    fn render_lines(&self) -> RenderedLines {
+        let text = self.fmt_text();
+        let content_width = text.lines
+            .iter()
+            .filter(|line| matches!(line, FmtLine::TableRow(_) | FmtLine::TableRule(_)))
+            .map(FmtLine::visible_length)
+            .max()
+            .unwrap_or(0);
+        let (with_scrollbar, _) = self.scrollbars(content_width, text.lines.len());
+        let width = self.area.width as usize - usize::from(with_scrollbar);
+        let lines = text.lines
+            .iter()
+            .map(|line| DisplayableLine::new(&self.skin, line, Some(width)).to_string())
+            .collect();
+        let links = text.links_for_width(Some(width));
+        let headings = text.heading_lines();
+        let highlighted = self.highlighted_lines(&text, &links, width);
+        RenderedLines {
+            area_width: self.area.width,
+            with_scrollbar,
+            content_width,
+            lines,
+            links,
+            headings,
+            highlighted_link: self.selected_link,
+            highlighted,
+        }
+    }
+    /// render the lines of the selected link with its cells
+    /// highlighted
+    fn highlighted_lines(&self, text: &FmtText<'_, '_>, links: &[Link], width: usize) -> Vec<(usize, String)> {
+        let link = match self.selected_link.and_then(|idx| links.get(idx)) {
+            Some(link) => link,
+            None => { return Vec::new(); }
+        };
+        let reversed;
+        let style = match &self.selected_link_style {
+            Some(style) => style,
+            None => {
+                reversed = CompoundStyle::with_attr(Attribute::Reverse);
+                &reversed
+            }
+        };
+        link.spans
+            .iter()
+            .filter_map(|(line_idx, cols)| {
+                let line = text.lines.get(*line_idx)?;
+                let ranges = source_map::char_ranges_in_columns(&self.skin, line, Some(width), cols);
+                let ol = OverlaidLine {
+                    skin: &self.skin,
+                    line,
+                    width: Some(width),
+                    overlay: Overlay {
+                        style,
+                        ranges: &ranges,
+                    },
+                };
+                Some((*line_idx, ol.to_string()))
+            })
+            .collect()
+    }
+    fn content_height(&self) -> usize {
+        self.rendered_lines().lines.len()
+    }
+    /// return the width of the content, the widest table included
+    fn content_width(&self) -> usize {
+        self.rendered_lines().content_width
+    }
+    /// return the number of rows and columns the text can take
+    /// (not counting the scrollbars)
+    fn text_size(&self) -> (usize, usize) {
+        let (with_scrollbar, horizontal) = self.scrollbars(self.content_width(), self.content_height());
+        (
+            self.area.width as usize - usize::from(with_scrollbar),
+            (self.area.height as usize).saturating_sub(usize::from(horizontal)),
+        )
+    }
+    /// set the scroll position but makes it fit into allowed positions.
+    fn set_scroll(&mut self, scroll: usize) {
+        let (_, text_height) = self.text_size();
+        let max_scroll = self.content_height().saturating_sub(text_height);
+        self.scroll = scroll.min(max_scroll);
+    }
+    /// set the horizontal scroll position, that is the number of
+    /// columns hidden at left, but makes it fit into allowed positions.
+    ///
+    /// Return true if the scroll changed.
+    pub fn set_h_scroll(&mut self, h_scroll: usize) -> bool {
+        let (text_width, _) = self.text_size();
+        let max_scroll = self.content_width().saturating_sub(text_width);
+        let h_scroll = h_scroll.min(max_scroll);
+        if h_scroll == self.h_scroll {
+            false
+        } else {
+            self.h_scroll = h_scroll;
+            true
+        }
+    }
+    /// scroll the content to the right, that is show the columns
+    /// hidden at right.
+    ///
+    /// Return true if the scroll changed.
+    pub fn scroll_right(&mut self, cols: usize) -> bool {
+        self.set_h_scroll(self.h_scroll + cols)
+    }
+    /// scroll the content to the left, that is show the columns
+    /// hidden at left.
+    ///
+    /// Return true if the scroll changed.
+    pub fn scroll_left(&mut self, cols: usize) -> bool {
+        self.set_h_scroll(self.h_scroll.saturating_sub(cols))
+    }
     /// render the markdown in the area, taking the scroll into
     /// account
     pub fn write(&self) -> Result<()> {
         self.write_on(&mut std::io::stdout())
     }
     pub fn write_on<W: Write>(&self, w: &mut W) -> Result<()> {
-        let text = self.skin.area_text(&self.markdown, &self.area);
-        let mut text_view = TextView::from(&self.area, &text);
-        text_view.scroll = self.scroll;
-        text_view.write_on(w)?;
+        let rendered = self.rendered_lines();
+        let (with_scrollbar, horizontal) = self.scrollbars(rendered.content_width, rendered.lines.len());
+        let width = self.area.width as usize - usize::from(with_scrollbar);
+        let height = self.area.height.saturating_sub(u16::from(horizontal));
+        let scrollbar = compute_scrollbar(self.scroll, rendered.lines.len(), height, self.area.top)
+            .filter(|_| with_scrollbar);
+        let scrollbar_style = &self.skin.scrollbar;
+        let left_scrollbar = scrollbar.filter(|_| scrollbar_style.is_left());
+        let mut lines = rendered.lines.iter().enumerate().skip(self.scroll).map(|(idx, line)| {
+            rendered.highlighted
+                .iter()
+                .find(|(highlighted_idx, _)| *highlighted_idx == idx)
+                .map_or(line, |(_, highlighted)| highlighted)
+        });
+        for j in 0..height {
+            let y = self.area.top + j;
+            w.queue(MoveTo(self.area.left, y))?;
+            if let Some(thumb) = left_scrollbar {
+                scrollbar_style.queue_row(w, y, thumb)?;
+            }
+            match lines.next() {
+                Some(line) if horizontal => {
+                    let (slice, slice_width) = slice_columns(line, self.h_scroll, width);
+                    w.queue(Print(slice))?;
+                    if slice_width < width {
+                        let filling = width - slice_width;
+                        SPACE_FILLING.queue_styled(w, &self.skin.paragraph.compound_style, filling)?;
+                    }
+                }
+                Some(line) => {
+                    w.queue(Print(line))?;
+                }
+                None => {
+                    SPACE_FILLING.queue_styled(w, &self.skin.paragraph.compound_style, width)?;
+                }
+            }
+            if let Some(thumb) = scrollbar.filter(|_| left_scrollbar.is_none()) {
+                scrollbar_style.queue_row(w, y, thumb)?;
+            }
+        }
+        if horizontal {
+            // the horizontal scrollbar, with the same chars than the vertical one
+            w.queue(MoveTo(self.area.left, self.area.top + height))?;
+            if left_scrollbar.is_some() {
+                SPACE_FILLING.queue_styled(w, &self.skin.paragraph.compound_style, 1)?;
+            }
+            if let Some(thumb) = compute_scrollbar(self.h_scroll, rendered.content_width, width as u16, 0) {
+                for x in 0..width as u16 {
+                    scrollbar_style.queue_row(w, x, thumb)?;
+                }
+            }
+            if with_scrollbar && left_scrollbar.is_none() {
+                SPACE_FILLING.queue_styled(w, &self.skin.paragraph.compound_style, 1)?;
+            }
+        }
         Ok(())
     }
     /// sets the new area. If it's the same as the precedent one,
@@ -50,27 +415,145 @@ impl MadView {
         }
         if area.width != self.area.width {
             self.scroll = 0; //TODO improve
+            self.h_scroll = 0;
         }
         self.area.left = area.left;
         self.area.top = area.top;
         self.area.height = area.height;
         self.area.width = area.width;
+        self.update_rendered_lines();
     }
     /// set the scroll amount.
     /// lines_count can be negative
     pub fn try_scroll_lines(&mut self, lines_count: i32) {
-        let text = self.skin.area_text(&self.markdown, &self.area);
-        let mut text_view = TextView::from(&self.area, &text);
-        text_view.scroll = self.scroll;
-        text_view.try_scroll_lines(lines_count);
-        self.scroll = text_view.scroll;
+        if lines_count < 0 {
+            let lines_count = -lines_count as usize;
+            self.scroll = self.scroll.saturating_sub(lines_count);
+        } else {
+            self.set_scroll(self.scroll + lines_count as usize);
+        }
     }
     /// set the scroll amount.
     /// lines_count can be negative
     pub fn try_scroll_pages(&mut self, pages_count: i32) {
         self.try_scroll_lines(pages_count * i32::from(self.area.height));
     }
-    /// Apply an event being a key: page_up, page_down, up and down.
+    /// Return the approximate byte offset, in the markdown, of what's
+    /// displayed at the given screen position.
+    ///
+    /// This can be used to find what was clicked, for example to
+    /// follow a reference.
+    ///
+    /// Return None when the position isn't on a line with some
+    /// content of the markdown (for example on a rule or a scrollbar)
+    pub fn source_offset_at(&self, x: u16, y: u16) -> Option<usize> {
+        let (with_scrollbar, _) = self.scrollbars(self.content_width(), self.content_height());
+        let (text_width, text_height) = self.text_size();
+        let area = &self.area;
+        let text_left = area.left + u16::from(with_scrollbar && self.skin.scrollbar.is_left());
+        if y < area.top || y >= area.top + text_height as u16 {
+            return None;
+        }
+        if x < text_left || x >= text_left + text_width as u16 {
+            return None;
+        }
+        let text = self.fmt_text();
+        let line = text.lines.get(self.scroll + (y - area.top) as usize)?;
+        let col = self.h_scroll + (x - text_left) as usize;
+        source_map::source_offset_at(&self.skin, line, Some(text_width), &self.markdown, col)
+    }
+    /// select the link following the selected one (or the first one),
+    /// scrolling to make it visible.
+    ///
+    /// Return false when there's no link in the text.
+    pub fn select_next_link(&mut self) -> bool {
+        let count = self.links_count();
+        if count == 0 {
+            return false;
+        }
+        self.select_link(self.selected_link.map_or(0, |idx| (idx + 1) % count));
+        true
+    }
+    /// select the link preceding the selected one (or the last one),
+    /// scrolling to make it visible.
+    ///
+    /// Return false when there's no link in the text.
+    pub fn select_prev_link(&mut self) -> bool {
+        let count = self.links_count();
+        if count == 0 {
+            return false;
+        }
+        self.select_link(self.selected_link.map_or(count - 1, |idx| (idx + count - 1) % count));
+        true
+    }
+    /// remove the link selection
+    pub fn unselect_link(&mut self) {
+        self.selected_link = None;
+        self.selected_url = None;
+        self.update_rendered_lines();
+    }
+    /// return the url of the selected link, if any
+    pub fn selected_link_url(&self) -> Option<&str> {
+        self.selected_url.as_deref()
+    }
+    fn links_count(&self) -> usize {
+        self.rendered_lines().links.len()
+    }
+    fn select_link(&mut self, idx: usize) {
+        let (url, spans) = match self.rendered_lines().links.get(idx) {
+            Some(link) => (link.url.clone(), link.spans.clone()),
+            None => { return; }
+        };
+        self.selected_url = Some(url);
+        self.selected_link = Some(idx);
+        self.update_rendered_lines();
+        let (first, last) = match (spans.first(), spans.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => { return; }
+        };
+        let (text_width, text_height) = self.text_size();
+        if first.0 < self.scroll {
+            self.set_scroll(first.0);
+        } else if last.0 >= self.scroll + text_height {
+            self.set_scroll((last.0 + 1).saturating_sub(text_height).min(first.0));
+        }
+        let cols = &first.1;
+        if cols.start < self.h_scroll || cols.end > self.h_scroll + text_width {
+            self.set_h_scroll(cols.start);
+        }
+    }
+    /// scroll so that the header of the given index (counted from 0,
+    /// in the order of the table of contents) is at the top, or as
+    /// near as possible when it's near the end of the text.
+    ///
+    /// Return false when there's no such header.
+    pub fn scroll_to_heading(&mut self, index: usize) -> bool {
+        let line_idx = self.rendered_lines().headings.get(index).copied();
+        match line_idx {
+            Some(line_idx) => {
+                self.set_scroll(line_idx);
+                true
+            }
+            None => false,
+        }
+    }
+    /// scroll so that the header with the given anchor (as in the
+    /// table of contents, with or without the leading `#`) is at the top.
+    ///
+    /// Return false when there's no such header.
+    pub fn scroll_to_anchor(&mut self, slug: &str) -> bool {
+        let slug = slug.strip_prefix('#').unwrap_or(slug);
+        let index = self.skin
+            .table_of_contents(&self.markdown)
+            .iter()
+            .position(|entry| entry.anchor == slug);
+        match index {
+            Some(index) => self.scroll_to_heading(index),
+            None => false,
+        }
+    }
+    /// Apply an event being a key: page_up, page_down, up and down,
+    /// and left and right for the horizontal scroll.
     ///
     /// Return true when the event led to a change, false when it
     /// was discarded.
@@ -78,14 +561,218 @@ impl MadView {
     /// It's possible to handle the key yourself and call the try_scroll
     /// methods.
     pub fn apply_key_event(&mut self, key: KeyEvent) -> bool {
-        let text = self.skin.area_text(&self.markdown, &self.area);
-        let mut text_view = TextView::from(&self.area, &text);
-        text_view.scroll = self.scroll;
-        if text_view.apply_key_event(key) {
-            self.scroll = text_view.scroll;
-            true
+        if key.modifiers != KeyModifiers::NONE {
+            return false;
+        }
+        let page_height = self.text_size().1 as i32;
+        let lines_count = match key.code {
+            KeyCode::Left => { return self.scroll_left(1); }
+            KeyCode::Right => { return self.scroll_right(1); }
+            KeyCode::Up => -1,
+            KeyCode::Down => 1,
+            KeyCode::PageUp => -page_height,
+            KeyCode::PageDown => page_height,
+            _ => { return false; }
+        };
+        let scroll = self.scroll;
+        self.try_scroll_lines(lines_count);
+        self.scroll != scroll
+    }
+    /// Apply a click at the given screen position, scrolling when
+    /// it's on a scrollbar.
+    ///
+    /// Return true when the click was on a scrollbar.
+    pub fn apply_click_event(&mut self, x: u16, y: u16) -> bool {
+        let content_height = self.content_height();
+        let content_width = self.content_width();
+        let (with_scrollbar, horizontal) = self.scrollbars(content_width, content_height);
+        let mut area = self.area.clone();
+        area.height = area.height.saturating_sub(u16::from(horizontal));
+        if horizontal && y == area.top + area.height {
+            let (text_width, _) = self.text_size();
+            let text_left = area.left + u16::from(with_scrollbar && self.skin.scrollbar.is_left());
+            if x < text_left || x >= text_left + text_width as u16 {
+                return false;
+            }
+            let col = (x - text_left) as usize;
+            self.set_h_scroll(col * content_width / text_width);
+            return true;
+        }
+        if !with_scrollbar {
+            return false;
+        }
+        let area = &area;
+        let scrollbar_x = if self.skin.scrollbar.is_left() {
+            area.left
         } else {
-            false
+            area.left + area.width - 1
+        };
+        if x != scrollbar_x || y < area.top || y >= area.top + area.height {
+            return false;
         }
+        let row = (y - area.top) as usize;
+        self.set_scroll(row * content_height / area.height as usize);
+        true
+    }
+}
+
+#[cfg(test)]
+mod mad_view_tests {
+
+    use crate::*;
+
+    /// render the text the way a TextView would
+    fn text_view_rendering(md: &str, area: &Area, skin: &MadSkin, scroll: usize) -> Vec<u8> {
+        let text = skin.area_text(md, area);
+        let mut text_view = TextView::from(area, &text);
+        text_view.scroll = scroll;
+        let mut w = Vec::new();
+        text_view.write_on(&mut w).unwrap();
+        w
+    }
+
+    fn mad_view_rendering(view: &MadView) -> Vec<u8> {
+        let mut w = Vec::new();
+        view.write_on(&mut w).unwrap();
+        w
+    }
+
+    #[test]
+    fn test_rendered_lines_cache() {
+        let md = "# Title\n\nSome *text* long enough to be wrapped a few times\n\n* a\n* b\n* c\n> quote";
+        let skin = MadSkin::default();
+        let area = Area::new(0, 0, 20, 5);
+        let mut view = MadView::from(md.to_string(), area.clone(), skin.clone());
+        assert_eq!(mad_view_rendering(&view), text_view_rendering(md, &area, &skin, 0));
+        view.try_scroll_lines(2);
+        assert_eq!(mad_view_rendering(&view), text_view_rendering(md, &area, &skin, 2));
+        view.try_scroll_lines(100);
+        let max_scroll = view.scroll;
+        assert!(max_scroll > 2);
+        assert_eq!(mad_view_rendering(&view), text_view_rendering(md, &area, &skin, max_scroll));
+        // a different width: the lines are wrapped again
+        let area = Area::new(0, 0, 30, 5);
+        view.resize(&area);
+        assert_eq!(mad_view_rendering(&view), text_view_rendering(md, &area, &skin, 0));
+        // a height for which there's no scrollbar
+        let area = Area::new(0, 0, 30, 20);
+        view.resize(&area);
+        assert_eq!(mad_view_rendering(&view), text_view_rendering(md, &area, &skin, 0));
+        // the skin's changes are applied after an invalidation
+        view.skin = MadSkin::no_style();
+        view.invalidate();
+        assert_eq!(mad_view_rendering(&view), text_view_rendering(md, &area, &view.skin, 0));
+    }
+
+    #[test]
+    fn test_slice_columns() {
+        use super::slice_columns;
+        assert_eq!(slice_columns("abcdef", 2, 3), ("cde".to_string(), 3));
+        assert_eq!(slice_columns("abc", 1, 5), ("bc".to_string(), 2));
+        assert_eq!(slice_columns("abc", 4, 5), ("".to_string(), 0));
+        // escape sequences are kept
+        assert_eq!(
+            slice_columns("a\u{1b}[1mbc\u{1b}[0md", 2, 2),
+            ("\u{1b}[1mc\u{1b}[0md".to_string(), 2),
+        );
+        // wide chars cut by an edge are replaced with spaces
+        assert_eq!(slice_columns("日本語", 1, 4), (" 本 ".to_string(), 4));
+    }
+
+    #[test]
+    fn test_wide_tables() {
+        let md = "|abcd|efgh|ijkl|\n|-|-|-|\n|1|2|3|\n|-|-|-|";
+        let mut skin = MadSkin::no_style();
+        skin.scrollbar.track = StyledChar::nude('.');
+        skin.scrollbar.thumb = StyledChar::nude('#');
+        let area = Area::new(0, 0, 10, 5);
+        let mut view = MadView::from(md.to_string(), area, skin);
+        view.set_wide_tables(true);
+        let rendering = |view: &MadView| String::from_utf8(mad_view_rendering(view)).unwrap();
+        assert_eq!(
+            rendering(&view),
+            "\u{1b}[1;1H│abcd│efgh\u{1b}[2;1H├────┼────\u{1b}[3;1H│1   │2   \
+            \u{1b}[4;1H└────┴────\u{1b}[5;1H#######...",
+        );
+        assert!(view.scroll_right(3));
+        assert_eq!(
+            rendering(&view),
+            "\u{1b}[1;1Hcd│efgh│ij\u{1b}[2;1H──┼────┼──\u{1b}[3;1H  │2   │3 \
+            \u{1b}[4;1H──┴────┴──\u{1b}[5;1H.#######..",
+        );
+        // the horizontal scroll can't go past the end
+        assert!(view.scroll_right(100));
+        assert_eq!(view.h_scroll, 6);
+        assert!(!view.scroll_right(1));
+        assert!(view.scroll_left(100));
+        assert_eq!(view.h_scroll, 0);
+        assert!(view.apply_click_event(9, 4));
+        assert_eq!(view.h_scroll, 6);
+        // without wide tables, the table is fitted
+        view.set_wide_tables(false);
+        assert!(!view.scroll_right(1));
+    }
+
+    #[test]
+    fn test_link_selection() {
+        let md = "see [the doc](u)\n\nfiller\n\nand [there](v)";
+        let mut skin = MadSkin::no_style();
+        skin.set_hyperlinks(HyperlinkMode::Text);
+        skin.scrollbar.track = StyledChar::nude('.');
+        skin.scrollbar.thumb = StyledChar::nude('#');
+        let area = Area::new(0, 0, 10, 2);
+        let mut view = MadView::from(md.to_string(), area, skin);
+        let rendering = |view: &MadView| String::from_utf8(mad_view_rendering(view)).unwrap();
+        assert_eq!(view.selected_link_url(), None);
+        assert!(view.select_next_link());
+        assert_eq!(view.selected_link_url(), Some("u"));
+        assert_eq!(
+            rendering(&view),
+            "\u{1b}[1;1Hsee      #\u{1b}[2;1H\u{1b}[7mthe doc\u{1b}[0m  .",
+        );
+        // the next link is scrolled into view
+        assert!(view.select_next_link());
+        assert_eq!(view.selected_link_url(), Some("v"));
+        assert_eq!(view.scroll, 4);
+        assert_eq!(
+            rendering(&view),
+            "\u{1b}[1;1H         .\u{1b}[2;1Hand \u{1b}[7mthere\u{1b}[0m#",
+        );
+        // selection cycles
+        assert!(view.select_next_link());
+        assert_eq!(view.selected_link_url(), Some("u"));
+        assert_eq!(view.scroll, 1);
+        assert!(view.select_prev_link());
+        assert_eq!(view.selected_link_url(), Some("v"));
+        view.unselect_link();
+        assert_eq!(view.selected_link_url(), None);
+        assert!(!rendering(&view).contains("\u{1b}[7m"));
+        let mut view = MadView::from("no link".to_string(), Area::new(0, 0, 10, 2), MadSkin::no_style());
+        assert!(!view.select_next_link());
+    }
+
+    #[test]
+    fn test_scroll_to_heading() {
+        let md = "# Title\nsome text\n## A header long enough to be wrapped\n\ntext\n\n## Usage\n\n\nend";
+        let area = Area::new(0, 0, 12, 3);
+        let mut view = MadView::from(md.to_string(), area, MadSkin::no_style());
+        assert!(view.scroll_to_heading(1));
+        assert_eq!(view.scroll, 2);
+        assert!(view.scroll_to_anchor("#usage"));
+        assert_eq!(view.scroll, 9);
+        // the end of the text can't be scrolled above the bottom
+        let mut view = MadView::from(md.to_string(), Area::new(0, 0, 12, 6), MadSkin::no_style());
+        assert!(view.scroll_to_anchor("usage"));
+        assert_eq!(view.scroll, 7);
+        assert!(view.scroll_to_anchor("title"));
+        assert_eq!(view.scroll, 0);
+        assert!(!view.scroll_to_heading(3));
+        assert!(!view.scroll_to_anchor("nope"));
+        assert_eq!(view.scroll, 0);
+    }
+    #[test]
+    fn test_mad_view_is_send_and_sync() {
+        fn check<T: Send + Sync>() {}
+        check::<MadView>();
     }
 }
@@ -4,12 +4,13 @@ mod list_view;
 mod mad_view;
 mod progress;
 mod text_view;
+mod undo_stack;
 
 pub use {
-    input_field::InputField,
+    input_field::{CursorShape, InputField, InputFieldAction},
     input_field_content::{InputFieldContent, Pos},
     list_view::{ListView, ListViewCell, ListViewColumn},
     mad_view::MadView,
-    progress::ProgressBar,
+    progress::{ProgressBar, Spinner},
     text_view::TextView,
 };
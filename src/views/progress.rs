@@ -1,12 +1,68 @@
-use std::fmt;
+use {
+    crate::{
+        compound_style::CompoundStyle,
+        errors::Result,
+    },
+    crossterm::style::Attribute,
+    std::{
+        fmt,
+        io::Write,
+    },
+    unicode_width::UnicodeWidthChar,
+};
 
 // See https://en.wikipedia.org/wiki/Block_Elements for more fun
 static CHARS: [char; 8] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
 
+/// the glyphs and speed of the animation of an indeterminate bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Spinner {
+    /// the char of the moving block
+    pub block: char,
+    /// the char of the rest of the bar
+    pub empty: char,
+    /// the width of the moving block, in cells
+    pub block_len: usize,
+    /// how many cells the block moves on each tick
+    pub speed: usize,
+}
+
+impl Default for Spinner {
+    fn default() -> Self {
+        Self {
+            block: CHARS[7],
+            empty: ' ',
+            block_len: 3,
+            speed: 1,
+        }
+    }
+}
+
 /// A pixel precise horizontal bar
+///
+/// The bar can display a centered label, either the percentage
+/// (when `show_percentage` is set) or an arbitrary caption.
+///
+/// When progress can't be measured, the bar can be made
+/// indeterminate: it then displays a block going back and forth,
+/// moving each time `tick` is called.
 pub struct ProgressBar {
     pub part: f32,
     pub chars_len: usize,
+    /// whether to display the percentage in the middle of the bar
+    /// (ignored when a label is set or the bar is indeterminate)
+    pub show_percentage: bool,
+    label: Option<String>,
+    spinner: Option<Spinner>,
+    phase: usize,
+}
+
+/// a part of the bar to display with a given style
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BarPart {
+    Bar,
+    LabelOnFilled,
+    LabelOnEmpty,
 }
 
 impl ProgressBar {
@@ -14,22 +70,248 @@ impl ProgressBar {
     /// `part` must be in `[0,1]`.
     /// `chars_len` is the max width of the bar in characters
     pub const fn new(part: f32, chars_len: usize) -> Self {
-        Self { part, chars_len }
+        Self {
+            part,
+            chars_len,
+            show_percentage: false,
+            label: None,
+            spinner: None,
+            phase: 0,
+        }
     }
-}
-
-impl fmt::Display for ProgressBar {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut s = String::new();
+    /// create an indeterminate bar of a given char length,
+    /// with the default spinner
+    pub fn indeterminate(chars_len: usize) -> Self {
+        let mut bar = Self::new(0.0, chars_len);
+        bar.set_spinner(Some(Spinner::default()));
+        bar
+    }
+    /// make the bar indeterminate, with the given animation, or
+    /// make it display `part` again
+    pub fn set_spinner(&mut self, spinner: Option<Spinner>) {
+        self.spinner = spinner;
+        self.phase = 0;
+    }
+    pub const fn is_indeterminate(&self) -> bool {
+        self.spinner.is_some()
+    }
+    /// advance the animation of an indeterminate bar
+    pub fn tick(&mut self) {
+        if let Some(spinner) = &self.spinner {
+            self.phase = self.phase.wrapping_add(spinner.speed);
+        }
+    }
+    /// set a caption to display in the middle of the bar
+    /// instead of the percentage
+    pub fn set_label(&mut self, label: Option<String>) {
+        self.label = label;
+    }
+    fn label(&self) -> Option<String> {
+        match &self.label {
+            Some(label) => Some(label.clone()),
+            None if self.show_percentage && self.spinner.is_none() => {
+                Some(format!("{:.0}%", self.part.clamp(0.0, 1.0) * 100.0))
+            }
+            None => None,
+        }
+    }
+    /// the cells of the bar without label, with whether they're filled.
+    ///
+    /// For a determinate bar the empty cells are only included when
+    /// `full_width` is true.
+    fn bar_cells(&self, full_width: bool) -> Vec<(char, bool)> {
+        if let Some(spinner) = &self.spinner {
+            return self.spinner_cells(spinner);
+        }
+        let mut cells = Vec::with_capacity(self.chars_len);
         let mp = (self.chars_len as f32) * self.part;
         let full = mp as usize;
         for _ in 0..full {
-            s.push(CHARS[7]);
+            cells.push((CHARS[7], true));
         }
         let remain = (mp.fract() * 8.0).round() as usize;
         if remain > 0 {
-            s.push(CHARS[remain - 1]);
+            cells.push((CHARS[remain - 1], remain >= 4));
+        }
+        if full_width {
+            cells.truncate(self.chars_len);
+            cells.resize(self.chars_len, (' ', false));
         }
+        cells
+    }
+    fn spinner_cells(&self, spinner: &Spinner) -> Vec<(char, bool)> {
+        let block_len = spinner.block_len.min(self.chars_len);
+        let range = self.chars_len - block_len;
+        let start = if range == 0 {
+            0
+        } else {
+            // the block bounces at both ends of the bar
+            let p = self.phase % (2 * range);
+            if p <= range { p } else { 2 * range - p }
+        };
+        (0..self.chars_len)
+            .map(|idx| {
+                if idx >= start && idx < start + block_len {
+                    (spinner.block, true)
+                } else {
+                    (spinner.empty, false)
+                }
+            })
+            .collect()
+    }
+    /// the texts to write, each one with the part it belongs to.
+    ///
+    /// The label is truncated so that it doesn't overflow the bar.
+    fn parts(&self) -> Vec<(BarPart, String)> {
+        let mut parts: Vec<(BarPart, String)> = Vec::new();
+        let mut push = |part: BarPart, c: char| match parts.last_mut() {
+            Some((last, s)) if *last == part => s.push(c),
+            _ => parts.push((part, c.to_string())),
+        };
+        let label = match self.label() {
+            Some(label) => label,
+            None => {
+                for (c, _) in self.bar_cells(false) {
+                    push(BarPart::Bar, c);
+                }
+                return parts;
+            }
+        };
+        let mut label_chars = Vec::new();
+        let mut label_width = 0;
+        for c in label.chars() {
+            let w = c.width().unwrap_or(0);
+            if label_width + w > self.chars_len {
+                break;
+            }
+            label_width += w;
+            label_chars.push((c, w));
+        }
+        let label_start = (self.chars_len - label_width) / 2;
+        let cells = self.bar_cells(true);
+        let mut label_chars = label_chars.into_iter();
+        let mut idx = 0;
+        while idx < self.chars_len {
+            let (bar_char, filled) = cells[idx];
+            if idx >= label_start {
+                if let Some((c, w)) = label_chars.next() {
+                    let part = if filled {
+                        BarPart::LabelOnFilled
+                    } else {
+                        BarPart::LabelOnEmpty
+                    };
+                    push(part, c);
+                    idx += w;
+                    continue;
+                }
+            }
+            push(BarPart::Bar, bar_char);
+            idx += 1;
+        }
+        parts
+    }
+    /// write the bar with the given style, whose foreground color
+    /// is the one of the filled part.
+    ///
+    /// The label is written with the colors reversed where it's
+    /// over the filled part, so that it stays readable.
+    pub fn display_on<W: Write>(&self, w: &mut W, style: &CompoundStyle) -> Result<()> {
+        let mut label_on_filled_style = style.clone();
+        label_on_filled_style.add_attr(Attribute::Reverse);
+        for (part, s) in self.parts() {
+            match part {
+                BarPart::LabelOnFilled => label_on_filled_style.queue_str(w, s)?,
+                _ => style.queue_str(w, s)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ProgressBar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s: String = self.parts()
+            .into_iter()
+            .map(|(_, s)| s)
+            .collect();
         f.pad(&s)
     }
 }
+
+#[cfg(test)]
+mod progress_tests {
+
+    use super::*;
+
+    #[test]
+    fn check_unlabelled_bar() {
+        assert_eq!(ProgressBar::new(0.5, 4).to_string(), "██");
+        assert_eq!(ProgressBar::new(0.5, 5).to_string(), "██▌");
+        assert_eq!(ProgressBar::new(0.0, 5).to_string(), "");
+    }
+
+    #[test]
+    fn check_percentage_label() {
+        let mut pb = ProgressBar::new(0.25, 10);
+        pb.show_percentage = true;
+        assert_eq!(pb.to_string(), "██▌25%    ");
+        assert_eq!(
+            pb.parts(),
+            vec![
+                (BarPart::Bar, "██▌".to_string()),
+                (BarPart::LabelOnEmpty, "25%".to_string()),
+                (BarPart::Bar, "    ".to_string()),
+            ],
+        );
+        pb.part = 0.5;
+        assert_eq!(
+            pb.parts()[1..3],
+            [
+                (BarPart::LabelOnFilled, "50".to_string()),
+                (BarPart::LabelOnEmpty, "%".to_string()),
+            ],
+        );
+    }
+
+    #[test]
+    fn check_custom_label() {
+        let mut pb = ProgressBar::new(1.0, 6);
+        pb.show_percentage = true;
+        pb.set_label(Some("日本語です".to_string()));
+        // the label is truncated, wide chars taking 2 cells
+        assert_eq!(pb.to_string(), "日本語");
+        pb.set_label(Some("ab".to_string()));
+        assert_eq!(pb.to_string(), "██ab██");
+        pb.set_label(None);
+        assert_eq!(pb.to_string(), "█100%█");
+    }
+
+    #[test]
+    fn check_indeterminate_bar() {
+        let mut pb = ProgressBar::indeterminate(5);
+        pb.show_percentage = true; // no percentage for an indeterminate bar
+        let frames: Vec<String> = (0..6)
+            .map(|_| {
+                let frame = pb.to_string();
+                pb.tick();
+                frame
+            })
+            .collect();
+        assert_eq!(frames, vec!["███  ", " ███ ", "  ███", " ███ ", "███  ", " ███ "]);
+        pb.set_spinner(Some(Spinner { block: '=', empty: '-', block_len: 1, speed: 2 }));
+        pb.tick();
+        assert_eq!(pb.to_string(), "--=--");
+        pb.set_label(Some("wait".to_string()));
+        assert_eq!(
+            pb.parts(),
+            vec![
+                (BarPart::LabelOnEmpty, "wa".to_string()),
+                (BarPart::LabelOnFilled, "i".to_string()),
+                (BarPart::LabelOnEmpty, "t".to_string()),
+                (BarPart::Bar, "-".to_string()),
+            ],
+        );
+        pb.set_spinner(None);
+        assert!(!pb.is_indeterminate());
+    }
+}
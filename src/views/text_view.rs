@@ -1,8 +1,13 @@
 use {
     crate::{
         area::Area,
-        displayable_line::DisplayableLine,
+        composite::FmtComposite,
+        compound_style::CompoundStyle,
+        displayable_line::{DisplayableLine, OverlaidLine},
         errors::Result,
+        line::FmtLine,
+        skin::Overlay,
+        source_map,
         text::FmtText,
         SPACE_FILLING,
     },
@@ -15,9 +20,12 @@ use {
         },
         queue,
         QueueableCommand,
-        style::Print,
+        style::{Attribute, Print},
+    },
+    std::{
+        io::{stdout, Write},
+        ops::Range,
     },
-    std::io::{stdout, Write},
 };
 
 /// A scrollable text, in a specific area.
@@ -53,6 +61,51 @@ pub struct TextView<'a, 't> {
     text: &'t FmtText<'t, 't>,
     pub scroll: usize, // number of lines hidden at start
     pub show_scrollbar: bool,
+    /// whether searches ignore the case
+    pub search_ignore_case: bool,
+    search_needle: Option<String>,
+    search_line: Option<usize>,
+    /// the style applied over the highlighted matches
+    /// (reverse video when None)
+    pub match_style: Option<CompoundStyle>,
+    highlight_needle: Option<String>,
+}
+
+/// a part, in a line, of a match of a searched needle
+struct MatchPart {
+    line_idx: usize,
+    range: Range<usize>, // in chars
+    first: bool, // whether it's the start of the match
+}
+
+/// return the text of a composite, as searched
+fn composite_text(fc: &FmtComposite<'_>) -> String {
+    (0..fc.composite.compounds.len()).map(|idx| fc.compound_text(idx)).collect()
+}
+
+/// return the text of a line, as searched
+fn line_text(line: &FmtLine<'_>) -> String {
+    match line {
+        FmtLine::Normal(fc) => composite_text(fc),
+        FmtLine::TableRow(row) => row.cells
+            .iter()
+            .map(composite_text)
+            .collect::<Vec<String>>()
+            .join("│"),
+        _ => String::new(),
+    }
+}
+
+const fn is_continuation(line: &FmtLine<'_>) -> bool {
+    matches!(line, FmtLine::Normal(fc) if fc.continuation)
+}
+
+fn fold(c: char, ignore_case: bool) -> char {
+    if ignore_case {
+        c.to_lowercase().next().unwrap_or(c)
+    } else {
+        c
+    }
 }
 
 impl<'a, 't> TextView<'a, 't> {
@@ -64,6 +117,11 @@ impl<'a, 't> TextView<'a, 't> {
             text,
             scroll: 0,
             show_scrollbar: true,
+            search_ignore_case: false,
+            search_needle: None,
+            search_line: None,
+            match_style: None,
+            highlight_needle: None,
         }
     }
 
@@ -97,30 +155,47 @@ impl<'a, 't> TextView<'a, 't> {
     /// display the text in the area, taking the scroll into account.
     pub fn write_on<W: Write>(&self, w: &mut W) -> Result<()> {
         let scrollbar = self.scrollbar();
-        let mut lines = self.text.lines.iter().skip(self.scroll as usize);
+        let mut lines = self.text.lines.iter().skip(self.scroll);
         let mut width = self.area.width as usize;
         if scrollbar.is_some() {
             width -= 1;
         }
+        let scrollbar_style = &self.text.skin.scrollbar;
+        let left_scrollbar = scrollbar.filter(|_| scrollbar_style.is_left());
+        let reverse = CompoundStyle::with_attr(Attribute::Reverse);
+        let match_style = self.match_style.as_ref().unwrap_or(&reverse);
         for j in 0..self.area.height {
             let y = self.area.top + j;
             w.queue(MoveTo(self.area.left, y))?;
+            if let Some(thumb) = left_scrollbar {
+                scrollbar_style.queue_row(w, y, thumb)?;
+            }
             if let Some(line) = lines.next() {
-                let dl = DisplayableLine::new(
-                    self.text.skin,
-                    line,
-                    Some(width),
-                );
-                queue!(w, Print(&dl))?;
+                let ranges = self.highlighted_ranges(self.scroll + j as usize);
+                if ranges.is_empty() {
+                    let dl = DisplayableLine::new(
+                        self.text.skin,
+                        line,
+                        Some(width),
+                    );
+                    queue!(w, Print(&dl))?;
+                } else {
+                    let ol = OverlaidLine {
+                        skin: self.text.skin,
+                        line,
+                        width: Some(width),
+                        overlay: Overlay {
+                            style: match_style,
+                            ranges: &ranges,
+                        },
+                    };
+                    queue!(w, Print(&ol))?;
+                }
             } else {
                 SPACE_FILLING.queue_styled(w, &self.text.skin.paragraph.compound_style, width)?;
             }
-            if let Some((sctop, scbottom)) = scrollbar {
-                if sctop <= y && y <= scbottom {
-                    self.text.skin.scrollbar.thumb.queue(w)?;
-                } else {
-                    self.text.skin.scrollbar.track.queue(w)?;
-                }
+            if let Some(thumb) = scrollbar.filter(|_| left_scrollbar.is_none()) {
+                scrollbar_style.queue_row(w, y, thumb)?;
             }
         }
         Ok(())
@@ -144,11 +219,7 @@ impl<'a, 't> TextView<'a, 't> {
     pub fn try_scroll_lines(&mut self, lines_count: i32) {
         if lines_count < 0 {
             let lines_count = -lines_count as usize;
-                self.scroll = if lines_count >= self.scroll {
-                0
-            } else {
-                self.scroll - lines_count
-            };
+            self.scroll = self.scroll.saturating_sub(lines_count);
         } else {
             self.set_scroll(self.scroll + lines_count as usize);
         }
@@ -207,6 +278,202 @@ impl<'a, 't> TextView<'a, 't> {
         }
     }
 
+    /// return the range of the lines coming from the wrapping
+    /// of the same source line than the given one
+    fn wrapped_lines(&self, line_idx: usize) -> Range<usize> {
+        let lines = &self.text.lines;
+        let mut start = line_idx;
+        while start > 0 && is_continuation(&lines[start]) {
+            start -= 1;
+        }
+        let mut end = line_idx + 1;
+        while end < lines.len() && is_continuation(&lines[end]) {
+            end += 1;
+        }
+        start..end
+    }
+
+    /// find the matches of the needle in the lines coming from the
+    /// wrapping of the same source line than the given one.
+    ///
+    /// Wrapped lines are searched as if joined with a space, so
+    /// that a match may be made of parts in several lines.
+    fn find_matches(&self, line_idx: usize, needle: &str) -> Vec<MatchPart> {
+        let ignore_case = self.search_ignore_case;
+        let lines = self.wrapped_lines(line_idx);
+        let mut chars = Vec::new();
+        let mut starts = Vec::new(); // where lines start in chars
+        let mut ends = Vec::new();
+        for idx in lines.clone() {
+            let text = line_text(&self.text.lines[idx]);
+            if idx > lines.start {
+                // the space removed at wrapping, if any
+                let apart = chars.last().map_or(false, |c: &char| c.is_whitespace())
+                    || text.starts_with(char::is_whitespace);
+                if !apart {
+                    chars.push(' ');
+                }
+            }
+            starts.push(chars.len());
+            chars.extend(text.chars().map(|c| fold(c, ignore_case)));
+            ends.push(chars.len());
+        }
+        let needle: Vec<char> = needle.chars().map(|c| fold(c, ignore_case)).collect();
+        let mut parts = Vec::new();
+        if needle.is_empty() || needle.len() > chars.len() {
+            return parts;
+        }
+        for start in 0..=chars.len() - needle.len() {
+            if chars[start..start + needle.len()] != needle[..] {
+                continue;
+            }
+            let end = start + needle.len();
+            for (i, &line_start) in starts.iter().enumerate() {
+                let line_end = ends[i];
+                if start < line_end && end > line_start {
+                    parts.push(MatchPart {
+                        line_idx: lines.start + i,
+                        range: start.max(line_start) - line_start..end.min(line_end) - line_start,
+                        first: start >= line_start,
+                    });
+                }
+            }
+        }
+        parts
+    }
+
+    /// tell whether a match of the needle starts in the line
+    /// at the given index
+    fn line_matches(&self, line_idx: usize, needle: &str) -> bool {
+        self.find_matches(line_idx, needle)
+            .iter()
+            .any(|part| part.line_idx == line_idx && part.first)
+    }
+
+    /// set the needle whose matches must be highlighted with the
+    /// `match_style`, or remove the highlighting with `None`
+    pub fn set_search_highlight(&mut self, needle: Option<String>) {
+        self.highlight_needle = needle;
+    }
+
+    /// return the ranges of chars of the line which must be highlighted
+    fn highlighted_ranges(&self, line_idx: usize) -> Vec<Range<usize>> {
+        match &self.highlight_needle {
+            Some(needle) => self.find_matches(line_idx, needle)
+                .into_iter()
+                .filter(|part| part.line_idx == line_idx)
+                .map(|part| part.range)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// find the line of the next (or previous) match, starting after
+    /// (or before) the given line, cycling through the text
+    fn find_match(&self, from: Option<usize>, forward: bool) -> Option<usize> {
+        let needle = self.search_needle.as_ref()?;
+        let count = self.content_height();
+        if needle.is_empty() || count == 0 {
+            return None;
+        }
+        // the search starts just after (or before) this line
+        let base = from.unwrap_or(if forward { count - 1 } else { 0 });
+        (1..=count)
+            .map(|i| {
+                if forward {
+                    (base + i) % count
+                } else {
+                    (base + count - i) % count
+                }
+            })
+            .find(|&line_idx| self.line_matches(line_idx, needle))
+    }
+
+    /// scroll, if necessary, so that the line at the given index is visible
+    pub fn show_line(&mut self, line_idx: usize) {
+        let page_height = self.area.height as usize;
+        if line_idx < self.scroll || line_idx >= self.scroll + page_height {
+            self.set_scroll(line_idx);
+        }
+    }
+
+    fn go_to_match(&mut self, line_idx: Option<usize>) -> Option<usize> {
+        self.search_line = line_idx;
+        if let Some(line_idx) = line_idx {
+            self.show_line(line_idx);
+        }
+        line_idx
+    }
+
+    /// search the first line containing the needle, scroll to have it
+    /// visible, and return its index
+    pub fn search(&mut self, needle: &str) -> Option<usize> {
+        self.search_needle = Some(needle.to_string());
+        let line_idx = self.find_match(None, true);
+        self.go_to_match(line_idx)
+    }
+
+    /// go to the next line matching the last search, cycling
+    /// to the start of the text when at its end
+    pub fn search_next(&mut self) -> Option<usize> {
+        let line_idx = self.find_match(self.search_line, true);
+        self.go_to_match(line_idx)
+    }
+
+    /// go to the previous line matching the last search, cycling
+    /// to the end of the text when at its start
+    pub fn search_prev(&mut self) -> Option<usize> {
+        let line_idx = self.find_match(self.search_line, false);
+        self.go_to_match(line_idx)
+    }
+
+    /// Apply a click at the given screen position: when it's on the
+    /// scrollbar, scroll so that the clicked row of the track matches
+    /// the new position of the thumb.
+    ///
+    /// Return true when the click was on the scrollbar, false when
+    /// it must be handled elsewhere.
+    pub fn apply_click_event(&mut self, x: u16, y: u16) -> bool {
+        if self.scrollbar().is_none() {
+            return false;
+        }
+        let area = self.area;
+        let scrollbar_x = if self.text.skin.scrollbar.is_left() {
+            area.left
+        } else {
+            area.left + area.width - 1
+        };
+        if x != scrollbar_x || y < area.top || y >= area.top + area.height {
+            return false;
+        }
+        let row = (y - area.top) as usize;
+        self.set_scroll(row * self.content_height() / area.height as usize);
+        true
+    }
+
+    /// Return the approximate byte offset, in the markdown the text
+    /// was built from, of what's displayed at the given screen position.
+    ///
+    /// This can be used to find what was clicked, for example to
+    /// follow a reference.
+    ///
+    /// Return None when the position isn't on a line with some
+    /// content of the markdown (for example on a rule or a scrollbar)
+    pub fn source_offset_at(&self, markdown: &str, x: u16, y: u16) -> Option<usize> {
+        let area = self.area;
+        if y < area.top || y >= area.top + area.height {
+            return None;
+        }
+        let scrollbar = self.scrollbar();
+        let text_left = area.left + u16::from(scrollbar.is_some() && self.text.skin.scrollbar.is_left());
+        let width = area.width as usize - usize::from(scrollbar.is_some());
+        if x < text_left || x >= text_left + width as u16 {
+            return None;
+        }
+        let line = self.text.lines.get(self.scroll + (y - area.top) as usize)?;
+        source_map::source_offset_at(self.text.skin, line, Some(width), markdown, (x - text_left) as usize)
+    }
+
     /// Apply an event being a key: page_up, page_down, up and down.
     ///
     /// Return true when the event led to a change, false when it
@@ -224,3 +491,106 @@ impl<'a, 't> TextView<'a, 't> {
         }
     }
 }
+
+#[cfg(test)]
+mod text_view_search_tests {
+
+    use crate::*;
+
+    #[test]
+    fn test_search() {
+        let skin = MadSkin::no_style();
+        let md = "# Title\nsome *Needle*\nnothing\nnothing\nnothing\nnothing\n|a|needle|\n";
+        let area = Area::new(0, 0, 20, 3);
+        let text = skin.area_text(md, &area);
+        let mut view = TextView::from(&area, &text);
+        assert_eq!(view.search("needle"), Some(6));
+        assert_eq!(view.scroll, 4);
+        assert_eq!(view.search_next(), Some(6)); // there's only one match
+        view.search_ignore_case = true;
+        assert_eq!(view.search_next(), Some(1));
+        assert_eq!(view.scroll, 1);
+        assert_eq!(view.search_next(), Some(6));
+        assert_eq!(view.search_prev(), Some(1));
+        assert_eq!(view.search_prev(), Some(6));
+        assert_eq!(view.search("absent"), None);
+        assert_eq!(view.search_next(), None);
+    }
+
+    #[test]
+    fn test_matches_over_wrapped_lines() {
+        let skin = MadSkin::no_style();
+        let md = "one two three four";
+        let area = Area::new(0, 0, 12, 3);
+        let text = skin.area_text(md, &area);
+        assert_eq!(text.lines.len(), 2); // "one two " and "three four"
+        let mut view = TextView::from(&area, &text);
+        assert_eq!(view.search("two thr"), Some(0));
+        view.set_search_highlight(Some("two thr".to_string()));
+        assert_eq!(view.highlighted_ranges(0), vec![4..8]);
+        assert_eq!(view.highlighted_ranges(1), vec![0..3]);
+        view.set_search_highlight(Some("o".to_string()));
+        assert_eq!(view.highlighted_ranges(0), vec![0..1, 6..7]);
+        assert_eq!(view.highlighted_ranges(1), vec![7..8]);
+        let mut w = Vec::new();
+        view.write_on(&mut w).unwrap();
+        let written = String::from_utf8(w).unwrap();
+        assert_eq!(written.matches("\u{1b}[7m").count(), 3); // reverse attribute
+    }
+
+    #[test]
+    fn test_match_covering_a_compound() {
+        let skin = MadSkin::no_style();
+        let md = "a *bc* d";
+        let area = Area::new(0, 0, 10, 1);
+        let text = skin.area_text(md, &area);
+        let mut view = TextView::from(&area, &text);
+        view.set_search_highlight(Some("bc".to_string()));
+        let mut w = Vec::new();
+        view.write_on(&mut w).unwrap();
+        let written = String::from_utf8(w).unwrap();
+        assert!(written.contains("\u{1b}[7mbc\u{1b}[0m"));
+    }
+
+    #[test]
+    fn test_scrollbar_click() {
+        let skin = MadSkin::no_style();
+        let md = (0..20).map(|i| format!("line {}\n", i)).collect::<String>();
+        let area = Area::new(1, 2, 10, 5);
+        let text = skin.area_text(&md, &area);
+        let mut view = TextView::from(&area, &text);
+        assert!(!view.apply_click_event(9, 3)); // in the text
+        assert!(!view.apply_click_event(10, 1)); // above the area
+        assert!(view.apply_click_event(10, 4));
+        assert_eq!(view.scroll, 8);
+        // the clicked row is the top of the thumb
+        assert_eq!(view.scrollbar().map(|(top, _)| top), Some(4));
+        assert!(view.apply_click_event(10, 6));
+        assert_eq!(view.scroll, 15); // the end
+        assert!(view.apply_click_event(10, 2));
+        assert_eq!(view.scroll, 0);
+        view.show_scrollbar = false;
+        assert!(!view.apply_click_event(10, 4));
+        assert_eq!(view.scroll, 0);
+    }
+
+    #[test]
+    fn test_left_scrollbar() {
+        let mut skin = MadSkin::no_style();
+        skin.scrollbar.track = StyledChar::nude('.');
+        skin.scrollbar.thumb = StyledChar::nude('#');
+        skin.scrollbar.set_side(ScrollBarSide::Left);
+        let area = Area::new(0, 0, 5, 3);
+        let text = skin.area_text("a\nb\nc\nd\ne\nf", &area);
+        let mut view = TextView::from(&area, &text);
+        let mut w = Vec::new();
+        view.write_on(&mut w).unwrap();
+        assert_eq!(
+            String::from_utf8(w).unwrap(),
+            "\u{1b}[1;1H#a   \u{1b}[2;1H#b   \u{1b}[3;1H.c   ",
+        );
+        assert!(!view.apply_click_event(4, 1));
+        assert!(view.apply_click_event(0, 2));
+        assert_eq!(view.scroll, 3);
+    }
+}
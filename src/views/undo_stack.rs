@@ -0,0 +1,100 @@
+use {
+    super::{InputFieldContent, Pos},
+    std::collections::VecDeque,
+};
+
+const DEFAULT_UNDO_DEPTH: usize = 100;
+
+/// The kind of an edit, used to decide whether it may
+/// be merged with the previous one in the undo history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditKind {
+    /// insertion of a char (not a new line) as if typed
+    CharInsertion,
+    /// any other edit
+    Other,
+}
+
+/// A bounded history of the states of an input field content,
+/// allowing undo and redo.
+#[derive(Debug, Clone)]
+pub struct UndoStack {
+    undos: VecDeque<InputFieldContent>,
+    redos: Vec<InputFieldContent>,
+    depth: usize,
+    /// the position of the cursor after the last char insertion, if
+    /// the last recorded edit was one.
+    /// This is used to coalesce the insertions of a typing run
+    typing_end: Option<Pos>,
+}
+
+impl Default for UndoStack {
+    fn default() -> Self {
+        Self {
+            undos: VecDeque::new(),
+            redos: Vec::new(),
+            depth: DEFAULT_UNDO_DEPTH,
+            typing_end: None,
+        }
+    }
+}
+
+impl UndoStack {
+    /// set the maximal number of undoable steps, removing the
+    /// oldest ones if necessary
+    pub fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+        while self.undos.len() > depth {
+            self.undos.pop_front();
+        }
+        self.redos.truncate(depth);
+    }
+    /// make the recorded states secure (or not), like the
+    /// content they're taken from
+    #[cfg(feature = "secure")]
+    pub fn set_secure(&mut self, secure: bool) {
+        for content in self.undos.iter_mut().chain(self.redos.iter_mut()) {
+            content.set_secure(secure);
+        }
+    }
+    /// record the state before a successful edit whose result is `after`
+    pub fn record(
+        &mut self,
+        before: InputFieldContent,
+        after: &InputFieldContent,
+        kind: EditKind,
+    ) {
+        if self.depth == 0 {
+            return;
+        }
+        let coalesce = kind == EditKind::CharInsertion
+            && self.typing_end == Some(before.cursor_pos());
+        if !coalesce {
+            if self.undos.len() == self.depth {
+                self.undos.pop_front();
+            }
+            self.undos.push_back(before);
+        }
+        self.redos.clear();
+        self.typing_end = match kind {
+            EditKind::CharInsertion => Some(after.cursor_pos()),
+            EditKind::Other => None,
+        };
+    }
+    /// return the state to restore in order to undo the last step,
+    /// storing the current one for a potential redo
+    pub fn undo(&mut self, current: &InputFieldContent) -> Option<InputFieldContent> {
+        self.typing_end = None;
+        let previous = self.undos.pop_back()?;
+        self.redos.push(current.clone());
+        Some(previous)
+    }
+    /// return the state to restore in order to redo the last undone step,
+    /// storing the current one for a potential undo
+    pub fn redo(&mut self, current: &InputFieldContent) -> Option<InputFieldContent> {
+        self.typing_end = None;
+        let next = self.redos.pop()?;
+        self.undos.push_back(current.clone());
+        Some(next)
+    }
+}